@@ -1,19 +1,145 @@
+use std::borrow::Cow;
 use std::io::Write;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use std::thread;
 use parking_lot::{FairMutex, RawFairMutex};
 use parking_lot::lock_api::MutexGuard;
 use config::on_queue_full::On_QueueFull;
-use crate::message::{Message, OutputKind};
-use crate::printing_state::PrintingState;
+use crate::message::{Message, OnComplete, OutputKind};
+use crate::printing_state::{PrintingState, StateGuard};
+use crate::render;
 use crate::config;
 use crate::config::on_max_retries_reached::On_MaxRetriesReached;
 use crate::config::on_queue_printing_fail::On_QueuePrintingFail;
+use crate::config::drop_channel::DropReason;
+use crate::config::flush_strategy::FlushStrategy;
+use crate::config::emit_order::EmitOrder;
+use crate::config::on_post_shutdown::On_PostShutdown;
+#[cfg(not(feature = "single-thread"))]
+use crate::config::on_spawn_failure::On_SpawnFailure;
 
 /// This is public within crate to allow testing.
 pub(crate) static QUEUE: FairMutex<Vec<Message>> = FairMutex::new(Vec::new());
 
 pub(crate) static STATE: FairMutex<PrintingState> = FairMutex::new(PrintingState::Idle);
 
+/// Locks [STATE], reporting changes to [ON_STATE_CHANGE](config::on_state_change) once released, see [StateGuard].
+fn lock_state() -> StateGuard {
+	return StateGuard::new(STATE.lock());
+}
+
+/// Same as [lock_state()], but returns [None] if [STATE] is already locked.
+fn try_lock_state() -> Option<StateGuard> {
+	return STATE.try_lock().map(StateGuard::new);
+}
+
+/// See [stderr_was_printed()].
+pub(crate) static STDERR_PRINTED: AtomicBool = AtomicBool::new(false);
+
+/// See [pause()].
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// The stream the last message was written to if it was a [progress](print_progress) line, which the next regular message must not overwrite.
+/// 
+/// 1 for [Stdout](OutputKind::Stdout), 2 for [Stderr](OutputKind::Stderr), 0 if the last message wasn't a progress line.
+pub(crate) static LAST_PROGRESS_STREAM: AtomicU8 = AtomicU8::new(0);
+
+/// How many of the most recently seen [dedup keys](Message::with_dedup_key()) are remembered.
+/// 
+/// A message is suppressed if its key is among them, seeing a key again makes it the most recent one.
+pub const DEDUP_WINDOW: usize = 64;
+
+/// Recently seen dedup keys, least recent first.
+pub(crate) static RECENT_DEDUP_KEYS: parking_lot::Mutex<VecDeque<u64>> = parking_lot::Mutex::new(VecDeque::new());
+
+/// See [suppressed_count()].
+pub(crate) static SUPPRESSED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// See [suppressed_diagnostics_count()].
+pub(crate) static SUPPRESSED_DIAGNOSTICS: AtomicUsize = AtomicUsize::new(0);
+
+/// See [dropped_count()].
+pub(crate) static DROPPED_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// When a message was last handed to [comfy_print](crate), in nanoseconds since [monotonic_nanos()]'s epoch, 0 if never, see [flush_if_idle()].
+pub(crate) static LAST_ENQUEUE_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// When the last progress line was let through by [PROGRESS_THROTTLE](config::progress_throttle), 
+/// in nanoseconds since [monotonic_nanos()]'s epoch, 0 if never.
+pub(crate) static LAST_PROGRESS_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Whether [PENDING_PROGRESS] holds an update, so that regular messages can skip locking it.
+static HAS_PENDING_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// The latest progress line held back by [PROGRESS_THROTTLE](config::progress_throttle).
+static PENDING_PROGRESS: parking_lot::Mutex<Option<String>> = parking_lot::Mutex::new(None);
+
+/// Whether [shutdown()] was called, see [ON_POST_SHUTDOWN](config::on_post_shutdown).
+pub(crate) static SHUTDOWN_CALLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether spawning the printing thread failed last time, with [RetryLater](On_SpawnFailure::RetryLater).
+#[cfg(not(feature = "single-thread"))]
+pub(crate) static SPAWN_RETRY_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// How many times in a row the drain retries a write that failed with [WouldBlock](std::io::ErrorKind::WouldBlock), 
+/// without counting towards [MAX_RETRIES](config::max_retries).
+pub const WOULD_BLOCK_RETRIES: usize = 16;
+
+/// How many times the drain retries writing a message that waited longer than [Message::escalate_after()], before giving up on it for this pass.
+pub const ESCALATION_RETRIES: usize = 16;
+
+/// How many spaces each level of [scope()] adds to the indentation of messages.
+pub const SCOPE_INDENT: usize = 2;
+
+/// How many of the most recently written messages are kept for [recent()].
+pub const RECENT_CAPACITY: usize = 64;
+
+/// See [recent()], oldest first.
+pub(crate) static RECENT: parking_lot::Mutex<VecDeque<(OutputKind, String)>> = parking_lot::Mutex::new(VecDeque::new());
+
+/// The sequence number assigned by the next call to [enqueue_seq()].
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+/// See [wait_for_seq()].
+static SEQ_PROGRESS: parking_lot::Mutex<SeqProgress> = parking_lot::Mutex::new(SeqProgress { completed_up_to: 0, completed_ahead: BTreeSet::new() });
+
+/// Notified whenever [SeqProgress::completed_up_to] advances.
+static SEQ_ADVANCED: parking_lot::Condvar = parking_lot::Condvar::new();
+
+/// Messages that would have been written while [DRY_RUN](config::dry_run) was enabled, see [dry_run_output()].
+static DRY_RUN_OUTPUT: parking_lot::Mutex<Vec<(OutputKind, String)>> = parking_lot::Mutex::new(Vec::new());
+
+/// How many messages each subscriber can fall behind before missing messages, see [subscribe()].
+#[cfg(feature = "subscribe")]
+pub const SUBSCRIBER_CAPACITY: usize = 256;
+
+/// See [subscribe()].
+#[cfg(feature = "subscribe")]
+pub(crate) static SUBSCRIBERS: parking_lot::Mutex<Vec<std::sync::mpsc::SyncSender<Message>>> = parking_lot::Mutex::new(Vec::new());
+
+/// Where messages meant for [std::io::stdout] are written instead, [None] means [std::io::stdout] itself, see [swap_stdout_sink()].
+pub(crate) static STDOUT_SINK: parking_lot::Mutex<Option<Box<dyn Write + Send>>> = parking_lot::Mutex::new(None);
+
+/// Completion callbacks of messages that were dropped, waiting for [run_completions()], see [Message::with_on_complete()].
+pub(crate) static PENDING_COMPLETIONS: parking_lot::Mutex<Vec<(OnComplete, std::io::Result<()>)>> = parking_lot::Mutex::new(Vec::new());
+
+thread_local! {
+	/// Messages buffered by [thread_buffer_print()], waiting for [thread_flush()].
+	static THREAD_BUFFER: std::cell::RefCell<Vec<Message>> = const { std::cell::RefCell::new(Vec::new()) };
+	/// Output captured by [capture()] on this thread, [None] when not capturing.
+	static CAPTURED: std::cell::RefCell<Option<Vec<(OutputKind, String)>>> = const { std::cell::RefCell::new(None) };
+	/// How many [ScopeGuard]s are alive on this thread.
+	static SCOPE_DEPTH: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+struct SeqProgress {
+	/// Every sequence number up to (and including) this one is complete.
+	completed_up_to: u64,
+	/// Completed sequence numbers after [SeqProgress::completed_up_to], waiting for the ones before them.
+	completed_ahead: BTreeSet<u64>,
+}
+
 /// Main function for printing user messages.
 /// 
 /// # Arguments 
@@ -42,287 +168,3772 @@ pub(crate) static STATE: FairMutex<PrintingState> = FairMutex::new(PrintingState
 /// comfy_print::async_impl::comfy_print_async(msg);
 /// 
 /// ```
-#[allow(unused_must_use)]
 pub fn comfy_print_async(msg: Message) {
+	print_or_queue(msg);
+	run_completions();
+}
+
+#[allow(unused_must_use)]
+fn print_or_queue(msg: Message) {
+	if config::enabled::get() == false {
+		return;
+	}
+	
+	let Ok(mut msg) = prepare(msg)
+			else { return; };
+	
+	mark_enqueued();
+	
 	let mut queue_guard = QUEUE.lock();
 	let queue_len = queue_guard.len();
 	
-	if queue_len == 0 {
+	if queue_len == 0 && is_paused() == false && config::always_async::get() == false && config::serialize_startup::is_active() == false {
 		drop(queue_guard);
 		
-		try_write(&msg).inspect_err(
+		let write_result = try_write(&msg, false);
+		if write_result.is_ok() {
+			msg.set_written();
+		}
+		
+		write_result.inspect_err(
 			|err| {
-				if config::max_queue_length::get() == 0 {
+				if config::max_queue_length::effective() == 0 {
+					notify_dropped(msg.output_kind(), DropReason::QueueDisabled);
 					return;
 				}
 
+				let is_diagnostic = msg.is_diagnostic();
 				let mut queue_guard = QUEUE.lock();
 				queue_guard.insert(0, msg);
-				owned_try_insert_write_err(&mut queue_guard, err, "comfy_print::async_impl::comfy_print_async(): Failed to print message, creating queue...");
+				config::queue_warn_threshold::observe(queue_guard.len());
+				
+				// Failing to print a diagnostic must not generate diagnostics about diagnostics.
+				if is_diagnostic == false {
+					owned_try_insert_write_err(&mut queue_guard, err, "comfy_print::async_impl::comfy_print_async(): Failed to print message, creating queue...");
+				}
+				
 				drop(queue_guard);
 				
 				check_state();
 			});
 	} 
 	else {
-		if queue_len < config::max_queue_length::get(){
-			queue_guard.push(msg);
-		} else if On_QueueFull::KeepNewest == config::on_queue_full::get() {
-			queue_guard.remove(0);
-			queue_guard.push(msg);
-		}
-		
+		push_capped(&mut queue_guard, msg);
 		drop(queue_guard);
 		
 		check_state();
 	}
 
-	return;
-	
-	/// WARNING: May lock [STATE], then may lock [QUEUE].
-	fn check_state() {
-		let Some(mut state_guard) = STATE.try_lock()
-				else { return; };
-
-		if state_guard.is_busy() { // We already pushed our msg to the queue and there's already someone else printing it, so we can return.
-			drop(state_guard);
-			return;
-		}
+}
 
-		let thread_result = thread::Builder::new().spawn(start_printing_queue);
+/// Why [prepare()] didn't hand a message back.
+enum Skipped {
+	/// Discarded by [STDERR_VERBOSITY](config::stderr_verbosity), which doesn't count as dropped.
+	Filtered,
+	/// Suppressed as a duplicate (see [Message::with_dedup_key()]), or dropped by [ON_POST_SHUTDOWN](config::on_post_shutdown).
+	Dropped,
+	/// Handed to the innermost [capture()] of the calling thread, or written by [DirectWrite](On_PostShutdown::DirectWrite).
+	Consumed,
+}
 
-		match thread_result {
-			Ok(handle) => {
-				*state_guard = PrintingState::Threaded(handle);
-				drop(state_guard);
+/// The steps shared by every way of printing a message ([comfy_print_async()], [println_slice()], [enqueue_many()] and [thread_flush()]), 
+/// before it's written or queued: [STDERR_VERBOSITY](config::stderr_verbosity), [MIDDLEWARE](config::middleware), 
+/// [ROUTES](config::routes), deduplication, [capture()] and [ON_POST_SHUTDOWN](config::on_post_shutdown).
+/// 
+/// Must not be called while holding the lock of the [QUEUE]: middleware, route predicates and the pending progress line may print.
+fn prepare(msg: Message) -> Result<Message, Skipped> {
+	if config::stderr_verbosity::permits(&msg) == false {
+		return Err(Skipped::Filtered);
+	}
+	
+	if msg.is_progress() == false {
+		print_pending_progress();
+	}
+	
+	let mut msg = 
+		if msg.is_verbatim() {
+			msg
+		} else {
+			config::middleware::apply(msg)
+		};
+	
+	let route = config::routes::resolve(&msg);
+	msg.set_route(route);
+	
+	if let Some(key) = msg.dedup_key() {
+		if was_recently_seen(key) {
+			saturating_increment(&SUPPRESSED_COUNT);
+			return Err(Skipped::Dropped);
+		}
+	}
+	
+	if CAPTURED.with_borrow(Option::is_some) {
+		capture_message(msg);
+		return Err(Skipped::Consumed);
+	}
+	
+	if SHUTDOWN_CALLED.load(Ordering::Relaxed) {
+		match config::on_post_shutdown::get() {
+			On_PostShutdown::Restart => {}
+			On_PostShutdown::Drop => {
+				notify_dropped(msg.output_kind(), DropReason::PostShutdown);
+				return Err(Skipped::Dropped);
 			}
-			Err(err) => {
-				*state_guard = PrintingState::Synchronous;
-				drop(state_guard);
-
-				try_insert_write_err(&err, "`comfy_print::async_impl::check_state()`: Failed to create a thread to print the queue.");
-
-				start_printing_queue();
-
-				let mut state_guard = STATE.lock();
-				*state_guard = PrintingState::Idle;
-				drop(state_guard);
+			On_PostShutdown::DirectWrite => {
+				return match try_write(&msg, false) {
+					Ok(()) => {
+						msg.set_written();
+						Err(Skipped::Consumed)
+					}
+					Err(_) => {
+						notify_dropped(msg.output_kind(), DropReason::PostShutdown);
+						Err(Skipped::Dropped)
+					}
+				};
+			}
+			On_PostShutdown::Panic => {
+				panic!("`comfy_print::async_impl::comfy_print_async()`: Printed after shutdown: {}", msg.str());
 			}
 		}
 	}
+	
+	return Ok(msg);
 }
 
-fn start_printing_queue() {
-	print_until_empty(config::max_retries::get(), 0);
+/// Stores `callback` to be invoked by [run_completions()]. 
+/// 
+/// Messages may be dropped while holding the lock of the [QUEUE], where invoking the callback could deadlock if it prints.
+/// 
+/// WARNING: Will lock [PENDING_COMPLETIONS].
+pub(crate) fn defer_completion(callback: OnComplete, result: std::io::Result<()>) {
+	PENDING_COMPLETIONS.lock().push((callback, result));
 }
 
-/// WARNING: Will lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr].
-fn print_until_empty(max_retries: usize, retries: usize) {
-	let mut queue_guard = QUEUE.lock();
-	
-	if queue_guard.is_empty() {
-		queue_guard.shrink_to_fit();
-		drop(queue_guard);
+/// Invokes every callback stored by [defer_completion()], in order. Must not be called while holding the lock of the [QUEUE].
+/// 
+/// WARNING: Will lock [PENDING_COMPLETIONS].
+fn run_completions() {
+	let mut pending_guard = PENDING_COMPLETIONS.lock();
+	if pending_guard.is_empty() {
+		drop(pending_guard);
 		return;
 	}
 	
-	let msg = queue_guard.remove(0);
-	drop(queue_guard); // unlock the queue before blocking stdout/err
+	let pending = std::mem::take(&mut *pending_guard);
+	drop(pending_guard);
 	
-	match try_write(&msg) {
-		Ok(_) => {
-			print_until_empty(max_retries, retries);
-		},
-		Err(err) => match config::on_queue_printing_fail::get() {
-			On_QueuePrintingFail::TryUntilMaxRetries => {
-				reinsert_message(msg, err);
+	for (callback, result) in pending {
+		callback(result);
+	}
+}
 
-				if retries < max_retries {
-					print_until_empty(max_retries, retries + 1);
-				} else {
-					on_max_retries();
-				}
-			}
-			On_QueuePrintingFail::Return => {
-				reinsert_message(msg, err);
-				return;
-			}
-		}
+/// Prints each of `lines` followed by a newline to `kind`, queueing all of them under a single lock of the queue.
+/// 
+/// - Cheaper than calling [comfy_print_async()] for each line when printing a lot of output at once.
+/// - The lines are always queued, even if the queue is empty, then printed by the thread responsible for printing the queue.
+/// - No other message can be queued in between the lines.
+/// - [MAX_QUEUE_LENGTH](config::max_queue_length), [ON_QUEUE_FULL](config::on_queue_full), [MIDDLEWARE](config::middleware) 
+///   and [ROUTES](config::routes) apply to each line.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [STATE].
+pub fn println_slice(lines: &[&str], kind: OutputKind) {
+	if config::enabled::get() == false {
+		return;
 	}
 	
-	return;
-
-	/// WARNING: Will lock [QUEUE].
-	fn reinsert_message(msg: Message, err: std::io::Error) {
-		let mut queue_guard = QUEUE.lock();
+	// Middleware may print, so it must run before locking the queue.
+	let messages: Vec<Message> = lines.iter()
+		.map(|line| match kind {
+			OutputKind::Stdout => Message::standard_ln(*line),
+			OutputKind::Stderr => Message::error_ln(*line),
+		})
+		.filter_map(|msg| prepare(msg).ok())
+		.collect();
+	
+	let mut queue_guard = QUEUE.lock();
+	for msg in messages {
+		push_capped(&mut queue_guard, msg);
+	}
+	
+	drop(queue_guard);
+	
+	check_state();
+	run_completions();
+}
 
-		// This can happen if another thread pushed a message to the queue while we were printing the current one.
-		if queue_guard.len() < config::max_queue_length::get() {
-			queue_guard.insert(0, msg);
-		} else if let On_QueueFull::KeepOldest = config::on_queue_full::get() {
-			queue_guard.pop();
-			queue_guard.insert(0, msg);
+/// Queues each of `msgs` under a single lock of the queue, then prints them like [println_slice()].
+/// 
+/// - [ON_QUEUE_FULL](config::on_queue_full), [MIDDLEWARE](config::middleware) and [ROUTES](config::routes) apply to each message.
+/// - Cheaper than calling [comfy_print_async()] for each message, and reports how many made it.
+/// 
+/// # Returns
+/// 
+/// `(accepted, dropped)`: how many of `msgs` were kept (in the queue, [spilled](On_QueueFull::Spill), [captured](capture()) 
+/// or written by [DirectWrite](On_PostShutdown::DirectWrite)), and how many were dropped.
+/// 
+/// - Messages of `msgs` evicted by [KeepNewest](On_QueueFull::KeepNewest) to make room for later ones count as dropped. 
+///   Messages queued before are only counted by [dropped_count()].
+/// - If [ENABLED](config::enabled) is false, every message is dropped.
+/// - Messages discarded by [STDERR_VERBOSITY](config::stderr_verbosity) are neither accepted nor dropped, 
+///   like [comfy_print_async()] doesn't report them to [DROP_CHANNEL](config::drop_channel).
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [STATE].
+pub fn enqueue_many(msgs: Vec<Message>) -> (usize, usize) {
+	if config::enabled::get() == false {
+		return (0, msgs.len());
+	}
+	
+	let mut accepted = 0;
+	let mut dropped = 0;
+	let mut messages = Vec::with_capacity(msgs.len());
+	
+	// Middleware may print, so it must run before locking the queue.
+	for msg in msgs {
+		match prepare(msg) {
+			Ok(msg) => messages.push(msg),
+			Err(Skipped::Consumed) => accepted += 1,
+			Err(Skipped::Dropped) => dropped += 1,
+			Err(Skipped::Filtered) => {}
 		}
-
-		owned_try_insert_write_err(&mut queue_guard, &err, "`comfy_print::async_impl::print_until_empty()`: Failed to print first message in queue.");
-		drop(queue_guard);
 	}
-
-	/// WARNING: May lock [QUEUE].
-	fn on_max_retries() {
-		match config::on_max_retries_reached::get() {
-			On_MaxRetriesReached::Return => {
-				return;
-			},
-			On_MaxRetriesReached::WriteToDisk => {
-				let Ok(mut file) = config::log_io_path::get_file()
-						else { return; };
-
-				let mut queue_guard = QUEUE.lock();
-
-				while !queue_guard.is_empty() {
-					let msg = &queue_guard[0];
-					let write_result = write!(file, "{}", msg);
-
-					match write_result {
-						Ok(_) => {
-							queue_guard.remove(0);
-							continue;
-						},
-						Err(err) => {
-							owned_try_insert_write_err(&mut queue_guard, &err, "`comfy_print::async_impl::on_max_retries_reached()`: Failed to write to log file.");
-							break;
-						}
-					}
-				}
-
-				queue_guard.shrink_to_fit();
-				drop(queue_guard);
-				drop(file);
+	
+	let mut queue_guard = QUEUE.lock();
+	
+	// The queue holds these messages from before the batch, followed by the ones of the batch still in it.
+	let mut preexisting = queue_guard.len();
+	
+	for msg in messages {
+		match push_capped(&mut queue_guard, msg) {
+			Pushed::Kept { evicted: None } => accepted += 1,
+			Pushed::Kept { evicted: Some(index) } if index < preexisting => {
+				preexisting -= 1;
+				accepted += 1;
 			}
+			// A message of this batch made room for this one, so only one of them was kept.
+			Pushed::Kept { evicted: Some(_) } => dropped += 1,
+			Pushed::Dropped => dropped += 1,
 		}
 	}
+	
+	drop(queue_guard);
+	
+	check_state();
+	run_completions();
+	return (accepted, dropped);
 }
 
-#[cfg(not(test))]
-/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
-fn try_write(msg: &Message) -> std::io::Result<()> { 
-	match msg.output_kind() {
-		OutputKind::Stdout => {
-			let mut stdout = std::io::stdout().lock();
-			write!(stdout, "{}", msg)?;
-			stdout.flush()?;
-			Ok(())
-		}
-		OutputKind::Stderr => {
-			let mut stderr = std::io::stderr().lock();
-			write!(stderr, "{}", msg)?;
-			stderr.flush()?;
-			Ok(())
-		}
+/// Appends `s` to a buffer local to the calling thread, without touching the [QUEUE].
+/// 
+/// The buffered messages are only queued (then printed) on [thread_flush()], all at once under a single lock of the queue, 
+/// which avoids contention when many threads print a lot.
+/// 
+/// - Unflushed messages are lost when the thread exits, call [thread_flush()] before returning from the thread.
+/// - [MIDDLEWARE](config::middleware), [ROUTES](config::routes), [MAX_QUEUE_LENGTH](config::max_queue_length) and 
+///   [ON_QUEUE_FULL](config::on_queue_full) apply when flushing.
+pub fn thread_buffer_print(s: &str, kind: OutputKind) {
+	if config::enabled::get() == false {
+		return;
 	}
+	
+	let msg = match kind {
+		OutputKind::Stdout => Message::standard(s),
+		OutputKind::Stderr => Message::error(s),
+	};
+	
+	THREAD_BUFFER.with_borrow_mut(|buffer| buffer.push(msg));
 }
 
-#[cfg(test)]
-/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
-fn try_write(msg: &Message) -> std::io::Result<()> {
-	use std::sync::atomic::Ordering;
+/// Moves every message buffered by [thread_buffer_print()] on the calling thread into the [QUEUE], under a single lock.
+/// 
+/// Does nothing if the buffer is empty.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [STATE].
+pub fn thread_flush() {
+	let messages = THREAD_BUFFER.with_borrow_mut(std::mem::take);
+	if messages.is_empty() {
+		return;
+	}
 	
-	if tests::TOGGLE_WRITE_FAIL.load(Ordering::Relaxed) == true {
-		return Err(std::io::Error::new(std::io::ErrorKind::Other, tests::FORCE_WRITE_FAIL_MSG));
+	// Middleware may print, so it must run outside the buffer's borrow and before locking the queue.
+	let messages: Vec<Message> = messages.into_iter()
+		.filter_map(|msg| prepare(msg).ok())
+		.collect();
+	
+	let mut queue_guard = QUEUE.lock();
+	for msg in messages {
+		push_capped(&mut queue_guard, msg);
 	}
 	
-	let force_write_fail_result = tests::FORCE_WRITE_FAIL
-			.compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed);
+	drop(queue_guard);
+	
+	check_state();
+	run_completions();
+}
 
-	if let Ok(_) = force_write_fail_result {
-		return Err(std::io::Error::new(std::io::ErrorKind::Other, tests::FORCE_WRITE_FAIL_MSG));
+/// Runs `f`, capturing every message printed on the calling thread meanwhile instead of writing it, 
+/// e.g. to inspect the output of a function in tests.
+/// 
+/// - Returns the captured messages as they would have been rendered, along with the stream they would have been written to.
+/// - Messages are captured after [MIDDLEWARE](config::middleware), [ROUTES](config::routes) are ignored.
+/// - Messages printed by other threads, or already in the queue, are written as usual.
+/// - Captures can be nested, the innermost one receives the messages. The previous capture is restored afterward, even if `f` panics.
+/// 
+/// ```
+/// use comfy_print::comfy_println;
+/// use comfy_print::message::OutputKind;
+/// 
+/// let captured = comfy_print::async_impl::capture(|| comfy_println!("Hello, world!"));
+/// assert_eq!(captured, vec![(OutputKind::Stdout, "Hello, world!\n".to_string())]);
+/// ```
+pub fn capture<F: FnOnce()>(f: F) -> Vec<(OutputKind, String)> {
+	/// Puts back the capture that was active before [capture()].
+	struct RestoreOnDrop(Option<Vec<(OutputKind, String)>>);
+	
+	impl Drop for RestoreOnDrop {
+		fn drop(&mut self) {
+			let previous = self.0.take();
+			CAPTURED.with_borrow_mut(|captured| *captured = previous);
+		}
 	}
+	
+	let restore = RestoreOnDrop(CAPTURED.with_borrow_mut(|captured| captured.replace(Vec::new())));
+	f();
+	
+	let captured = CAPTURED.with_borrow_mut(Option::take).unwrap_or_default();
+	drop(restore);
+	return captured;
+}
 
-	match msg.output_kind() {
-		OutputKind::Stdout => {
-			let mut stdout = std::io::stdout().lock();
-			write!(stdout, "{}", msg)?;
-			stdout.flush()?;
-			Ok(())
-		}
-		OutputKind::Stderr => {
-			let mut stderr = std::io::stderr().lock();
-			write!(stderr, "{}", msg)?;
-			stderr.flush()?;
-			Ok(())
+/// Runs `f`, returning everything printed on the calling thread meanwhile as a single string, e.g. for snapshot tests.
+/// 
+/// - Messages are captured by [capture()], then concatenated in the order they were printed.
+/// - Each stderr line is prefixed by [TRANSCRIPT_STDERR_PREFIX](config::transcript_stderr_prefix), so the streams can be told apart.
+/// 
+/// ```
+/// use comfy_print::{comfy_eprintln, comfy_println};
+/// 
+/// let transcript = comfy_print::async_impl::capture_transcript(|| {
+/// 	comfy_println!("Compiling...");
+/// 	comfy_eprintln!("warning: unused variable");
+/// 	comfy_println!("Done.");
+/// });
+/// 
+/// assert_eq!(transcript, "Compiling...\n[stderr] warning: unused variable\nDone.\n");
+/// ```
+pub fn capture_transcript<F: FnOnce()>(f: F) -> String {
+	let captured = capture(f);
+	
+	let mut prefix = String::new();
+	config::transcript_stderr_prefix::get(&mut prefix);
+	
+	let mut transcript = String::new();
+	for (kind, text) in captured {
+		match kind {
+			OutputKind::Stdout => transcript.push_str(&text),
+			OutputKind::Stderr => {
+				for line in text.split_inclusive('\n') {
+					transcript.push_str(&prefix);
+					transcript.push_str(line);
+				}
+			}
 		}
 	}
+	
+	return transcript;
 }
 
-/// WARNING: Will lock [QUEUE]
-#[inline(always)]
-fn try_insert_write_err(err: &std::io::Error, call_description: &'static str) {
-	if config::allow_logging_print_failures::get() == false {
-		return;
-	}
+/// Renders `msg` into the current [capture()], considering it written.
+fn capture_message(mut msg: Message) {
+	let update_guard = config::update_lock::read();
+	let configs = StreamConfigs::read(&msg);
+	drop(update_guard);
 	
-	let max_length = config::max_queue_length::get();
-	let mut queue_guard: MutexGuard<RawFairMutex, Vec<Message>> = QUEUE.lock();
-	if queue_guard.len() < max_length {
-		queue_guard.insert(0, Message::error_ln(format!("{call_description}\nError: {err}.")));
-	}
+	// Rendering may invoke a renderer that prints, so it must run outside the capture's borrow.
+	let Ok(text) = render::for_stream(&msg, configs.annotate)
+			else { return; };
 	
-	drop(queue_guard);
+	msg.set_written();
+	CAPTURED.with_borrow_mut(|captured| {
+		if let Some(captured) = captured.as_mut() {
+			captured.push((configs.target, text));
+		}
+	});
 }
 
-/// WARNING: does not lock anything since this receives a mutable reference to a queue.
-#[inline(always)]
-fn owned_try_insert_write_err(queue_guard: &mut MutexGuard<RawFairMutex, Vec<Message>>, err: &std::io::Error, call_description: &'static str) {
-	if config::allow_logging_print_failures::get() == false {
-		return;
-	}
+/// Enters a nested scope on the calling thread, until the returned guard is dropped.
+/// 
+/// Messages created on this thread while the guard is alive are indented by [SCOPE_INDENT] more spaces per level, 
+/// on top of [INDENT](config::indent) (or [Message::with_indent()]), for tree-structured output:
+/// 
+/// ```
+/// use comfy_print::comfy_println;
+/// 
+/// comfy_println!("Task"); // "Task"
+/// let _scope = comfy_print::async_impl::scope();
+/// comfy_println!("Step"); // "  Step"
+/// ```
+/// 
+/// The level is captured when the message is created, so it doesn't matter which thread ends up writing it.
+pub fn scope() -> ScopeGuard {
+	SCOPE_DEPTH.with(|depth| depth.set(depth.get() + 1));
+	return ScopeGuard { _not_send: std::marker::PhantomData };
+}
 
-	let max_length = config::max_queue_length::get();
-	if queue_guard.len() < max_length {
-		queue_guard.insert(0, Message::error_ln(format!("{call_description}\nError: {err}.")));
-	}
+/// How many [scope()]s the calling thread is in.
+pub fn scope_depth() -> usize {
+	return SCOPE_DEPTH.with(std::cell::Cell::get);
 }
 
-#[cfg(test)]
-pub(crate) mod tests {
-	pub(crate) static FORCE_WRITE_FAIL: AtomicBool = AtomicBool::new(false);
-	pub(crate) static TOGGLE_WRITE_FAIL: AtomicBool = AtomicBool::new(false);
-	pub const FORCE_WRITE_FAIL_MSG: &str = "Forced write failure";
+/// Leaves the [scope()] that created it when dropped.
+/// 
+/// Bound to the thread that created it, since the scope depth is per thread.
+#[must_use = "the scope ends as soon as the guard is dropped"]
+pub struct ScopeGuard {
+	_not_send: std::marker::PhantomData<*const ()>,
+}
 
-	use std::sync::atomic::AtomicBool;
-	use crate::comfy_println;
-	use super::*;
-	use crate::test_utils;
-	
-	#[test]
-	fn test_when_queue_is_empty() {
-		comfy_println!("Test message");
-		assert_eq!(test_utils::get_queue().len(), 0);
+impl Drop for ScopeGuard {
+	fn drop(&mut self) {
+		SCOPE_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
 	}
+}
 
-	#[test]
-	fn test_when_queue_is_not_empty() {
-		// Just so the error messages don't interfere with the test.
+/// Prints the error of `result` to [std::io::stderr], with a newline, then returns [None]. Returns the value if `result` is [Ok].
+/// 
+/// - For the "log the error and continue" pattern, e.g. `let Some(file) = log_err(File::open(path)) else { continue; };`
+/// - Printed like [`comfy_eprintln!`](crate::comfy_eprintln): queued and retried on failure, and panics raised by `E`'s [Display] are caught.
+/// - Use [`comfy_log_err!`](crate::comfy_log_err) to also attach the location of the call.
+pub fn log_err<T, E: std::fmt::Display>(result: Result<T, E>) -> Option<T> {
+	return match result {
+		Ok(value) => Some(value),
+		Err(err) => {
+			comfy_print_async(crate::message::try_format(|| Message::error_ln(err.to_string())));
+			None
+		}
+	};
+}
+
+/// Prints text that was already rendered, e.g. captured from another process's [comfy_print](crate) output, without rendering it again.
+/// 
+/// - Written verbatim: no indentation, location, trailing newline, [MIDDLEWARE](config::middleware) or stream-only config (e.g. [WRAP_WIDTH](config::wrap_width)) is applied.
+/// - Otherwise handled like any other message: queued and retried on failure.
+/// - Messages are text, invalid UTF-8 sequences in `bytes` are replaced by `U+FFFD`. Bytes rendered by [comfy_print](crate) are always valid UTF-8.
+pub fn forward_rendered(bytes: Vec<u8>, kind: OutputKind) {
+	let rendered = String::from_utf8(bytes)
+		.unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned());
+	
+	comfy_print_async(Message::verbatim(rendered, kind));
+}
+
+/// Same as [comfy_print_async()], but assigns `msg` a sequence number to be used with [wait_for_seq()].
+/// 
+/// Sequence numbers are unique and increase with each call, starting at 1.
+/// 
+/// After [u64::MAX] they wrap around to 0 instead of panicking, 
+/// which would take centuries even at a billion calls per second.
+pub fn enqueue_seq(mut msg: Message) -> u64 {
+	let seq = NEXT_SEQ.fetch_add(1, Ordering::Relaxed);
+	msg.set_seq(seq);
+	comfy_print_async(msg);
+	return seq;
+}
+
+/// Blocks until the message with sequence number `seq`, along with every message with a lower sequence number, 
+/// has left [comfy_print](crate), see [enqueue_seq()].
+/// 
+/// A message leaves when it's written to its stream, but also when it's discarded 
+/// (e.g. [queue full](config::on_queue_full), suppressed by [dedup key](Message::with_dedup_key())) 
+/// or moved to disk (e.g. [persist_queue()]).
+/// 
+/// - Blocks forever if `seq` was never returned by [enqueue_seq()].
+/// - Blocks while [paused](pause()), or while the message is stuck in the queue.
+/// 
+/// WARNING: Will lock [SEQ_PROGRESS].
+pub fn wait_for_seq(seq: u64) {
+	let mut progress_guard = SEQ_PROGRESS.lock();
+	
+	while progress_guard.completed_up_to < seq {
+		SEQ_ADVANCED.wait(&mut progress_guard);
+	}
+	
+	drop(progress_guard);
+}
+
+/// Called when a message with sequence number `seq` is dropped.
+/// 
+/// WARNING: Will lock [SEQ_PROGRESS].
+pub(crate) fn complete_seq(seq: u64) {
+	let mut progress_guard = SEQ_PROGRESS.lock();
+	progress_guard.completed_ahead.insert(seq);
+	
+	let mut advanced = false;
+	loop {
+		let next = progress_guard.completed_up_to.wrapping_add(1);
+		if progress_guard.completed_ahead.remove(&next) == false {
+			break;
+		}
+		
+		progress_guard.completed_up_to = next;
+		advanced = true;
+	}
+	
+	drop(progress_guard);
+	
+	if advanced {
+		SEQ_ADVANCED.notify_all();
+	}
+}
+
+/// Removes up to `n` messages from the front of the queue (the oldest ones), without printing them.
+/// 
+/// - Useful for shedding load when the queue grows faster than it can be printed.
+/// - Each removed message is reported to [DROP_CHANNEL](config::drop_channel) and counted by [dropped_count()].
+/// 
+/// # Returns
+/// 
+/// The number of messages removed, less than `n` if the queue was shorter.
+/// 
+/// WARNING: Will lock [QUEUE].
+pub fn drop_oldest(n: usize) -> usize {
+	let mut queue_guard = QUEUE.lock();
+	let count = n.min(queue_guard.len());
+	
+	for evicted in queue_guard.drain(..count) {
+		notify_dropped(evicted.output_kind(), DropReason::Requested);
+	}
+	
+	config::queue_warn_threshold::observe(queue_guard.len());
+	drop(queue_guard);
+	
+	run_completions();
+	return count;
+}
+
+/// An estimate of the memory used by the messages in the queue, in bytes: the allocated capacity of each message's text, 
+/// plus the size of each [Message] itself.
+/// 
+/// - Unlike the length of the text, includes the spare capacity of each [String].
+/// - Doesn't include memory owned by a message's [completion callback](Message::with_on_complete()), if any.
+/// 
+/// WARNING: Will lock [QUEUE].
+pub fn queue_memory_bytes() -> usize {
+	let queue_guard = QUEUE.lock();
+	let bytes = queue_guard
+		.iter()
+		.map(|msg| msg.string_capacity() + std::mem::size_of::<Message>())
+		.sum();
+	
+	drop(queue_guard);
+	return bytes;
+}
+
+/// How many messages in the queue target the stream `kind`, including diagnostics.
+/// 
+/// Counts by [OutputKind], regardless of [COMBINED_STREAM](config::combined_stream).
+/// 
+/// WARNING: Will lock [QUEUE].
+pub fn queue_len_by_kind(kind: OutputKind) -> usize {
+	let queue_guard = QUEUE.lock();
+	let len = queue_guard.iter().filter(|msg| msg.output_kind() == kind).count();
+	drop(queue_guard);
+	return len;
+}
+
+/// Distribution of the time between creating each message and writing it to its stream, in microseconds: `(p50, p99, max)`.
+/// 
+/// - Includes the time spent waiting in the queue and retrying, messages written to disk or dropped aren't measured.
+/// - Percentiles are approximate (within ~6%), `max` is exact.
+/// - All zeros until a message is written.
+/// - Requires feature **latency-metrics**, without it nothing is measured.
+#[cfg(feature = "latency-metrics")]
+pub fn latency_percentiles() -> (u64, u64, u64) {
+	return crate::latency::percentiles();
+}
+
+/// How many messages were dropped instead of being printed, see [DROP_CHANNEL](config::drop_channel).
+/// 
+/// Saturates at [usize::MAX].
+pub fn dropped_count() -> usize {
+	return DROPPED_COUNT.load(Ordering::Relaxed);
+}
+
+/// Adds 1 to `counter`, unless it already reached [usize::MAX].
+fn saturating_increment(counter: &AtomicUsize) {
+	let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| count.checked_add(1));
+}
+
+fn notify_dropped(kind: OutputKind, reason: DropReason) {
+	saturating_increment(&DROPPED_COUNT);
+	config::drop_channel::notify(kind, reason);
+}
+
+/// What [push_capped()] did with a message.
+enum Pushed {
+	/// Kept, either in the queue or [spilled](On_QueueFull::Spill).
+	Kept {
+		/// Index in the queue of the message removed to make room, by [KeepNewest](On_QueueFull::KeepNewest).
+		evicted: Option<usize>,
+	},
+	Dropped,
+}
+
+/// Pushes `msg` to the back of the queue, respecting [CATEGORY_CAPS](config::category_caps), [MAX_QUEUE_LENGTH](config::max_queue_length) 
+/// and [ON_QUEUE_FULL](config::on_queue_full).
+/// 
+/// WARNING: Will lock [UPDATE_LOCK](config::update_lock) for reading, then may lock [CATEGORY_CAPS](config::category_caps).
+fn push_capped(queue_guard: &mut MutexGuard<RawFairMutex, Vec<Message>>, msg: Message) -> Pushed {
+	mark_enqueued();
+	
+	let mut pushed = Pushed::Kept { evicted: None };
+	let category = msg.category();
+	
+	// Read together, so that a group of changes being applied is seen entirely or not at all.
+	let update_guard = config::update_lock::read();
+	let category_cap = category.and_then(config::category_caps::get);
+	let max_queue_length = config::max_queue_length::effective();
+	let on_queue_full = config::on_queue_full::get();
+	drop(update_guard);
+	
+	if category_cap.is_some_and(|cap| queue_guard.iter().filter(|queued| queued.category() == category).count() >= cap) {
+		match queue_guard.iter().position(|queued| queued.category() == category) {
+			Some(oldest) if On_QueueFull::KeepNewest == on_queue_full => {
+				let evicted = queue_guard.remove(oldest);
+				notify_dropped(evicted.output_kind(), DropReason::CategoryFull);
+				queue_guard.push(msg);
+				pushed = Pushed::Kept { evicted: Some(oldest) };
+			}
+			_ => {
+				notify_dropped(msg.output_kind(), DropReason::CategoryFull);
+				pushed = Pushed::Dropped;
+			}
+		}
+	} else if queue_guard.len() < max_queue_length {
+		queue_guard.push(msg);
+	} else if On_QueueFull::KeepNewest == on_queue_full && queue_guard.is_empty() == false {
+		let evicted = queue_guard.remove(0);
+		notify_dropped(evicted.output_kind(), DropReason::QueueFull);
+		queue_guard.push(msg);
+		pushed = Pushed::Kept { evicted: Some(0) };
+	} else if On_QueueFull::Spill == on_queue_full {
+		if spill(&msg).is_err() {
+			notify_dropped(msg.output_kind(), DropReason::QueueFull);
+			pushed = Pushed::Dropped;
+		}
+	} else {
+		notify_dropped(msg.output_kind(), DropReason::QueueFull);
+		pushed = Pushed::Dropped;
+	}
+	
+	config::queue_warn_threshold::observe(queue_guard.len());
+	return pushed;
+}
+
+/// Starts every line written by [spill()], ASCII's record separator.
+/// 
+/// Rendered messages appended to the same file have it doubled at the start of their lines (see [escape_spill_marker()]), 
+/// so [replay_spill()] can't mistake them for spilled messages.
+pub(crate) const SPILL_MARKER: char = '\u{1E}';
+
+/// Doubles [SPILL_MARKER] wherever it starts a line of `rendered`.
+pub(crate) fn escape_spill_marker(rendered: &str) -> Cow<'_, str> {
+	if rendered.contains(SPILL_MARKER) == false {
+		return Cow::Borrowed(rendered);
+	}
+	
+	let mut escaped = String::with_capacity(rendered.len() + 1);
+	for line in rendered.split_inclusive('\n') {
+		if line.starts_with(SPILL_MARKER) {
+			escaped.push(SPILL_MARKER);
+		}
+		
+		escaped.push_str(line);
+	}
+	
+	return Cow::Owned(escaped);
+}
+
+/// Appends `msg` to the file at [LOG_IO_PATH](config::log_io_path), see [On_QueueFull::Spill].
+/// 
+/// Must be called while holding the lock of the [QUEUE], so that it doesn't race with [replay_spill()].
+fn spill(msg: &Message) -> std::io::Result<()> {
+	let mut file = config::log_io_path::get_file()?;
+	file.write_all(format!("{SPILL_MARKER}{}\n", msg.to_persisted_line()).as_bytes())?;
+	return file.flush();
+}
+
+/// Moves messages spilled by [On_QueueFull::Spill] from the file at [LOG_IO_PATH](config::log_io_path) back to the back of the queue, 
+/// as many as fit within [MAX_QUEUE_LENGTH](config::max_queue_length).
+/// 
+/// - Spilled messages are read in the order they were spilled, the ones that don't fit are kept in the file.
+/// - Lines that are not spilled messages (e.g. written by [WriteToDisk](On_MaxRetriesReached::WriteToDisk)) are kept in the file.
+/// - The file is deleted once nothing is left in it.
+/// 
+/// # Returns
+/// 
+/// * `Ok(count)` with the number of messages replayed, `Ok(0)` if the file doesn't exist.
+/// * `Err(std::io::Error)` if the file couldn't be read or rewritten, in which case nothing is replayed.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [STATE].
+pub fn replay_spill() -> std::io::Result<usize> {
+	let mut path = String::new();
+	config::log_io_path::get(&mut path);
+	
+	// Spilling happens while holding the queue's lock, holding it here ensures no message is spilled while the file is rewritten.
+	let mut queue_guard = QUEUE.lock();
+	
+	let contents = match std::fs::read_to_string(path.as_str()) {
+		Ok(contents) => contents,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+			drop(queue_guard);
+			return Ok(0);
+		}
+		Err(err) => {
+			drop(queue_guard);
+			return Err(err);
+		}
+	};
+	
+	let mut room = config::max_queue_length::effective().saturating_sub(queue_guard.len());
+	let mut replayed = Vec::new();
+	let mut remaining = String::new();
+	
+	// Written when the file was created, if LOG_FILE_BOM was enabled.
+	let contents = match contents.strip_prefix('\u{FEFF}') {
+		Some(stripped) => {
+			remaining.push('\u{FEFF}');
+			stripped
+		}
+		None => contents.as_str(),
+	};
+	
+	for line in contents.lines() {
+		match line.strip_prefix(SPILL_MARKER).and_then(Message::from_persisted_line) {
+			Some(msg) if room > 0 => {
+				replayed.push(msg);
+				room -= 1;
+			}
+			_ => {
+				remaining.push_str(line);
+				remaining.push('\n');
+			}
+		}
+	}
+	
+	if replayed.is_empty() {
+		drop(queue_guard);
+		return Ok(0);
+	}
+	
+	let write_result = 
+		if remaining.is_empty() {
+			std::fs::remove_file(path.as_str())
+		} else {
+			std::fs::write(path.as_str(), remaining)
+		};
+	
+	if let Err(err) = write_result {
+		drop(queue_guard);
+		return Err(err);
+	}
+	
+	let count = replayed.len();
+	queue_guard.extend(replayed);
+	config::queue_warn_threshold::observe(queue_guard.len());
+	drop(queue_guard);
+	
+	check_state();
+	return Ok(count);
+}
+
+/// Marks `key` as the most recently seen, returning whether it was already within [DEDUP_WINDOW].
+/// 
+/// WARNING: Will lock [RECENT_DEDUP_KEYS].
+fn was_recently_seen(key: u64) -> bool {
+	let mut keys_guard = RECENT_DEDUP_KEYS.lock();
+	
+	let was_seen = 
+		if let Some(index) = keys_guard.iter().position(|seen| *seen == key) {
+			keys_guard.remove(index);
+			true
+		} else {
+			false
+		};
+	
+	keys_guard.push_back(key);
+	if keys_guard.len() > DEDUP_WINDOW {
+		keys_guard.pop_front();
+	}
+	
+	drop(keys_guard);
+	return was_seen;
+}
+
+/// WARNING: May lock [STATE], then may lock [QUEUE].
+fn check_state() {
+	if is_paused() {
+		return;
+	}
+	
+	let Some(mut state_guard) = try_lock_state()
+			else { return; };
+
+	if state_guard.is_busy() { // We already pushed our msg to the queue and there's already someone else printing it, so we can return.
+		drop(state_guard);
+		return;
+	}
+
+	if let PrintingState::Parked(handle) = std::mem::replace(&mut *state_guard, PrintingState::Idle) {
+		handle.thread().unpark();
+		*state_guard = PrintingState::Threaded(handle);
+		drop(state_guard);
+		return;
+	}
+	
+	#[cfg(any(test, feature = "test-sync-drain"))]
+	if config::test_sync_drain::get() {
+		print_on_caller(state_guard);
+		return;
+	}
+	
+	if config::drain_spawner::is_set() {
+		// The spawner may run the task inline, which locks the state.
+		*state_guard = PrintingState::Spawned;
+		drop(state_guard);
+		
+		let Err(_) = config::drain_spawner::spawn(Box::new(print_on_spawner))
+				else { return; };
+		
+		state_guard = lock_state();
+		*state_guard = PrintingState::Idle;
+	}
+
+	#[cfg(feature = "single-thread")]
+	print_on_caller(state_guard);
+	
+	#[cfg(not(feature = "single-thread"))]
+	match spawn_printer() {
+		Ok(handle) => {
+			SPAWN_RETRY_PENDING.store(false, Ordering::Relaxed);
+			*state_guard = PrintingState::Threaded(handle);
+			drop(state_guard);
+		}
+		Err(err) => {
+			on_spawn_failure(state_guard, err);
+		}
+	}
+}
+
+#[cfg(all(not(feature = "single-thread"), not(test)))]
+fn spawn_printer() -> std::io::Result<thread::JoinHandle<()>> {
+	return thread::Builder::new().spawn(print_then_linger);
+}
+
+#[cfg(all(not(feature = "single-thread"), test))]
+fn spawn_printer() -> std::io::Result<thread::JoinHandle<()>> {
+	let fail_next_result = tests::FAIL_NEXT_SPAWNS
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| remaining.checked_sub(1));
+	
+	if fail_next_result.is_ok() {
+		return Err(std::io::Error::other(tests::FORCE_WRITE_FAIL_MSG));
+	}
+	
+	return thread::Builder::new().spawn(print_then_linger);
+}
+
+/// Applies [ON_SPAWN_FAILURE](config::on_spawn_failure) after failing to spawn the thread responsible for printing the queue.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr].
+#[cfg(not(feature = "single-thread"))]
+fn on_spawn_failure(state_guard: StateGuard, err: std::io::Error) {
+	const DESCRIPTION: &str = "`comfy_print::async_impl::check_state()`: Failed to create a thread to print the queue.";
+	
+	match config::on_spawn_failure::get() {
+		On_SpawnFailure::Synchronous => {
+			try_insert_write_err(&err, DESCRIPTION);
+			print_on_caller(state_guard);
+		}
+		On_SpawnFailure::DropQueue => {
+			let mut queue_guard = QUEUE.lock();
+			for dropped in queue_guard.drain(..) {
+				if dropped.is_diagnostic() == false {
+					notify_dropped(dropped.output_kind(), DropReason::SpawnFailed);
+				}
+			}
+			
+			config::queue_warn_threshold::observe(0);
+			drop(queue_guard);
+			drop(state_guard);
+		}
+		On_SpawnFailure::RetryLater => {
+			drop(state_guard);
+			
+			if SPAWN_RETRY_PENDING.swap(true, Ordering::Relaxed) == false {
+				try_insert_write_err(&err, DESCRIPTION);
+			}
+		}
+	}
+}
+
+/// Prints the queue on the caller's thread, marking the state as [Synchronous](PrintingState::Synchronous) meanwhile.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr], then will lock [STATE].
+fn print_on_caller(mut state_guard: StateGuard) {
+	*state_guard = PrintingState::Synchronous;
+	drop(state_guard);
+
+	start_printing_queue();
+
+	let mut state_guard = lock_state();
+	*state_guard = PrintingState::Idle;
+	drop(state_guard);
+}
+
+/// Prints the queue on the [DRAIN_SPAWNER](config::drain_spawner)'s executor, the state is [Spawned](PrintingState::Spawned) meanwhile.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr], then will lock [STATE].
+fn print_on_spawner() {
+	start_printing_queue();
+
+	let mut state_guard = lock_state();
+	*state_guard = PrintingState::Idle;
+	drop(state_guard);
+}
+
+/// Pre-spawns the thread responsible for printing the queue, leaving it parked until a message fails to be printed.
+/// 
+/// This is an optional optimization for latency-sensitive programs: 
+/// by default the thread is only spawned after the first failure, which adds latency to that call.
+/// 
+/// Does nothing if the queue is already being printed or if a thread was already pre-spawned.
+/// 
+/// # Returns
+/// 
+/// * `Ok(())` if a thread is parked and ready, or if feature **single-thread** is enabled, in which case nothing is spawned.
+/// * `Err(std::io::Error)` if spawning the thread failed.
+pub fn warmup() -> std::io::Result<()> {
+	if cfg!(feature = "single-thread") {
+		return Ok(());
+	}
+	
+	let mut state_guard = lock_state();
+	
+	if state_guard.is_busy() || matches!(*state_guard, PrintingState::Parked(_)) {
+		drop(state_guard);
+		return Ok(());
+	}
+	
+	let handle = thread::Builder::new().spawn(wait_until_unparked)?;
+	*state_guard = PrintingState::Parked(handle);
+	drop(state_guard);
+	return Ok(());
+	
+	/// WARNING: Will lock [STATE].
+	fn wait_until_unparked() {
+		loop {
+			let state_guard = lock_state();
+			let is_parked = matches!(*state_guard, PrintingState::Parked(_));
+			drop(state_guard);
+			
+			if is_parked == false {
+				break;
+			}
+			
+			thread::park();
+		}
+		
+		print_then_linger();
+	}
+}
+
+/// Runs on the thread printing the queue: prints it, then stays parked for [PRINTER_IDLE_TIMEOUT](config::printer_idle_timeout), 
+/// printing it again each time [check_state()] unparks the thread.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr], then may lock [STATE].
+fn print_then_linger() {
+	loop {
+		start_printing_queue();
+		
+		let timeout = config::printer_idle_timeout::get();
+		if timeout.is_zero() || linger(timeout) == false {
+			return;
+		}
+	}
+	
+	/// Parks the current thread until [check_state()] unparks it or `timeout` passes.
+	/// 
+	/// Returns true if the queue must be printed again, false if the thread must exit.
+	/// 
+	/// WARNING: Will lock [STATE], then may lock [QUEUE].
+	fn linger(timeout: std::time::Duration) -> bool {
+		let mut state_guard = lock_state();
+		match std::mem::replace(&mut *state_guard, PrintingState::Idle) {
+			PrintingState::Threaded(handle) if handle.thread().id() == thread::current().id() => {
+				*state_guard = PrintingState::Parked(handle);
+			}
+			other => {
+				*state_guard = other;
+				drop(state_guard);
+				return false;
+			}
+		}
+		
+		drop(state_guard);
+		
+		let deadline = std::time::Instant::now() + timeout;
+		
+		loop {
+			let mut state_guard = lock_state();
+			match &*state_guard {
+				PrintingState::Threaded(_) => { // unparked by [check_state()]
+					drop(state_guard);
+					return true;
+				}
+				PrintingState::Idle | PrintingState::Spawned => { // taken by [shutdown()]
+					drop(state_guard);
+					return false;
+				}
+				PrintingState::Parked(_) | PrintingState::Synchronous => {} // Synchronous: temporarily taken by [flush_with()]
+			}
+			
+			let now = std::time::Instant::now();
+			if now >= deadline {
+				if let PrintingState::Parked(_) = &*state_guard {
+					*state_guard = PrintingState::Idle; // detaches the thread
+				}
+				
+				drop(state_guard);
+				return false;
+			}
+			
+			drop(state_guard);
+			thread::park_timeout(deadline - now);
+		}
+	}
+}
+
+/// Waits until the queue is printed, then joins the thread responsible for printing it, including a thread parked by [warmup()].
+/// 
+/// - Call it before exiting, otherwise messages still in the queue are lost when the process ends.
+/// - Messages left in the queue (e.g. after [MAX_RETRIES](config::max_retries) was reached) are retried until `timeout`.
+/// - Messages printed afterward are handled according to [ON_POST_SHUTDOWN](config::on_post_shutdown), 
+///   which restarts printing by default.
+/// 
+/// # Returns
+/// 
+/// * `true` if the queue was printed and no thread is left running.
+/// * `false` if `timeout` was reached first, e.g. while [paused](pause()). The thread, if any, is left running.
+/// 
+/// WARNING: Will lock [QUEUE], then [STATE].
+pub fn shutdown(timeout: std::time::Duration) -> bool {
+	print_pending_progress();
+	SHUTDOWN_CALLED.store(true, Ordering::Relaxed);
+	let deadline = std::time::Instant::now() + timeout;
+	
+	loop {
+		let queue_guard = QUEUE.lock();
+		let is_queue_empty = queue_guard.is_empty();
+		drop(queue_guard);
+		
+		let state_guard = lock_state();
+		let is_busy = state_guard.is_busy();
+		drop(state_guard);
+		
+		if is_queue_empty && is_busy == false {
+			break;
+		}
+		
+		if std::time::Instant::now() >= deadline {
+			return false;
+		}
+		
+		if is_busy == false {
+			check_state();
+		}
+		
+		thread::sleep(std::time::Duration::from_millis(1));
+	}
+	
+	let mut state_guard = lock_state();
+	match std::mem::replace(&mut *state_guard, PrintingState::Idle) {
+		PrintingState::Threaded(handle) => {
+			drop(state_guard);
+			let _ = handle.join();
+		}
+		PrintingState::Parked(handle) => {
+			drop(state_guard);
+			handle.thread().unpark();
+			let _ = handle.join();
+		}
+		other => {
+			*state_guard = other;
+			drop(state_guard);
+		}
+	}
+	
+	return true;
+}
+
+/// Prints the queue on the caller's thread, retrying up to `max_retries` times regardless of [MAX_RETRIES](config::max_retries).
+/// 
+/// - Useful for a final drain before exiting, where the caller may want to try harder than the configured budget.
+/// - Waits for the thread printing the queue, if any, to stop first.
+/// - Doesn't follow [ON_QUEUE_PRINTING_FAIL](config::on_queue_printing_fail) nor [ON_MAX_RETRIES_REACHED](config::on_max_retries_reached): 
+///   a message that fails to be written is kept at the front of the queue and retried.
+/// - Without `deadline`, [FLUSH_TIMEOUT](config::flush_timeout) applies, if set. 
+///   It's checked between attempts, a single write blocked by the operating system can't be interrupted.
+/// - No global is modified.
+/// 
+/// # Returns
+/// 
+/// * `Ok(())` if the queue was printed.
+/// * `Err(std::io::Error)` with the last write error if `max_retries` ran out, 
+///   [TimedOut](std::io::ErrorKind::TimedOut) if `deadline` passed first,
+///   or [WouldBlock](std::io::ErrorKind::WouldBlock) if printing is [paused](pause()).
+///   Messages that were not printed are kept in the queue.
+/// 
+/// WARNING: Will lock [STATE], then may lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr].
+pub fn flush_with(max_retries: usize, deadline: Option<std::time::Duration>) -> std::io::Result<()> {
+	let deadline = deadline.or_else(config::flush_timeout::get).map(|duration| std::time::Instant::now() + duration);
+	let is_past_deadline = || deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline);
+	
+	if is_paused() {
+		return Err(std::io::Error::new(std::io::ErrorKind::WouldBlock, "`comfy_print::async_impl::flush_with()`: Printing is paused."));
+	}
+	
+	print_pending_progress();
+	
+	let mut state_guard = lock_state();
+	while state_guard.is_busy() {
+		drop(state_guard);
+		
+		if is_past_deadline() {
+			return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "`comfy_print::async_impl::flush_with()`: Timed out waiting for the queue to be released."));
+		}
+		
+		thread::yield_now();
+		state_guard = lock_state();
+	}
+	
+	let previous_state = std::mem::replace(&mut *state_guard, PrintingState::Synchronous);
+	drop(state_guard);
+	
+	let mut pass_stream = None;
+	let mut retries = 0;
+	
+	let result = loop {
+		let mut queue_guard = QUEUE.lock();
+		if queue_guard.is_empty() {
+			config::queue_warn_threshold::observe(0);
+			drop(queue_guard);
+			
+			let _ = flush_stdout();
+			let _ = std::io::stderr().flush();
+			config::flush_every::reset();
+			break Ok(());
+		}
+		
+		config::queue_warn_threshold::observe(queue_guard.len());
+		let mut msg = queue_guard.remove(0);
+		drop(queue_guard);
+		
+		if pass_stream.is_none() {
+			let target = target_stream(&msg);
+			write_batch_marker(config::batch_preamble::get, target);
+			pass_stream = Some(target);
+		}
+		
+		match try_write_draining(&msg) {
+			Ok(_) => {
+				let target = target_stream(&msg);
+				pass_stream = Some(target);
+				msg.set_written();
+				drop(msg);
+				run_completions();
+				write_batch_separator(target);
+			}
+			Err(err) => {
+				let mut queue_guard = QUEUE.lock();
+				queue_guard.insert(0, msg);
+				drop(queue_guard);
+				
+				if retries >= max_retries || config::global_retry_budget::spend() == false {
+					break Err(err);
+				}
+				
+				if is_past_deadline() {
+					break Err(std::io::Error::new(std::io::ErrorKind::TimedOut, format!(
+						"`comfy_print::async_impl::flush_with()`: Deadline passed while retrying.\nLast error: {err}.")));
+				}
+				
+				retries += 1;
+			}
+		}
+	};
+	
+	if let Some(target) = pass_stream {
+		write_batch_marker(config::batch_postamble::get, target);
+		
+		if result.is_ok() {
+			config::on_queue_empty::notify();
+		}
+	}
+	
+	let mut state_guard = lock_state();
+	*state_guard = previous_state;
+	drop(state_guard);
+	
+	return result;
+}
+
+/// Same as [flush_with()] with [MAX_RETRIES](config::max_retries), but only if no message was printed or queued for at least `idle`, 
+/// so that batched output is only flushed once it settles.
+/// 
+/// # Returns
+/// 
+/// * `true` if the output was idle, and thus flushed. Messages that still failed to be written are kept in the queue.
+/// * `false` if a message was printed or queued less than `idle` ago, nothing is done.
+/// 
+/// WARNING: May lock [STATE], then may lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr].
+pub fn flush_if_idle(idle: std::time::Duration) -> bool {
+	let last_enqueue = LAST_ENQUEUE_NANOS.load(Ordering::Relaxed);
+	
+	if last_enqueue != 0 {
+		let idle_nanos = u64::try_from(idle.as_nanos()).unwrap_or(u64::MAX);
+		if monotonic_nanos().saturating_sub(last_enqueue) < idle_nanos {
+			return false;
+		}
+	}
+	
+	let _ = flush_with(config::max_retries::get(), None);
+	return true;
+}
+
+/// Nanoseconds since the first call, never 0.
+fn monotonic_nanos() -> u64 {
+	static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+	let nanos = EPOCH.get_or_init(std::time::Instant::now).elapsed().as_nanos();
+	return u64::try_from(nanos).unwrap_or(u64::MAX).max(1);
+}
+
+fn mark_enqueued() {
+	LAST_ENQUEUE_NANOS.store(monotonic_nanos(), Ordering::Relaxed);
+}
+
+/// Stops writing to [std::io::stdout] | [std::io::stderr] until [resume()] is called.
+/// 
+/// - Useful while another component temporarily takes over the terminal (e.g. a full-screen menu).
+/// - Messages printed while paused are stored in the queue, subject to [MAX_QUEUE_LENGTH](config::max_queue_length).
+/// - A message being written when this is called will still finish writing.
+pub fn pause() {
+	PAUSED.store(true, Ordering::Relaxed);
+}
+
+/// Resumes writing after [pause()], printing every message stored in the queue meanwhile.
+pub fn resume() {
+	PAUSED.store(false, Ordering::Relaxed);
+	
+	let queue_guard = QUEUE.lock();
+	let is_empty = queue_guard.is_empty();
+	drop(queue_guard);
+	
+	if is_empty == false {
+		check_state();
+	}
+}
+
+/// Takes every message that would have been written since the last call, while [DRY_RUN](config::dry_run) was enabled, 
+/// along with the stream it would have been written to.
+/// 
+/// WARNING: Will lock [DRY_RUN_OUTPUT].
+pub fn dry_run_output() -> Vec<(OutputKind, String)> {
+	return std::mem::take(&mut *DRY_RUN_OUTPUT.lock());
+}
+
+/// Returns a receiver of a copy of each message successfully written to [std::io::stdout] | [std::io::stderr] from now on, 
+/// e.g. to build a live log viewer.
+/// 
+/// - Subscribers that fall more than [SUBSCRIBER_CAPACITY] messages behind miss the messages written meanwhile, printing never waits for them.
+/// - Dropping the receiver unsubscribes.
+/// 
+/// WARNING: Will lock [SUBSCRIBERS].
+#[cfg(feature = "subscribe")]
+#[cfg_attr(docsrs, doc(cfg(feature = "subscribe")))]
+pub fn subscribe() -> std::sync::mpsc::Receiver<Message> {
+	let (sender, receiver) = std::sync::mpsc::sync_channel(SUBSCRIBER_CAPACITY);
+	SUBSCRIBERS.lock().push(sender);
+	return receiver;
+}
+
+/// Sends a copy of `msg` to each subscriber, see [subscribe()].
+/// 
+/// WARNING: Will lock [SUBSCRIBERS].
+#[cfg(feature = "subscribe")]
+fn broadcast(msg: &Message) {
+	let mut subscribers_guard = SUBSCRIBERS.lock();
+	if subscribers_guard.is_empty() {
+		drop(subscribers_guard);
+		return;
+	}
+	
+	subscribers_guard.retain(|sender| match sender.try_send(msg.duplicate()) {
+		Ok(()) | Err(std::sync::mpsc::TrySendError::Full(_)) => true,
+		Err(std::sync::mpsc::TrySendError::Disconnected(_)) => false,
+	});
+	
+	drop(subscribers_guard);
+}
+
+/// Installs `new` as the sink of every message meant for [std::io::stdout], then prints the messages stored in the queue to it.
+/// 
+/// - Useful to buffer output until the real destination is ready, e.g. a terminal that is still being initialized.
+/// - A message being written when this is called finishes writing to the old sink, no message is written to both.
+/// - Returns the old sink, which is [std::io::stdout] itself if no sink was installed before.
+/// - [raw_write()] and [signal_safe_write()] still write to [std::io::stdout].
+/// 
+/// WARNING: Will lock [STDOUT_SINK], then [QUEUE].
+pub fn swap_stdout_sink(new: Box<dyn Write + Send>) -> Box<dyn Write + Send> {
+	let mut sink_guard = STDOUT_SINK.lock();
+	let old = sink_guard.replace(new);
+	drop(sink_guard);
+	
+	let queue_guard = QUEUE.lock();
+	let is_empty = queue_guard.is_empty();
+	drop(queue_guard);
+	
+	if is_empty == false {
+		check_state();
+	}
+	
+	return old.unwrap_or_else(|| Box::new(std::io::stdout()));
+}
+
+/// Whether printing is currently [paused](pause()).
+pub fn is_paused() -> bool {
+	return PAUSED.load(Ordering::Relaxed);
+}
+
+/// The name of the current state of the queue's printer, the same names passed to [ON_STATE_CHANGE](config::on_state_change):
+/// - `"Idle"`: Nothing is printing the queue.
+/// - `"Threaded"`: A thread is printing the queue.
+/// - `"Parked"`: A thread is parked, waiting for messages to print, see [warmup()] and [PRINTER_IDLE_TIMEOUT](config::printer_idle_timeout).
+/// - `"Synchronous"`: A caller is printing the queue on its own thread, e.g. [flush_with()].
+/// - `"Spawned"`: The queue is printed by the [DRAIN_SPAWNER](config::drain_spawner)'s executor.
+/// 
+/// WARNING: Will lock [STATE].
+pub fn state_name() -> &'static str {
+	return STATE.lock().name();
+}
+
+/// Prints `progress` at the start of the current line of [std::io::stdout], without a newline.
+/// 
+/// - Useful for progress indicators, each call overwrites the previous progress line.
+/// - The next regular message will be preceded by a newline, so it doesn't clobber the progress line.
+/// - Just like the macros, failed progress lines are stored in the queue.
+/// - On Windows, if [std::io::stdout] is a console, the line is cleared through the console API instead of relying on `\r`.
+/// - Updates arriving faster than [PROGRESS_THROTTLE](config::progress_throttle) are coalesced, only the latest one is written.
+pub fn print_progress(progress: &str) {
+	let throttle = config::progress_throttle::get();
+	if throttle.is_zero() == false {
+		let now = monotonic_nanos();
+		let last = LAST_PROGRESS_NANOS.load(Ordering::Relaxed);
+		let throttle_nanos = u64::try_from(throttle.as_nanos()).unwrap_or(u64::MAX);
+		
+		if last != 0 && now.saturating_sub(last) < throttle_nanos {
+			let mut pending_guard = PENDING_PROGRESS.lock();
+			*pending_guard = Some(progress.to_string());
+			HAS_PENDING_PROGRESS.store(true, Ordering::Relaxed);
+			drop(pending_guard);
+			return;
+		}
+		
+		LAST_PROGRESS_NANOS.store(now, Ordering::Relaxed);
+		take_pending_progress();
+	}
+	
+	comfy_print_async(Message::progress(progress));
+}
+
+/// Takes the progress line held back by [PROGRESS_THROTTLE](config::progress_throttle), if any.
+/// 
+/// WARNING: May lock [PENDING_PROGRESS].
+pub(crate) fn take_pending_progress() -> Option<String> {
+	if HAS_PENDING_PROGRESS.swap(false, Ordering::Relaxed) == false {
+		return None;
+	}
+	
+	return PENDING_PROGRESS.lock().take();
+}
+
+/// Prints the progress line held back by [PROGRESS_THROTTLE](config::progress_throttle), if any.
+fn print_pending_progress() {
+	if let Some(progress) = take_pending_progress() {
+		LAST_PROGRESS_NANOS.store(monotonic_nanos(), Ordering::Relaxed);
+		print_or_queue(Message::progress(progress));
+	}
+}
+
+/// Prints `progress` wrapped in ANSI save cursor (`ESC[s`) and restore cursor (`ESC[u`) sequences, 
+/// so the cursor returns to where it was after printing, without disturbing the scroll region of interactive TUIs.
+/// 
+/// - Only wraps if [std::io::stdout] is a [terminal](std::io::IsTerminal), otherwise `progress` is printed as a regular line.
+/// - Unlike [print_progress()], the next message is not preceded by a newline.
+pub fn print_progress_region(progress: &str) {
+	if std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+		comfy_print_async(Message::standard(wrap_in_saved_cursor(progress)));
+	} else {
+		comfy_print_async(Message::standard_ln(progress));
+	}
+}
+
+fn wrap_in_saved_cursor(text: &str) -> String {
+	return format!("\x1b[s{text}\x1b[u");
+}
+
+/// Replaces the [panic hook](std::panic::set_hook) with one that prints the panic to [std::io::stderr] through [comfy_print_async()].
+/// 
+/// - Unlike a hook using [eprintln!], printing the panic can't panic again (which would abort the process).
+/// - The backtrace is included if enabled, see [Backtrace::capture()](std::backtrace::Backtrace::capture()).
+/// - The previous hook is chained: it still runs after the panic is printed. 
+///   If the previous hook is the default one, the panic is also printed by it,
+///   call [std::panic::set_hook()] with an empty hook before this one to prevent that.
+pub fn install_panic_hook() {
+	let previous_hook = std::panic::take_hook();
+	
+	std::panic::set_hook(Box::new(move |info| {
+		let thread = thread::current();
+		let thread_name = thread.name().unwrap_or("<unnamed>");
+		let reason = crate::message::panic_payload_str(info.payload());
+		
+		let mut description = match info.location() {
+			Some(location) => format!("thread '{thread_name}' panicked at {location}:\n{reason}"),
+			None => format!("thread '{thread_name}' panicked:\n{reason}"),
+		};
+		
+		let backtrace = std::backtrace::Backtrace::capture();
+		if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+			description.push_str(&format!("\nstack backtrace:\n{backtrace}"));
+		}
+		
+		comfy_print_async(Message::error_ln(description));
+		previous_hook(info);
+	}));
+}
+
+/// Moves every unprinted message from the queue to the file at [PERSIST_QUEUE_PATH](config::persist_queue_path), 
+/// so that [restore_queue()] can print them after a restart.
+/// 
+/// - Call it before exiting, if the queue couldn't be printed (e.g. stdout is a closed pipe).
+/// - Messages are appended to the file, one per line.
+/// - If writing fails, the messages are kept in the queue.
+/// 
+/// # Returns
+/// 
+/// * `Ok(count)` with the number of messages persisted, `Ok(0)` if [PERSIST_QUEUE_PATH](config::persist_queue_path) is empty.
+/// * `Err(std::io::Error)` if the file couldn't be written.
+/// 
+/// WARNING: Will lock [QUEUE].
+pub fn persist_queue() -> std::io::Result<usize> {
+	let mut path = String::new();
+	config::persist_queue_path::get(&mut path);
+	if path.is_empty() {
+		return Ok(0);
+	}
+	
+	let mut queue_guard = QUEUE.lock();
+	if queue_guard.is_empty() {
+		drop(queue_guard);
+		return Ok(0);
+	}
+	
+	let mut lines = String::new();
+	for msg in queue_guard.iter() {
+		lines.push_str(&msg.to_persisted_line());
+		lines.push('\n');
+	}
+	
+	let path = std::path::Path::new(path.as_str());
+	if let Some(dir) = path.parent() {
+		std::fs::create_dir_all(dir)?;
+	}
+	
+	let mut file = std::fs::OpenOptions::new()
+			.append(true)
+			.create(true)
+			.open(path)?;
+	
+	file.write_all(lines.as_bytes())?;
+	file.flush()?;
+	
+	let count = queue_guard.len();
+	queue_guard.clear();
+	config::queue_warn_threshold::observe(0);
+	drop(queue_guard);
+	
+	run_completions();
+	return Ok(count);
+}
+
+/// Loads the messages stored by [persist_queue()] back into the queue, ahead of any message queued since startup, then deletes the file.
+/// 
+/// # Returns
+/// 
+/// * `Ok(count)` with the number of messages restored, `Ok(0)` if [PERSIST_QUEUE_PATH](config::persist_queue_path) is empty or the file doesn't exist.
+/// * `Err(std::io::Error)` if the file couldn't be read, deleted or contains malformed lines, in which case nothing is restored.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [STATE].
+pub fn restore_queue() -> std::io::Result<usize> {
+	let mut path = String::new();
+	config::persist_queue_path::get(&mut path);
+	if path.is_empty() {
+		return Ok(0);
+	}
+	
+	let contents = match std::fs::read_to_string(path.as_str()) {
+		Ok(contents) => contents,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+		Err(err) => return Err(err),
+	};
+	
+	let mut restored = Vec::new();
+	for (index, line) in contents.lines().enumerate() {
+		let Some(msg) = Message::from_persisted_line(line)
+				else {
+					return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!(
+						"Malformed message at line {} of {path}", index + 1)));
+				};
+		
+		restored.push(msg);
+	}
+	
+	std::fs::remove_file(path.as_str())?;
+	
+	let count = restored.len();
+	let mut queue_guard = QUEUE.lock();
+	queue_guard.splice(0..0, restored);
+	config::queue_warn_threshold::observe(queue_guard.len());
+	drop(queue_guard);
+	
+	if count > 0 {
+		check_state();
+	}
+	
+	return Ok(count);
+}
+
+/// The last [RECENT_CAPACITY] messages successfully written, along with the stream they were written to, 
+/// ordered according to [EMIT_ORDER](config::emit_order).
+/// 
+/// WARNING: Will lock [RECENT].
+pub fn recent() -> Vec<(OutputKind, String)> {
+	let recent_guard = RECENT.lock();
+	let messages = match config::emit_order::get() {
+		EmitOrder::Chronological => recent_guard.iter().cloned().collect(),
+		EmitOrder::NewestFirst => recent_guard.iter().rev().cloned().collect(),
+	};
+	
+	drop(recent_guard);
+	return messages;
+}
+
+/// The last `n` messages of [recent()] as a JSON array of `{"stream": "Stdout" | "Stderr", "text": "..."}` objects, 
+/// ready to be served from a debug endpoint.
+/// 
+/// - Ordered according to [EMIT_ORDER](config::emit_order), like [recent()].
+/// - Snapshots the messages while holding the lock, then serializes them after releasing it.
+/// 
+/// WARNING: Will lock [RECENT].
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub fn recent_json(n: usize) -> String {
+	let recent_guard = RECENT.lock();
+	let skipped = recent_guard.len().saturating_sub(n);
+	let mut snapshot: Vec<(OutputKind, String)> = recent_guard.iter().skip(skipped).cloned().collect();
+	drop(recent_guard);
+	
+	if config::emit_order::get() == EmitOrder::NewestFirst {
+		snapshot.reverse();
+	}
+	
+	let entries = snapshot
+		.into_iter()
+		.map(|(stream, text)| serde_json::json!({ "stream": stream, "text": text }))
+		.collect();
+	
+	return serde_json::Value::Array(entries).to_string();
+}
+
+/// How many messages were suppressed for sharing a [dedup key](Message::with_dedup_key()) with a recent message.
+/// 
+/// Saturates at [usize::MAX].
+pub fn suppressed_count() -> usize {
+	return SUPPRESSED_COUNT.load(Ordering::Relaxed);
+}
+
+/// How many diagnostics were suppressed because the queue already held [MAX_DIAGNOSTICS_IN_QUEUE](config::max_diagnostics_in_queue) of them.
+/// 
+/// Saturates at [usize::MAX].
+pub fn suppressed_diagnostics_count() -> usize {
+	return SUPPRESSED_DIAGNOSTICS.load(Ordering::Relaxed);
+}
+
+/// Whether any message was successfully written to [std::io::stderr] while [TRACK_STDERR_PRINTED](config::track_stderr_printed) was enabled.
+/// 
+/// Check it at the end of `main` to decide the exit code.
+pub fn stderr_was_printed() -> bool {
+	return STDERR_PRINTED.load(Ordering::Relaxed);
+}
+
+fn start_printing_queue() {
+	let mut pass_stream = None;
+	print_until_empty(config::max_retries::get(), 0, &mut pass_stream);
+	
+	// Messages dropped while holding the queue's lock, e.g. evicted when re-inserting a failed message.
+	run_completions();
+	
+	if let Some(target) = pass_stream {
+		write_batch_marker(config::batch_postamble::get, target);
+	}
+	
+	let queue_guard = QUEUE.lock();
+	let is_queue_empty = queue_guard.is_empty();
+	drop(queue_guard);
+	
+	if is_queue_empty {
+		config::serialize_startup::STARTUP_DONE.store(true, Ordering::Relaxed);
+		
+		if pass_stream.is_some() {
+			config::on_queue_empty::notify();
+		}
+	}
+}
+
+/// `pass_stream` is the stream the last message of this pass was written to, [None] until the first write is attempted.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock [std::io::stdout] and/or [std::io::stderr].
+fn print_until_empty(max_retries: usize, retries: usize, pass_stream: &mut Option<OutputKind>) {
+	if is_paused() {
+		return;
+	}
+	
+	let mut queue_guard = QUEUE.lock();
+	
+	if queue_guard.is_empty() {
+		queue_guard.shrink_to_fit();
+		config::queue_warn_threshold::observe(0);
+		drop(queue_guard);
+		
+		config::on_heartbeat::on_empty();
+		
+		// Messages printed with FlushStrategy::OnDrainEnd or FLUSH_EVERY were not flushed yet. There's nowhere to report errors at this point.
+		let _ = flush_stdout();
+		let _ = std::io::stderr().flush();
+		config::flush_every::reset();
+		return;
+	}
+	
+	// The message being written still counts, so that failing to write it doesn't repeat the warning.
+	config::queue_warn_threshold::observe(queue_guard.len());
+	let mut msg = queue_guard.remove(0);
+	drop(queue_guard); // unlock the queue before blocking stdout/err
+	
+	if pass_stream.is_none() {
+		let target = target_stream(&msg);
+		write_batch_marker(config::batch_preamble::get, target);
+		*pass_stream = Some(target);
+	}
+	
+	let write_result = 
+		if msg.is_overdue() {
+			write_escalated(&msg)
+		} else {
+			try_write_draining(&msg)
+		};
+	
+	match write_result {
+		Ok(_) => {
+			let target = target_stream(&msg);
+			*pass_stream = Some(target);
+			msg.set_written();
+			drop(msg); // completes its sequence number, if any, see [wait_for_seq()]
+			run_completions();
+			write_batch_separator(target);
+			print_until_empty(max_retries, retries, pass_stream);
+		},
+		Err(err) => match msg.fail_policy().unwrap_or_else(config::on_queue_printing_fail::get) {
+			On_QueuePrintingFail::TryUntilMaxRetries => {
+				let queue_len = reinsert_message(msg, err);
+				config::on_heartbeat::on_failure(queue_len);
+
+				if retries < max_retries && config::global_retry_budget::spend() {
+					print_until_empty(max_retries, retries + 1, pass_stream);
+				} else {
+					on_max_retries();
+				}
+			}
+			On_QueuePrintingFail::Return => {
+				let queue_len = reinsert_message(msg, err);
+				config::on_heartbeat::on_failure(queue_len);
+				return;
+			}
+		}
+	}
+	
+	return;
+
+	/// Returns the length of the queue afterward.
+	/// 
+	/// WARNING: Will lock [UPDATE_LOCK](config::update_lock) for reading, then [QUEUE].
+	fn reinsert_message(msg: Message, err: std::io::Error) -> usize {
+		let is_diagnostic = msg.is_diagnostic();
+		
+		let update_guard = config::update_lock::read();
+		let max_queue_length = config::max_queue_length::effective();
+		let on_queue_full = config::on_queue_full::get();
+		drop(update_guard);
+		
+		let mut queue_guard = QUEUE.lock();
+
+		// This can happen if another thread pushed a message to the queue while we were printing the current one.
+		if queue_guard.len() < max_queue_length {
+			queue_guard.insert(0, msg);
+		} else if let policy @ (On_QueueFull::KeepOldest | On_QueueFull::Spill) = on_queue_full {
+			if let Some(evicted) = queue_guard.pop() {
+				if policy == On_QueueFull::KeepOldest || spill(&evicted).is_err() {
+					notify_dropped(evicted.output_kind(), DropReason::QueueFull);
+				}
+			}
+			
+			queue_guard.insert(0, msg);
+		} else {
+			notify_dropped(msg.output_kind(), DropReason::QueueFull);
+		}
+
+		// Failing to print a diagnostic must not generate diagnostics about diagnostics.
+		if is_diagnostic == false {
+			owned_try_insert_write_err(&mut queue_guard, &err, "`comfy_print::async_impl::print_until_empty()`: Failed to print first message in queue.");
+		}
+		
+		let queue_len = queue_guard.len();
+		drop(queue_guard);
+		return queue_len;
+	}
+
+	/// WARNING: May lock [QUEUE].
+	fn on_max_retries() {
+		match config::on_max_retries_reached::get() {
+			On_MaxRetriesReached::Return => {
+				return;
+			},
+			On_MaxRetriesReached::WriteToDisk => {
+				write_queue_to_disk();
+			}
+			On_MaxRetriesReached::Abort => {
+				write_queue_to_disk();
+				
+				let queue_guard = QUEUE.lock();
+				let is_empty = queue_guard.is_empty();
+				drop(queue_guard);
+				
+				if is_empty == false {
+					abort_on_loss();
+				}
+			}
+		}
+	}
+	
+	/// WARNING: May lock [QUEUE].
+	fn write_queue_to_disk() {
+		let Ok(mut files) = config::log_targets::all()
+				else { return; };
+
+		let mut queue_guard = QUEUE.lock();
+
+		while !queue_guard.is_empty() {
+			let msg = &queue_guard[0];
+			let is_diagnostic = msg.is_diagnostic();
+			let write_result = config::log_targets::append(&mut files, msg);
+
+			match write_result {
+				Ok(_) => {
+					queue_guard.remove(0).set_written();
+					continue;
+				},
+				Err(err) => {
+					if is_diagnostic == false {
+						owned_try_insert_write_err(&mut queue_guard, &err, "`comfy_print::async_impl::on_max_retries_reached()`: Failed to write to log file.");
+					}
+					
+					break;
+				}
+			}
+		}
+
+		queue_guard.shrink_to_fit();
+		config::queue_warn_threshold::observe(queue_guard.len());
+		drop(queue_guard);
+		drop(files);
+	}
+	
+	/// Writes a last diagnostic straight to stderr, then aborts, see [On_MaxRetriesReached::Abort].
+	fn abort_on_loss() -> ! {
+		const DIAGNOSTIC: &[u8] = b"`comfy_print::async_impl::on_max_retries_reached()`: Messages could not be printed nor written to disk, aborting.\n";
+		
+		#[cfg(unix)]
+		signal_safe_write(DIAGNOSTIC, OutputKind::Stderr);
+		#[cfg(not(unix))]
+		let _ = std::io::stderr().write_all(DIAGNOSTIC);
+		
+		std::process::abort();
+	}
+}
+
+/// Writes `msg` from the queue. Non-blocking streams failing with [WouldBlock](std::io::ErrorKind::WouldBlock) are usually ready again right away, 
+/// so those failures are retried in place, up to [WOULD_BLOCK_RETRIES] times, instead of consuming [MAX_RETRIES](config::max_retries).
+/// 
+/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
+fn try_write_draining(msg: &Message) -> std::io::Result<()> {
+	let mut retries = 0;
+	
+	loop {
+		match try_write(msg, true) {
+			Err(err) if err.kind() == std::io::ErrorKind::WouldBlock && retries < WOULD_BLOCK_RETRIES => {
+				retries += 1;
+				thread::yield_now();
+			}
+			result => return result,
+		}
+	}
+}
+
+/// Writes `msg` bypassing [FLUSH_STRATEGY](config::flush_strategy), retrying up to [ESCALATION_RETRIES] times, see [Message::escalate_after()].
+/// 
+/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
+fn write_escalated(msg: &Message) -> std::io::Result<()> {
+	let mut retries = 0;
+	
+	loop {
+		match try_write(msg, false) {
+			Ok(()) => return Ok(()),
+			Err(err) if retries >= ESCALATION_RETRIES => return Err(err),
+			Err(_) => {
+				retries += 1;
+				thread::yield_now();
+			}
+		}
+	}
+}
+
+#[cfg(not(test))]
+/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
+fn try_write(msg: &Message, is_draining: bool) -> std::io::Result<()> { 
+	return write_to_stream(msg, is_draining);
+}
+
+#[cfg(test)]
+/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
+fn try_write(msg: &Message, is_draining: bool) -> std::io::Result<()> {
+	tests::WRITE_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+	
+	if tests::TOGGLE_WRITE_FAIL.load(Ordering::Relaxed) == true {
+		return Err(std::io::Error::other(tests::FORCE_WRITE_FAIL_MSG));
+	}
+	
+	let force_write_fail_result = tests::FORCE_WRITE_FAIL
+			.compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed);
+
+	if force_write_fail_result.is_ok() {
+		return Err(std::io::Error::other(tests::FORCE_WRITE_FAIL_MSG));
+	}
+	
+	let would_block_result = tests::WOULD_BLOCK_NEXT_WRITES
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| remaining.checked_sub(1));
+	
+	if would_block_result.is_ok() {
+		return Err(std::io::Error::new(std::io::ErrorKind::WouldBlock, tests::FORCE_WRITE_FAIL_MSG));
+	}
+	
+	let fail_next_result = tests::FAIL_NEXT_WRITES
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| remaining.checked_sub(1));
+	
+	if fail_next_result.is_ok() {
+		return Err(std::io::Error::other(tests::FORCE_WRITE_FAIL_MSG));
+	}
+
+	return write_to_stream(msg, is_draining);
+}
+
+/// `is_draining` is true when called while printing the queue, where [FLUSH_STRATEGY](config::flush_strategy) applies.
+/// 
+/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr]
+fn write_to_stream(msg: &Message, is_draining: bool) -> std::io::Result<()> {
+	let update_guard = config::update_lock::read();
+	let configs = StreamConfigs::read(msg);
+	let target = configs.target;
+	let should_flush = is_draining == false 
+			|| (config::flush_strategy::resolve(target) == FlushStrategy::EveryMessage && config::flush_every::tick());
+	
+	// The renderer may print.
+	drop(update_guard);
+	
+	let text = render::for_stream(msg, configs.annotate)?;
+	
+	// Written wherever the message goes, before it.
+	let banner = config::session_banner::take_first();
+	let redirected_text = match banner.as_deref() {
+		Some(banner) => Cow::Owned(format!("{banner}{text}")),
+		None => Cow::Borrowed(text.as_str()),
+	};
+	
+	// With DRY_RUN, redirected messages are recorded like any other.
+	let redirection = match configs.dry_run {
+		true => None,
+		false => redirection(msg, msg.route()),
+	};
+	
+	let redirected = match redirection {
+		Some(Destination::Route(sink_id)) => config::routes::write(&sink_id, &redirected_text, should_flush),
+		Some(Destination::Descriptor) => msg.descriptor().map(|descriptor| crate::descriptor::write(descriptor, &redirected_text, should_flush)),
+		Some(Destination::Tty) => crate::tty::write(&redirected_text, should_flush),
+		Some(Destination::Stream(_) | Destination::DryRun(_) | Destination::LogFile) | None => None,
+	};
+	
+	drop(redirected_text);
+	let is_redirected = redirected.is_some();
+	
+	if let Some(result) = redirected {
+		result.inspect_err(|_| config::session_banner::rearm())?;
+	} else {
+		if let Some(banner) = banner.as_deref() {
+			write_rendered(target, banner, should_flush, configs.dry_run)
+				.inspect_err(|_| config::session_banner::rearm())?;
+		}
+		
+		let progress_stream = match LAST_PROGRESS_STREAM.load(Ordering::Relaxed) {
+			1 => Some(OutputKind::Stdout),
+			2 => Some(OutputKind::Stderr),
+			_ => None,
+		};
+		
+		match progress_stream {
+			Some(stream) if msg.is_progress() == false && msg.is_verbatim() == false => {
+				if stream == target {
+					write_rendered(target, &format!("\n{text}"), should_flush, configs.dry_run)?;
+				} else {
+					// The progress line must be closed on its own stream, not on the one of this message.
+					write_rendered(stream, "\n", should_flush, configs.dry_run)?;
+					LAST_PROGRESS_STREAM.store(0, Ordering::Relaxed);
+					write_rendered(target, &text, should_flush, configs.dry_run)?;
+				}
+			}
+			_ => write_rendered(target, &text, should_flush, configs.dry_run)?,
+		}
+	}
+	
+	if configs.log_to_disk {
+		if let Ok(mut files) = config::log_targets::all() {
+			if let Some(banner) = banner {
+				let _ = config::log_targets::append(&mut files, &Message::verbatim(banner, msg.output_kind()));
+			}
+			
+			let _ = config::log_targets::append(&mut files, msg);
+		}
+	}
+	
+	#[cfg(feature = "subscribe")]
+	broadcast(msg);
+	
+	let mut recent_guard = RECENT.lock();
+	recent_guard.push_back((target, text));
+	if recent_guard.len() > RECENT_CAPACITY {
+		recent_guard.pop_front();
+	}
+	
+	drop(recent_guard);
+	
+	#[cfg(feature = "latency-metrics")]
+	crate::latency::record(msg.age());
+	
+	if target == OutputKind::Stderr && is_redirected == false && config::track_stderr_printed::get() {
+		STDERR_PRINTED.store(true, Ordering::Relaxed);
+	}
+	
+	let progress_stream = match (msg.is_progress(), target) {
+		(false, _) => 0,
+		(true, OutputKind::Stdout) => 1,
+		(true, OutputKind::Stderr) => 2,
+	};
+	
+	LAST_PROGRESS_STREAM.store(progress_stream, Ordering::Relaxed);
+	config::global_retry_budget::refill();
+	return Ok(());
+}
+
+/// Writes `s` to [std::io::stdout] | [std::io::stderr], then flushes it, and does nothing else.
+/// 
+/// Skips every part of [comfy_print](crate): the queue, [paused](pause()) state, configs and [MIDDLEWARE](config::middleware). 
+/// Meant as ground truth when troubleshooting [comfy_print](crate) itself.
+/// 
+/// WARNING: Will lock one of [std::io::stdout] | [std::io::stderr].
+pub fn raw_write(s: &str, kind: OutputKind) -> std::io::Result<()> {
+	#[cfg(test)]
+	tests::WRITTEN.lock().push((kind, s.to_string()));
+	
+	match kind {
+		OutputKind::Stdout => {
+			let mut stdout = std::io::stdout().lock();
+			stdout.write_all(s.as_bytes())?;
+			stdout.flush()?;
+			drop(stdout);
+		}
+		OutputKind::Stderr => {
+			let mut stderr = std::io::stderr().lock();
+			stderr.write_all(s.as_bytes())?;
+			stderr.flush()?;
+			drop(stderr);
+		}
+	}
+	
+	return Ok(());
+}
+
+/// Writes `bytes` straight to the file descriptor of `kind`, for emergency output from within a signal handler.
+/// 
+/// Unlike every other way of printing, this only calls `write(2)`, which is async-signal-safe: 
+/// it doesn't allocate, doesn't lock and bypasses the queue, [std::io::stdout]'s buffer and every config.
+/// 
+/// # Constraints
+/// 
+/// - `bytes` must be static, formatting allocates so it can't happen inside a signal handler.
+/// - Errors are ignored, there's no way to retry or report them from a signal handler.
+/// - May interleave with output being written by other threads.
+/// - Only available on unix.
+#[cfg(unix)]
+pub fn signal_safe_write(bytes: &'static [u8], kind: OutputKind) {
+	let fd = match kind {
+		OutputKind::Stdout => libc::STDOUT_FILENO,
+		OutputKind::Stderr => libc::STDERR_FILENO,
+	};
+	
+	write_all_to_fd(fd, bytes);
+}
+
+/// Calls `write(2)` until every byte is written or an error other than [Interrupted](std::io::ErrorKind::Interrupted) happens.
+#[cfg(unix)]
+fn write_all_to_fd(fd: std::os::fd::RawFd, mut bytes: &[u8]) {
+	while bytes.is_empty() == false {
+		// SAFETY: the pointer and length come from a valid slice, which `write` only reads during the call.
+		let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+		
+		if written > 0 {
+			bytes = &bytes[written as usize..];
+		} else if written < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+			continue;
+		} else {
+			return;
+		}
+	}
+}
+
+/// Where a message is written to, see [resolve_targets()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+	/// Written to [std::io::stdout] | [std::io::stderr].
+	Stream(OutputKind),
+	/// Written to the sink with this id, instead of its stream, see [ROUTES](config::routes).
+	Route(String),
+	/// Written to the file descriptor or handle given to `Message::to_fd()` | `Message::to_handle()`, instead of its stream.
+	Descriptor,
+	/// Written to the controlling terminal, instead of its stream, see [Message::to_tty()]. 
+	/// Still written to its stream if the process has no terminal.
+	Tty,
+	/// Stored in memory under this stream instead of being written, see [DRY_RUN](config::dry_run).
+	DryRun(OutputKind),
+	/// Appended to the files at [LOG_IO_PATH](config::log_io_path) and [LOG_TARGETS](config::log_targets) that match its [OutputKind].
+	LogFile,
+}
+
+/// Every destination `msg` would be written to if it was printed now, after applying the current routing configs: 
+/// [DRY_RUN](config::dry_run), [ROUTES](config::routes), [USE_CONTROLLING_TTY](config::use_controlling_tty), 
+/// [COMBINED_STREAM](config::combined_stream) and [ALWAYS_LOG_TO_DISK](config::always_log_to_disk).
+/// 
+/// - Useful for debugging routing configs.
+/// - Messages without a route yet are matched against [ROUTES](config::routes), like printing them would.
+/// - Doesn't account for failures, e.g. [ON_MAX_RETRIES_REACHED](config::on_max_retries_reached) writing the queue to disk, 
+///   or the process having no terminal.
+pub fn resolve_targets(msg: &Message) -> Vec<Destination> {
+	if config::dry_run::get() {
+		return vec![Destination::DryRun(target_stream(msg))];
+	}
+	
+	// Printing resolves the route before queueing the message, which may not have happened yet.
+	let resolved = match msg.route() {
+		Some(_) => None,
+		None => config::routes::resolve(msg),
+	};
+	
+	let route = msg.route().or(resolved.as_deref());
+	let mut destinations = vec![redirection(msg, route).unwrap_or(Destination::Stream(target_stream(msg)))];
+	
+	if config::always_log_to_disk::get() == Some(msg.output_kind()) {
+		destinations.push(Destination::LogFile);
+	}
+	
+	return destinations;
+}
+
+/// Where `msg` is written instead of its stream, if anywhere: [Route](Destination::Route) (to `route`), 
+/// [Descriptor](Destination::Descriptor) or [Tty](Destination::Tty), checked in that order.
+/// 
+/// - Shared by [write_to_stream()] and [resolve_targets()], so that they agree.
+/// - Doesn't account for [DRY_RUN](config::dry_run), under which redirected messages are recorded like any other.
+/// 
+/// WARNING: May lock the sinks of [ROUTES](config::routes).
+fn redirection(msg: &Message, route: Option<&str>) -> Option<Destination> {
+	return match route {
+		Some(sink_id) if config::routes::is_set(sink_id) => Some(Destination::Route(sink_id.to_string())),
+		_ if msg.descriptor().is_some() => Some(Destination::Descriptor),
+		_ if msg.is_tty() && config::use_controlling_tty::get() => Some(Destination::Tty),
+		_ => None,
+	};
+}
+
+/// The stream [Message] will be written to, after applying [COMBINED_STREAM](config::combined_stream).
+fn target_stream(msg: &Message) -> OutputKind {
+	return config::combined_stream::get().unwrap_or(msg.output_kind());
+}
+
+/// The configs deciding where and how a message is written, read together so that a group of changes 
+/// made through [apply()](config::apply) affects a whole write or none of it.
+struct StreamConfigs {
+	/// See [target_stream()].
+	target: OutputKind,
+	/// [ANNOTATE_STREAM](config::annotate_stream), which only applies while [COMBINED_STREAM](config::combined_stream) is set.
+	annotate: bool,
+	/// [DRY_RUN](config::dry_run).
+	dry_run: bool,
+	/// Whether the message is also appended to disk, see [ALWAYS_LOG_TO_DISK](config::always_log_to_disk).
+	log_to_disk: bool,
+}
+
+impl StreamConfigs {
+	/// Must be called while holding [UPDATE_LOCK](config::update_lock) for reading.
+	fn read(msg: &Message) -> Self {
+		let combined_stream = config::combined_stream::get();
+		let dry_run = config::dry_run::get();
+		
+		return Self {
+			target: combined_stream.unwrap_or(msg.output_kind()),
+			annotate: config::annotate_stream::get() && combined_stream.is_some(),
+			dry_run,
+			log_to_disk: config::always_log_to_disk::get() == Some(msg.output_kind()) && dry_run == false,
+		};
+	}
+}
+
+/// `dry_run` stores `rendered` instead of writing it, see [DRY_RUN](config::dry_run).
+/// 
+/// WARNING: May lock [SHARED_STREAM_LOCK], then will lock one of [STDOUT_SINK] | [std::io::stderr].
+fn write_rendered(target: OutputKind, rendered: &str, should_flush: bool, dry_run: bool) -> std::io::Result<()> {
+	#[cfg(test)]
+	tests::WRITTEN.lock().push((target, rendered.to_string()));
+	
+	if dry_run {
+		DRY_RUN_OUTPUT.lock().push((target, rendered.to_string()));
+		return Ok(());
+	}
+	
+	// Each stream has its own lock, which doesn't stop a stdout write from interleaving with a stderr write to the same file.
+	let shared_guard = streams_share_file().then(|| SHARED_STREAM_LOCK.lock());
+	
+	#[cfg(test)]
+	if should_flush {
+		tests::FLUSHED.lock().push(target);
+	}
+	
+	match target {
+		OutputKind::Stdout => {
+			let mut sink_guard = STDOUT_SINK.lock();
+			match sink_guard.as_mut() {
+				Some(sink) => write_and_flush(sink, rendered, should_flush)?,
+				None => {
+					let mut stdout = std::io::stdout().lock();
+					#[cfg(windows)]
+					let rendered = crate::win_console::clear_for_progress(&mut stdout, rendered);
+					write_and_flush(&mut stdout, rendered, should_flush)?;
+					drop(stdout);
+				}
+			}
+			
+			drop(sink_guard);
+		}
+		OutputKind::Stderr => write_and_flush(&mut std::io::stderr().lock(), rendered, should_flush)?,
+	}
+	
+	drop(shared_guard);
+	return Ok(());
+}
+
+fn write_and_flush(writer: &mut dyn Write, rendered: &str, should_flush: bool) -> std::io::Result<()> {
+	write_all_bounded(writer, rendered.as_bytes())?;
+	if should_flush {
+		writer.flush()?;
+	}
+	
+	return Ok(());
+}
+
+/// How many consecutive writes may accept zero bytes before [write_all_bounded()] considers the writer stuck.
+const MAX_ZERO_WRITES: usize = 8;
+
+/// Like [Write::write_all], except that a writer accepting zero bytes is retried up to [MAX_ZERO_WRITES] times in a row
+/// before failing with [WriteZero](std::io::ErrorKind::WriteZero), which is then handled like any other write failure.
+fn write_all_bounded(writer: &mut dyn Write, mut bytes: &[u8]) -> std::io::Result<()> {
+	let mut zero_writes = 0;
+	
+	while bytes.is_empty() == false {
+		match writer.write(bytes) {
+			Ok(0) => {
+				zero_writes += 1;
+				if zero_writes >= MAX_ZERO_WRITES {
+					return Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "`comfy_print::async_impl::write_all_bounded()`: The writer stopped accepting bytes."));
+				}
+			}
+			Ok(written) => {
+				bytes = &bytes[written..];
+				zero_writes = 0;
+			}
+			Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+			Err(err) => return Err(err),
+		}
+	}
+	
+	return Ok(());
+}
+
+/// Flushes the [sink](swap_stdout_sink()) of messages meant for [std::io::stdout].
+/// 
+/// WARNING: Will lock [STDOUT_SINK], then may lock [std::io::stdout].
+fn flush_stdout() -> std::io::Result<()> {
+	let mut sink_guard = STDOUT_SINK.lock();
+	let result = match sink_guard.as_mut() {
+		Some(sink) => sink.flush(),
+		None => std::io::stdout().flush(),
+	};
+	
+	drop(sink_guard);
+	return result;
+}
+
+/// Held while writing to either stream, if [streams_share_file()].
+static SHARED_STREAM_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+/// Whether [std::io::stdout] and [std::io::stderr] refer to the same file (e.g. the same terminal or pipe), detected once then cached.
+/// 
+/// Always `false` on non-unix platforms.
+fn streams_share_file() -> bool {
+	#[cfg(test)]
+	if tests::FORCE_STREAMS_SHARE_FILE.load(Ordering::Relaxed) {
+		return true;
+	}
+	
+	static STREAMS_SHARE_FILE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+	return *STREAMS_SHARE_FILE.get_or_init(detect);
+	
+	#[cfg(unix)]
+	fn detect() -> bool {
+		use std::os::fd::AsFd;
+		return is_same_file(std::io::stdout().as_fd(), std::io::stderr().as_fd());
+	}
+	
+	#[cfg(not(unix))]
+	fn detect() -> bool {
+		return false;
+	}
+}
+
+/// Compares the device and inode of both file descriptors, `false` if either can't be inspected.
+#[cfg(unix)]
+fn is_same_file(left: std::os::fd::BorrowedFd, right: std::os::fd::BorrowedFd) -> bool {
+	use std::os::unix::fs::MetadataExt;
+	
+	let metadata = |fd: std::os::fd::BorrowedFd| fd
+		.try_clone_to_owned()
+		.map(std::fs::File::from)
+		.and_then(|file| file.metadata());
+	
+	return match (metadata(left), metadata(right)) {
+		(Ok(left), Ok(right)) => left.dev() == right.dev() && left.ino() == right.ino(),
+		_ => false,
+	};
+}
+
+/// Writes [BATCH_PREAMBLE](config::batch_preamble) | [BATCH_POSTAMBLE](config::batch_postamble), failures are ignored.
+fn write_batch_marker(get_marker: fn(&mut String), target: OutputKind) {
+	let mut marker = String::new();
+	get_marker(&mut marker);
+	if marker.is_empty() {
+		return;
+	}
+	
+	let _ = write_rendered(target, &marker, true, config::dry_run::get());
+}
+
+/// Writes [BATCH_SEPARATOR](config::batch_separator) to `target` if more messages are waiting in the queue.
+/// 
+/// WARNING: Will lock [QUEUE], then may lock one of [std::io::stdout] | [std::io::stderr].
+fn write_batch_separator(target: OutputKind) {
+	let queue_guard = QUEUE.lock();
+	let is_empty = queue_guard.is_empty();
+	drop(queue_guard);
+	
+	if is_empty == false {
+		write_batch_marker(config::batch_separator::get, target);
+	}
+}
+
+/// WARNING: Will lock [QUEUE]
+#[cfg(not(any(feature = "no-diagnostics", feature = "single-thread")))]
+#[inline(always)]
+fn try_insert_write_err(err: &std::io::Error, call_description: &'static str) {
+	if config::allow_logging_print_failures::get() == false {
+		return;
+	}
+	
+	let mut queue_guard: MutexGuard<RawFairMutex, Vec<Message>> = QUEUE.lock();
+	insert_diagnostic(&mut queue_guard, err, call_description);
+	drop(queue_guard);
+}
+
+/// Diagnostics are compiled out by feature **no-diagnostics**.
+#[cfg(all(feature = "no-diagnostics", not(feature = "single-thread")))]
+#[inline(always)]
+fn try_insert_write_err(_err: &std::io::Error, _call_description: &'static str) {}
+
+/// WARNING: does not lock anything since this receives a mutable reference to a queue.
+#[cfg(not(feature = "no-diagnostics"))]
+#[inline(always)]
+fn owned_try_insert_write_err(queue_guard: &mut MutexGuard<RawFairMutex, Vec<Message>>, err: &std::io::Error, call_description: &'static str) {
+	if config::allow_logging_print_failures::get() == false {
+		return;
+	}
+
+	insert_diagnostic(queue_guard, err, call_description);
+}
+
+/// Inserts a diagnostic describing `err` at the front of `queue`, 
+/// unless it's full or already holds [MAX_DIAGNOSTICS_IN_QUEUE](config::max_diagnostics_in_queue) diagnostics.
+#[cfg(not(feature = "no-diagnostics"))]
+fn insert_diagnostic(queue: &mut Vec<Message>, err: &std::io::Error, call_description: &'static str) {
+	if queue.len() >= config::max_queue_length::effective() {
+		return;
+	}
+	
+	let max_diagnostics = config::max_diagnostics_in_queue::get();
+	if max_diagnostics != usize::MAX && queue.iter().filter(|msg| msg.is_diagnostic()).count() >= max_diagnostics {
+		saturating_increment(&SUPPRESSED_DIAGNOSTICS);
+		return;
+	}
+	
+	queue.insert(0, Message::diagnostic(format!("{call_description}\nError: {err}.")));
+}
+
+/// Diagnostics are compiled out by feature **no-diagnostics**.
+#[cfg(feature = "no-diagnostics")]
+#[inline(always)]
+fn owned_try_insert_write_err(_queue_guard: &mut MutexGuard<RawFairMutex, Vec<Message>>, _err: &std::io::Error, _call_description: &'static str) {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	pub(crate) static FORCE_WRITE_FAIL: AtomicBool = AtomicBool::new(false);
+	/// Makes [streams_share_file()] true regardless of what it detected, since the streams of the test process can't be swapped.
+	pub(crate) static FORCE_STREAMS_SHARE_FILE: AtomicBool = AtomicBool::new(false);
+	pub(crate) static TOGGLE_WRITE_FAIL: AtomicBool = AtomicBool::new(false);
+	pub(crate) static WRITE_ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+	/// How many of the next writes will fail.
+	pub(crate) static FAIL_NEXT_WRITES: AtomicUsize = AtomicUsize::new(0);
+	/// How many of the next writes will fail with [WouldBlock](std::io::ErrorKind::WouldBlock).
+	pub(crate) static WOULD_BLOCK_NEXT_WRITES: AtomicUsize = AtomicUsize::new(0);
+	/// How many of the next attempts to spawn the printing thread will fail.
+	pub(crate) static FAIL_NEXT_SPAWNS: AtomicUsize = AtomicUsize::new(0);
+	/// Every message successfully rendered by [write_to_stream], in order.
+	pub(crate) static WRITTEN: parking_lot::Mutex<Vec<(OutputKind, String)>> = parking_lot::Mutex::new(Vec::new());
+	/// The stream of every message flushed right after being written by [write_rendered], in order.
+	pub(crate) static FLUSHED: parking_lot::Mutex<Vec<OutputKind>> = parking_lot::Mutex::new(Vec::new());
+	pub const FORCE_WRITE_FAIL_MSG: &str = "Forced write failure";
+
+	use std::sync::atomic::{AtomicBool, AtomicUsize};
+	use crate::{comfy_eprint, comfy_eprintln, comfy_print, comfy_println};
+	use super::*;
+	use crate::config::drop_channel::DropEvent;
+	use crate::test_utils;
+	
+	#[test]
+	fn test_scope() {
+		let _guard = test_utils::lock_tests();
+		
+		comfy_println!("Test_01");
+		
+		{
+			let _outer = scope();
+			comfy_println!("Test_02");
+			
+			{
+				let _inner = scope();
+				assert_eq!(scope_depth(), 2);
+				comfy_println!("Test_03\nTest_04");
+			}
+			
+			comfy_eprintln!("Test_05");
+		}
+		
+		comfy_println!("Test_06");
+		assert_eq!(scope_depth(), 0);
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stdout, "  Test_02\n".to_string()),
+			(OutputKind::Stdout, "    Test_03\n    Test_04\n".to_string()),
+			(OutputKind::Stderr, "  Test_05\n".to_string()),
+			(OutputKind::Stdout, "Test_06\n".to_string()),
+		]);
+	}
+	
+	#[test]
+	fn test_flush_if_idle() {
+		let _guard = test_utils::lock_tests();
+		config::allow_logging_print_failures::set(false);
+		config::on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		
+		{
+			test_utils::set_toggle_write_fail(true);
+			comfy_println!("Test_01");
+			test_utils::yield_until_idle();
+			test_utils::set_toggle_write_fail(false);
+			
+			assert_eq!(flush_if_idle(std::time::Duration::from_secs(60)), false);
+			assert_eq!(test_utils::get_queue().len(), 1);
+		}
+		
+		{
+			thread::sleep(std::time::Duration::from_millis(20));
+			assert_eq!(flush_if_idle(std::time::Duration::from_millis(10)), true);
+			assert!(test_utils::get_queue().is_empty());
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+		}
+	}
+	
+	#[test]
+	fn test_forward_rendered() {
+		let _guard = test_utils::lock_tests();
+		
+		#[derive(Clone, Default)]
+		struct CapturingSink(std::sync::Arc<parking_lot::Mutex<Vec<u8>>>);
+		
+		impl Write for CapturingSink {
+			fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+				self.0.lock().extend_from_slice(buf);
+				return Ok(buf.len());
+			}
+			
+			fn flush(&mut self) -> std::io::Result<()> {
+				return Ok(());
+			}
+		}
+		
+		let captured = CapturingSink::default();
+		config::indent::set(2);
+		
+		{
+			swap_stdout_sink(Box::new(captured.clone()));
+			comfy_println!("Test_01\nTest_02");
+			test_utils::take_written();
+		}
+		
+		{
+			config::middleware::add(Box::new(|msg| msg.with_str("Middleware")));
+			swap_stdout_sink(Box::new(std::io::sink()));
+			
+			let bytes = std::mem::take(&mut *captured.0.lock());
+			forward_rendered(bytes, OutputKind::Stdout);
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "  Test_01\n  Test_02\n".to_string())]);
+			
+			forward_rendered(vec![b'T', 0xFF], OutputKind::Stderr);
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, "T\u{FFFD}".to_string())]);
+		}
+	}
+	
+	#[test]
+	fn test_capture() {
+		let _guard = test_utils::lock_tests();
+		
+		let captured = capture(|| {
+			comfy_println!("Test_01");
+			comfy_eprint!("Test_02");
+			
+			let inner = capture(|| comfy_println!("Test_03"));
+			assert_eq!(inner, vec![(OutputKind::Stdout, "Test_03\n".to_string())]);
+			
+			comfy_println!("Test_04");
+			
+			println_slice(&["Test_05", "Test_06"], OutputKind::Stderr);
+			assert_eq!(enqueue_many(vec![Message::standard("Test_07")]), (1, 0));
+			thread_buffer_print("Test_08", OutputKind::Stdout);
+			thread_flush();
+		});
+		
+		assert_eq!(captured, vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stderr, "Test_02".to_string()),
+			(OutputKind::Stdout, "Test_04\n".to_string()),
+			(OutputKind::Stderr, "Test_05\n".to_string()),
+			(OutputKind::Stderr, "Test_06\n".to_string()),
+			(OutputKind::Stdout, "Test_07".to_string()),
+			(OutputKind::Stdout, "Test_08".to_string()),
+		]);
+		
+		test_utils::yield_until_idle();
+		assert!(test_utils::take_written().is_empty());
+		
+		let panicked = std::panic::catch_unwind(|| capture(|| panic!("Test_09")));
+		assert!(panicked.is_err());
+		
+		comfy_println!("Test_10");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_10\n".to_string())]);
+	}
+	
+	#[test]
+	fn test_capture_transcript() {
+		let _guard = test_utils::lock_tests();
+		
+		let print_all = || {
+			comfy_println!("Test_01");
+			comfy_eprintln!("Test_02\nTest_03");
+			comfy_print!("Test_04");
+			comfy_eprint!("Test_05");
+			comfy_println!();
+		};
+		
+		assert_eq!(capture_transcript(print_all), "Test_01\n[stderr] Test_02\n[stderr] Test_03\nTest_04[stderr] Test_05\n");
+		
+		config::transcript_stderr_prefix::set("");
+		assert_eq!(capture_transcript(print_all), "Test_01\nTest_02\nTest_03\nTest_04Test_05\n");
+		
+		config::transcript_stderr_prefix::set("E| ");
+		assert_eq!(capture_transcript(|| comfy_eprintln!("Test_06")), "E| Test_06\n");
+		assert!(test_utils::take_written().is_empty());
+	}
+	
+	#[test]
+	fn test_log_err() {
+		let _guard = test_utils::lock_tests();
+		
+		assert_eq!(log_err(Ok::<_, String>(5)), Some(5));
+		assert_eq!(log_err(Err::<i32, _>("Test message_1")), None);
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, "Test message_1\n".to_string())]);
+		
+		config::show_location::set(true);
+		assert_eq!(crate::comfy_log_err!(Ok::<_, String>("Test message_2")), Some("Test message_2"));
+		assert_eq!(crate::comfy_log_err!("-".parse::<u32>()), None);
+		test_utils::yield_until_idle();
+		
+		let written = test_utils::take_written();
+		assert_eq!(written.len(), 1);
+		assert!(written[0].1.starts_with(concat!("[", file!(), ":")));
+		assert!(written[0].1.ends_with("] invalid digit found in string\n"));
+	}
+	
+	#[test]
+	fn test_when_queue_is_empty() {
+		let _guard = test_utils::lock_tests();
+		comfy_println!("Test message");
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+
+	#[test]
+	#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+	fn test_when_queue_is_not_empty() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		
+		test_utils::set_toggle_write_fail(true);
+		comfy_println!("Test message_1");
+		comfy_println!("Test message_2");
+		comfy_println!("Test message_3");
+		
+		assert_eq!(STATE.lock().is_busy(), true);
+		
+		test_utils::yield_until_idle();
+		assert_eq!(STATE.lock().is_busy(), false);
+		assert_eq!(test_utils::get_queue().len(), 3);
+
+		test_utils::set_toggle_write_fail(false);
+		comfy_println!("Test message_4");
+		assert_eq!(STATE.lock().is_busy(), true);
+		
+		test_utils::yield_until_idle();
+		assert_eq!(STATE.lock().is_busy(), false);
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+
+	#[test]
+	#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+	fn test_warmup() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+
+		warmup().unwrap();
+		let thread_id = match &*STATE.lock() {
+			PrintingState::Parked(handle) => {
+				assert_eq!(handle.is_finished(), false);
+				handle.thread().id()
+			},
+			_ => panic!("warmup() did not park a thread."),
+		};
+
+		// Calling it again must not replace the parked thread.
+		warmup().unwrap();
+		assert!(matches!(&*STATE.lock(), PrintingState::Parked(handle) if handle.thread().id() == thread_id));
+
+		test_utils::write_fail_once();
+		comfy_println!("Test message_1");
+		assert!(matches!(&*STATE.lock(), PrintingState::Threaded(handle) if handle.thread().id() == thread_id));
+
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+
+	#[test]
+	#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+	fn test_printer_idle_timeout() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		config::printer_idle_timeout::set(std::time::Duration::from_millis(300));
+
+		test_utils::write_fail_once();
+		comfy_println!("Test message_1");
+		test_utils::yield_until_idle();
+		
+		let thread_id = match &*STATE.lock() {
+			PrintingState::Parked(handle) => {
+				assert_eq!(handle.is_finished(), false);
+				handle.thread().id()
+			},
+			_ => panic!("The thread did not linger after printing the queue."),
+		};
+
+		// The lingering thread is woken up instead of spawning a new one.
+		test_utils::write_fail_once();
+		comfy_println!("Test message_2");
+		test_utils::yield_until_idle();
+		assert!(matches!(&*STATE.lock(), PrintingState::Parked(handle) if handle.thread().id() == thread_id));
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stdout, "Test message_2\n".to_string()),
+		]);
+
+		thread::sleep(std::time::Duration::from_millis(600));
+		assert!(matches!(&*STATE.lock(), PrintingState::Idle));
+	}
+
+	#[test]
+	fn test_replay_spill() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		config::max_retries::set(0);
+		config::max_queue_length::set(2);
+		config::on_queue_full::set(On_QueueFull::Spill);
+		
+		let path = test_utils::temp_path("comfy_print_test_replay_spill.txt");
+		let path = path.as_str();
+		let _ = std::fs::remove_file(path);
+		config::log_io_path::set(path).unwrap();
+		let spilled_lines = || std::fs::read_to_string(path).map(|contents| contents.lines().count() - 2).unwrap_or(0);
+		
+		// Rendered lines shaped like spilled messages, which must stay in the file.
+		let mut files = config::log_targets::all().unwrap();
+		config::log_targets::append(&mut files, &Message::standard_ln("0 1 Test rendered_1")).unwrap();
+		config::log_targets::append(&mut files, &Message::standard_ln(format!("{SPILL_MARKER}0 1 Test rendered_2"))).unwrap();
+		drop(files);
+		
+		test_utils::set_toggle_write_fail(true);
+		for index in 1..=5 {
+			comfy_println!("Test message_{index}");
+		}
+		
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 2);
+		assert_eq!(spilled_lines(), 3);
+		assert_eq!(dropped_count(), 0);
+		
+		// No room in the queue.
+		assert_eq!(replay_spill().unwrap(), 0);
+		assert_eq!(spilled_lines(), 3);
+		
+		config::max_queue_length::set(3);
+		assert_eq!(replay_spill().unwrap(), 1);
+		test_utils::yield_until_idle();
+		assert_eq!(spilled_lines(), 2);
+		
+		config::max_queue_length::set(8);
+		assert_eq!(replay_spill().unwrap(), 2);
+		test_utils::yield_until_idle();
+		assert_eq!(replay_spill().unwrap(), 0);
+		assert_eq!(std::fs::read_to_string(path).unwrap(), format!("0 1 Test rendered_1\n{SPILL_MARKER}{SPILL_MARKER}0 1 Test rendered_2\n"));
+		std::fs::remove_file(path).unwrap();
+		assert_eq!(replay_spill().unwrap(), 0);
+		
+		test_utils::set_toggle_write_fail(false);
+		comfy_println!("Test message_6");
+		test_utils::yield_until_idle();
+		
+		let written: Vec<String> = test_utils::take_written().into_iter().map(|(_, text)| text).collect();
+		assert_eq!(written, (1..=6).map(|index| format!("Test message_{index}\n")).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn test_per_message_fail_policy() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		config::on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		config::max_retries::set(5);
+		test_utils::set_toggle_write_fail(true);
+
+		{
+			test_utils::reset_write_attempts();
+			comfy_print_async(Message::standard_ln("Test message_1"));
+			test_utils::yield_until_idle();
+
+			// First attempt on the caller, then a single attempt on the printing thread.
+			assert_eq!(test_utils::write_attempts(), 2);
+			test_utils::get_queue().clear();
+		}
+
+		{
+			test_utils::reset_write_attempts();
+			comfy_print_async(Message::standard_ln("Test message_2").with_fail_policy(On_QueuePrintingFail::TryUntilMaxRetries));
+			test_utils::yield_until_idle();
+
+			// First attempt on the caller, then the first attempt + 5 retries on the printing thread.
+			assert_eq!(test_utils::write_attempts(), 7);
+			assert_eq!(test_utils::get_queue().len(), 1);
+		}
+	}
+
+	#[test]
+	fn test_diagnostics() {
+		let _guard = test_utils::lock_tests();
+		config::max_retries::set(0);
+
+		test_utils::set_toggle_write_fail(true);
+		comfy_println!("Test message_1");
+		test_utils::yield_until_idle();
+
+		let queue = test_utils::get_queue();
+		let diagnostics_count = queue.iter().filter(|msg| msg.str().contains(tests::FORCE_WRITE_FAIL_MSG)).count();
+		if cfg!(feature = "no-diagnostics") {
+			assert_eq!(queue.len(), 1);
+			assert_eq!(diagnostics_count, 0);
+		} else {
+			assert!(queue.len() > 1);
+			assert_eq!(diagnostics_count, queue.len() - 1);
+		}
+		
+		drop(queue);
+		
+		test_utils::set_toggle_write_fail(false);
+		comfy_println!("Test message_2");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+
+	#[test]
+	fn test_max_diagnostics_in_queue() {
+		let _guard = test_utils::lock_tests();
+		config::max_diagnostics_in_queue::set(3);
+		
+		pause();
+		comfy_println!("Test message_1");
+		
+		let err = std::io::Error::other(FORCE_WRITE_FAIL_MSG);
+		for _ in 0..10 {
+			owned_try_insert_write_err(&mut test_utils::get_queue(), &err, "Test failure");
+		}
+		
+		let queue = test_utils::get_queue();
+		let diagnostics_count = queue.iter().filter(|msg| msg.is_diagnostic()).count();
+		assert_eq!(queue.len() - diagnostics_count, 1);
+		drop(queue);
+		
+		if cfg!(feature = "no-diagnostics") {
+			assert_eq!(diagnostics_count, 0);
+		} else {
+			assert_eq!(diagnostics_count, 3);
+			assert_eq!(suppressed_diagnostics_count(), 7);
+		}
+	}
+	
+	#[test]
+	#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+	fn test_print_progress() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+
+		{
+			print_progress("50%");
+			print_progress("100%");
+			comfy_println!("Done");
+			comfy_println!("Test message_1");
+			
+			assert_eq!(test_utils::take_written(), vec![
+				(OutputKind::Stdout, "\r50%".to_string()),
+				(OutputKind::Stdout, "\r100%".to_string()),
+				(OutputKind::Stdout, "\nDone\n".to_string()),
+				(OutputKind::Stdout, "Test message_1\n".to_string()),
+			]);
+		}
+
+		{
+			test_utils::write_fail_once();
+			print_progress("10%");
+			assert_eq!(test_utils::get_queue().len(), 1);
+
+			test_utils::yield_until_idle();
+			comfy_eprintln!("Test message_2");
+			
+			assert_eq!(test_utils::take_written(), vec![
+				(OutputKind::Stdout, "\r10%".to_string()),
+				(OutputKind::Stdout, "\n".to_string()),
+				(OutputKind::Stderr, "Test message_2\n".to_string()),
+			]);
+		}
+	}
+
+	#[test]
+	fn test_no_diagnostics_about_diagnostics() {
+		let _guard = test_utils::lock_tests();
+		config::max_retries::set(3);
+
+		test_utils::set_toggle_write_fail(true);
+		comfy_print_async(Message::diagnostic("Test diagnostic_1"));
+		test_utils::yield_until_idle();
+
+		let queue = test_utils::get_queue();
+		assert_eq!(queue.len(), 1);
+		assert_eq!(queue[0].str(), "Test diagnostic_1");
+		assert_eq!(queue[0].is_diagnostic(), true);
+		drop(queue);
+
+		test_utils::set_toggle_write_fail(false);
+		comfy_println!("Test message_1");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+
+	#[test]
+	fn test_pause() {
+		let _guard = test_utils::lock_tests();
+
+		pause();
+		assert_eq!(is_paused(), true);
+		
+		comfy_println!("Test message_1");
+		comfy_eprintln!("Test message_2");
+		comfy_println!("Test message_3");
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::get_queue().len(), 3);
+		assert!(test_utils::take_written().is_empty());
+
+		resume();
+		assert_eq!(is_paused(), false);
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::get_queue().len(), 0);
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stderr, "Test message_2\n".to_string()),
+			(OutputKind::Stdout, "Test message_3\n".to_string()),
+		]);
+	}
+	
+	#[test]
+	fn test_batch_markers() {
+		let _guard = test_utils::lock_tests();
+		config::batch_preamble::set("::group::Output\n");
+		config::batch_postamble::set("::endgroup::\n");
+		
+		// Direct writes are not part of a pass
+		comfy_println!("Test message_0");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test message_0\n".to_string())]);
+		
+		pause();
+		comfy_println!("Test message_1");
+		comfy_eprintln!("Test message_2");
+		comfy_println!("Test message_3");
+		resume();
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "::group::Output\n".to_string()),
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stderr, "Test message_2\n".to_string()),
+			(OutputKind::Stdout, "Test message_3\n".to_string()),
+			(OutputKind::Stdout, "::endgroup::\n".to_string()),
+		]);
+	}
+	
+	#[test]
+	fn test_dedup_key() {
+		let _guard = test_utils::lock_tests();
+		
+		comfy_print_async(Message::standard_ln("Connection lost (attempt 1)").with_dedup_key(7));
+		comfy_print_async(Message::standard_ln("Connection lost (attempt 2)").with_dedup_key(7));
+		comfy_print_async(Message::standard_ln("Disk full").with_dedup_key(8));
+		comfy_println!("No key");
+		comfy_println!("No key");
+		test_utils::yield_until_idle();
+		
+		assert_eq!(suppressed_count(), 1);
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Connection lost (attempt 1)\n".to_string()),
+			(OutputKind::Stdout, "Disk full\n".to_string()),
+			(OutputKind::Stdout, "No key\n".to_string()),
+			(OutputKind::Stdout, "No key\n".to_string()),
+		]);
+		
+		// Keys fall out of the window once enough newer keys are seen.
+		for key in 100..(100 + DEDUP_WINDOW as u64) {
+			comfy_print_async(Message::standard("").with_dedup_key(key));
+		}
+		
+		comfy_print_async(Message::standard_ln("Connection lost (attempt 3)").with_dedup_key(7));
+		test_utils::yield_until_idle();
+		assert_eq!(suppressed_count(), 1);
+		assert_eq!(test_utils::take_written().last(), Some(&(OutputKind::Stdout, "Connection lost (attempt 3)\n".to_string())));
+	}
+	
+	#[test]
+	fn test_install_panic_hook() {
+		let _guard = test_utils::lock_tests();
+		let original_hook = std::panic::take_hook();
+		
+		std::panic::set_hook(Box::new(|_| {}));
+		install_panic_hook();
+		
+		let result = thread::Builder::new()
+			.name("panicking".to_string())
+			.spawn(|| panic!("Test panic"))
+			.unwrap()
+			.join();
+		
+		drop(std::panic::take_hook());
+		std::panic::set_hook(original_hook);
+		
+		assert!(result.is_err());
+		test_utils::yield_until_idle();
+		
+		let written = test_utils::take_written();
+		assert_eq!(written.len(), 1);
+		assert_eq!(written[0].0, OutputKind::Stderr);
+		assert!(written[0].1.starts_with("thread 'panicking' panicked at "));
+		assert!(written[0].1.contains("Test panic\n"));
+	}
+	
+	#[test]
+	fn test_persist_queue() {
+		let _guard = test_utils::lock_tests();
+		let path = test_utils::temp_path("comfy_print_test_persist_queue.txt");
+		let _ = std::fs::remove_file(&path);
+		config::persist_queue_path::set(&path);
+		
+		pause();
+		comfy_println!("Test message_1");
+		comfy_eprint!("Test \\ message_2\n\r");
+		comfy_println!("Test message_3");
+		
+		assert_eq!(persist_queue().unwrap(), 3);
+		assert_eq!(test_utils::get_queue().len(), 0);
+		
+		// Simulate a restart
+		test_utils::reset_globals();
+		config::persist_queue_path::set(&path);
+		
+		assert_eq!(restore_queue().unwrap(), 3);
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::get_queue().len(), 0);
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stderr, "Test \\ message_2\n\r".to_string()),
+			(OutputKind::Stdout, "Test message_3\n".to_string()),
+		]);
+		
+		// The file is deleted once restored
+		assert_eq!(restore_queue().unwrap(), 0);
+		assert_eq!(std::path::Path::new(&path).exists(), false);
+	}
+	
+	#[test]
+	fn test_recent() {
+		let _guard = test_utils::lock_tests();
+		
+		comfy_println!("Test message_1");
+		comfy_eprintln!("Test message_2");
+		comfy_println!("Test message_3");
+		test_utils::yield_until_idle();
+		
+		let chronological = vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stderr, "Test message_2\n".to_string()),
+			(OutputKind::Stdout, "Test message_3\n".to_string()),
+		];
+		
+		assert_eq!(recent(), chronological);
+		
+		config::emit_order::set(EmitOrder::NewestFirst);
+		assert_eq!(recent(), chronological.iter().rev().cloned().collect::<Vec<_>>());
+		
+		// Writing to the streams stays chronological
+		assert_eq!(test_utils::take_written(), chronological);
+		
+		for _ in 0..RECENT_CAPACITY {
+			comfy_println!("Filler");
+		}
+		
+		assert_eq!(recent().len(), RECENT_CAPACITY);
+		assert!(recent().iter().all(|(_, text)| text == "Filler\n"));
+	}
+	
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_recent_json() {
+		let _guard = test_utils::lock_tests();
+		
+		comfy_println!("Test message_1");
+		comfy_eprintln!("Test \"message\"_2");
+		comfy_println!("Test message_3");
+		test_utils::yield_until_idle();
+		
+		let parsed: serde_json::Value = serde_json::from_str(&recent_json(2)).unwrap();
+		assert_eq!(parsed, serde_json::json!([
+			{ "stream": "Stderr", "text": "Test \"message\"_2\n" },
+			{ "stream": "Stdout", "text": "Test message_3\n" },
+		]));
+		
+		config::emit_order::set(EmitOrder::NewestFirst);
+		let parsed: serde_json::Value = serde_json::from_str(&recent_json(1)).unwrap();
+		assert_eq!(parsed, serde_json::json!([{ "stream": "Stdout", "text": "Test message_3\n" }]));
+		
+		assert_eq!(recent_json(0), "[]");
+		assert_eq!(serde_json::from_str::<Vec<serde_json::Value>>(&recent_json(usize::MAX)).unwrap().len(), 3);
+	}
+	
+	#[test]
+	fn test_println_slice() {
+		let _guard = test_utils::lock_tests();
+		
+		let lines: Vec<String> = (0..200).map(|index| format!("Line_{index:03}")).collect();
+		let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+		
+		pause();
+		let interleaver = thread::spawn(|| {
+			for _ in 0..200 {
+				comfy_eprintln!("Other");
+			}
+		});
+		
+		println_slice(&lines, OutputKind::Stdout);
+		interleaver.join().unwrap();
+		
+		// The slice was queued under a single lock, so no other message landed in between its lines.
+		let queue = test_utils::get_queue();
+		assert_eq!(queue.len(), 400);
+		let first_line = queue.iter().position(|msg| msg.output_kind() == OutputKind::Stdout).unwrap();
+		assert!(queue[first_line..first_line + 200].iter().all(|msg| msg.output_kind() == OutputKind::Stdout));
+		drop(queue);
+		
+		resume();
+		test_utils::yield_until_idle();
+		
+		let written: Vec<String> = test_utils::take_written()
+			.into_iter()
+			.filter(|(kind, _)| *kind == OutputKind::Stdout)
+			.map(|(_, text)| text)
+			.collect();
+		
+		assert_eq!(written, lines.iter().map(|line| format!("{line}\n")).collect::<Vec<_>>());
+	}
+	
+	#[test]
+	fn test_wrap_in_saved_cursor() {
+		let _guard = test_utils::lock_tests();
+		
+		assert_eq!(wrap_in_saved_cursor("50%"), "\x1b[s50%\x1b[u");
+		assert_eq!(wrap_in_saved_cursor(""), "\x1b[s\x1b[u");
+	}
+	
+	#[test]
+	fn test_thread_buffer() {
+		let _guard = test_utils::lock_tests();
+		
+		for index in 1..=3 {
+			thread_buffer_print(&format!("Test message_{index}\n"), OutputKind::Stdout);
+		}
+		thread_buffer_print("Test error\n", OutputKind::Stderr);
+		
+		// Nothing reaches the queue until flushed.
+		assert!(test_utils::get_queue().is_empty());
+		test_utils::yield_until_idle();
+		assert!(test_utils::take_written().is_empty());
+		
+		thread_flush();
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stdout, "Test message_2\n".to_string()),
+			(OutputKind::Stdout, "Test message_3\n".to_string()),
+			(OutputKind::Stderr, "Test error\n".to_string()),
+		]);
+		
+		// The buffer was emptied.
+		thread_flush();
+		test_utils::yield_until_idle();
+		assert!(test_utils::take_written().is_empty());
+	}
+	
+	#[test]
+	fn test_wait_for_seq() {
+		let _guard = test_utils::lock_tests();
+		
+		pause();
+		let seqs: Vec<u64> = (1..=5)
+			.map(|index| enqueue_seq(Message::standard_ln(format!("Test message_{index}"))))
+			.collect();
+		
+		assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]));
+		
+		let middle = seqs[2];
+		let waiter = thread::spawn(move || {
+			wait_for_seq(middle);
+			return tests::WRITTEN.lock().iter().map(|(_, text)| text.clone()).take(3).collect::<Vec<_>>();
+		});
+		
+		thread::sleep(std::time::Duration::from_millis(20));
+		assert_eq!(waiter.is_finished(), false);
+		
+		resume();
+		let written_before_return = waiter.join().unwrap();
+		assert_eq!(written_before_return, vec!["Test message_1\n", "Test message_2\n", "Test message_3\n"]);
+		
+		// Already complete, returns immediately
+		test_utils::yield_until_idle();
+		wait_for_seq(seqs[4]);
+	}
+	
+	#[test]
+	#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+	fn test_shutdown() {
+		let _guard = test_utils::lock_tests();
 		config::allow_logging_print_failures::set(false);
 		
-		test_utils::set_toggle_write_fail(true);
+		{
+			warmup().unwrap();
+			assert!(matches!(&*STATE.lock(), PrintingState::Parked(_)));
+			
+			assert_eq!(shutdown(std::time::Duration::from_secs(5)), true);
+			assert!(matches!(&*STATE.lock(), PrintingState::Idle));
+		}
+		
+		{
+			pause();
+			comfy_println!("Test message_1");
+			assert_eq!(shutdown(std::time::Duration::from_millis(10)), false);
+			assert_eq!(test_utils::get_queue().len(), 1);
+			
+			resume();
+			assert_eq!(shutdown(std::time::Duration::from_secs(5)), true);
+			assert!(matches!(&*STATE.lock(), PrintingState::Idle));
+			assert_eq!(test_utils::get_queue().len(), 0);
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test message_1\n".to_string())]);
+		}
+	}
+	
+	#[test]
+	fn test_drop_oldest() {
+		let _guard = test_utils::lock_tests();
+		let (sender, receiver) = std::sync::mpsc::channel();
+		config::drop_channel::set(sender);
+		
+		pause();
+		comfy_println!("Test message_1");
+		comfy_eprintln!("Test message_2");
+		comfy_println!("Test message_3");
+		
+		assert_eq!(drop_oldest(2), 2);
+		assert_eq!(dropped_count(), 2);
+		assert_eq!(receiver.try_recv(), Ok(DropEvent { kind: OutputKind::Stdout, reason: DropReason::Requested }));
+		assert_eq!(receiver.try_recv(), Ok(DropEvent { kind: OutputKind::Stderr, reason: DropReason::Requested }));
+		
+		let queue = test_utils::get_queue();
+		assert_eq!(queue.len(), 1);
+		assert_eq!(queue[0].str(), "Test message_3");
+		drop(queue);
+		
+		assert_eq!(drop_oldest(5), 1);
+		assert_eq!(dropped_count(), 3);
+		assert_eq!(drop_oldest(5), 0);
+		assert_eq!(dropped_count(), 3);
+	}
+	
+	#[test]
+	fn test_enqueue_many() {
+		let _guard = test_utils::lock_tests();
+		config::max_queue_length::set(5);
+		
+		pause();
+		comfy_println!("Test message_1");
+		comfy_println!("Test message_2");
+		
+		let batch = (0..5).map(|index| Message::standard_ln(format!("Batch_{index}"))).collect();
+		assert_eq!(enqueue_many(batch), (3, 2));
+		assert_eq!(dropped_count(), 2);
+		
+		let queue = test_utils::get_queue();
+		assert_eq!(queue.iter().map(Message::str).collect::<Vec<_>>(), ["Test message_1", "Test message_2", "Batch_0", "Batch_1", "Batch_2"]);
+		drop(queue);
+		
+		// Evicts messages queued before the batch, which aren't part of its count.
+		config::on_queue_full::set(On_QueueFull::KeepNewest);
+		let batch = (5..7).map(|index| Message::standard_ln(format!("Batch_{index}"))).collect();
+		assert_eq!(enqueue_many(batch), (2, 0));
+		assert_eq!(dropped_count(), 4);
+		
+		// Evicts the whole queue, then the first messages of the batch itself.
+		let batch = (7..14).map(|index| Message::standard_ln(format!("Batch_{index}"))).collect();
+		assert_eq!(enqueue_many(batch), (5, 2));
+		assert_eq!(dropped_count(), 11);
+		
+		let queue = test_utils::get_queue();
+		assert_eq!(queue.iter().map(Message::str).collect::<Vec<_>>(), ["Batch_9", "Batch_10", "Batch_11", "Batch_12", "Batch_13"]);
+		drop(queue);
+		
+		resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written().len(), 5);
+	}
+	
+	#[test]
+	fn test_queue_memory_bytes() {
+		let _guard = test_utils::lock_tests();
+		assert_eq!(queue_memory_bytes(), 0);
+		
+		pause();
+		let mut text = String::with_capacity(256);
+		text.push_str("Test message_1");
+		comfy_print_async(Message::standard_ln(text));
+		comfy_eprintln!("Test message_2");
+		
+		let content_len: usize = test_utils::get_queue().iter().map(|msg| msg.str().len()).sum();
+		assert!(queue_memory_bytes() >= content_len + 256);
+		assert!(queue_memory_bytes() >= 2 * std::mem::size_of::<Message>());
+	}
+	
+	#[test]
+	fn test_queue_len_by_kind() {
+		let _guard = test_utils::lock_tests();
+		config::allow_logging_print_failures::set(false);
+		assert_eq!(queue_len_by_kind(OutputKind::Stdout), 0);
+		
+		pause();
+		test_utils::write_fail_once();
+		comfy_println!("Test message_1");
+		comfy_println!("Test message_2");
+		comfy_eprintln!("Test message_3");
+		
+		assert_eq!(queue_len_by_kind(OutputKind::Stdout), 2);
+		assert_eq!(queue_len_by_kind(OutputKind::Stderr), 1);
+		
+		resume();
+		test_utils::yield_until_idle();
+		assert_eq!(queue_len_by_kind(OutputKind::Stdout), 0);
+		assert_eq!(queue_len_by_kind(OutputKind::Stderr), 0);
+		assert_eq!(test_utils::take_written().len(), 3);
+	}
+	
+	#[cfg(unix)]
+	#[test]
+	fn test_is_same_file() {
+		use std::os::fd::AsFd;
+		let _guard = test_utils::lock_tests();
+		
+		let (_reader, writer) = std::io::pipe().unwrap();
+		let writer_clone = writer.try_clone().unwrap();
+		assert_eq!(is_same_file(writer.as_fd(), writer_clone.as_fd()), true);
+		
+		let (_other_reader, other_writer) = std::io::pipe().unwrap();
+		assert_eq!(is_same_file(writer.as_fd(), other_writer.as_fd()), false);
+	}
+	
+	#[cfg(unix)]
+	#[test]
+	fn test_shared_stream_lock() {
+		use std::os::fd::AsRawFd;
+		let _guard = test_utils::lock_tests();
+		tests::FORCE_STREAMS_SHARE_FILE.store(true, Ordering::Relaxed);
+		
+		// Stdout through its sink and stderr through fd 2 both end up in the same pipe.
+		// Messages are longer than PIPE_BUF, so that the pipe itself doesn't keep them whole.
+		let (mut reader, writer) = std::io::pipe().unwrap();
+		let write_fd = writer.as_raw_fd();
+		// SAFETY: `saved_stderr` is a new descriptor, restored onto fd 2 below.
+		let saved_stderr = unsafe { libc::dup(libc::STDERR_FILENO) };
+		assert!(saved_stderr >= 0);
+		// SAFETY: both descriptors are open.
+		assert!(unsafe { libc::dup2(write_fd, libc::STDERR_FILENO) } >= 0);
+		*STDOUT_SINK.lock() = Some(Box::new(writer));
+		
+		let read_all = std::thread::spawn(move || {
+			let mut output = String::new();
+			std::io::Read::read_to_string(&mut reader, &mut output).unwrap();
+			return output;
+		});
+		
+		let message = |kind: OutputKind, index: usize| {
+			let fill = match kind {
+				OutputKind::Stdout => "o",
+				OutputKind::Stderr => "e",
+			};
+			
+			return format!("Test message_{index} {}\n", fill.repeat(64 * 1024));
+		};
+		
+		let writers = [OutputKind::Stdout, OutputKind::Stderr].map(|kind| std::thread::spawn(move || {
+			for index in 0..16 {
+				write_rendered(kind, &message(kind, index), false, false).unwrap();
+			}
+		}));
+		
+		for writer in writers {
+			writer.join().unwrap();
+		}
+		
+		*STDOUT_SINK.lock() = None;
+		// SAFETY: `saved_stderr` is still open.
+		assert!(unsafe { libc::dup2(saved_stderr, libc::STDERR_FILENO) } >= 0);
+		// SAFETY: fd 2 now refers to the same file, so `saved_stderr` is no longer needed.
+		unsafe { libc::close(saved_stderr) };
+		
+		let output = read_all.join().unwrap();
+		let mut written: Vec<&str> = output.split_inclusive('\n').collect();
+		let mut expected: Vec<String> = (0..16)
+			.flat_map(|index| [message(OutputKind::Stdout, index), message(OutputKind::Stderr, index)])
+			.collect();
+		
+		written.sort_unstable();
+		expected.sort_unstable();
+		assert!(written == expected, "{} messages written, some were broken up", written.len());
+		
+		test_utils::take_written();
+	}
+	
+	#[test]
+	fn test_flush_with() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		config::max_retries::set(2);
+		config::on_max_retries_reached::set(On_MaxRetriesReached::Return);
+		
+		{
+			// 1 direct attempt, then 1 + 2 retries from the queue: not enough for the global budget.
+			tests::FAIL_NEXT_WRITES.store(5, Ordering::Relaxed);
+			comfy_println!("Test message_1");
+			test_utils::yield_until_idle();
+			assert_eq!(test_utils::get_queue().len(), 1);
+			assert_eq!(config::max_retries::get(), 2);
+			
+			assert_eq!(flush_with(10, None).is_ok(), true);
+			assert_eq!(test_utils::get_queue().len(), 0);
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test message_1\n".to_string())]);
+		}
+		
+		{
+			tests::FAIL_NEXT_WRITES.store(3, Ordering::Relaxed);
+			pause();
+			comfy_println!("Test message_2");
+			assert_eq!(flush_with(10, None).unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+			PAUSED.store(false, Ordering::Relaxed);
+			
+			assert_eq!(flush_with(2, None).unwrap_err().to_string(), tests::FORCE_WRITE_FAIL_MSG);
+			assert_eq!(test_utils::get_queue().len(), 1);
+			
+			test_utils::set_toggle_write_fail(true);
+			let result = flush_with(usize::MAX, Some(std::time::Duration::from_millis(10)));
+			assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+			assert_eq!(test_utils::get_queue().len(), 1);
+			
+			test_utils::set_toggle_write_fail(false);
+			assert_eq!(flush_with(0, None).is_ok(), true);
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test message_2\n".to_string())]);
+		}
+	}
+	
+	#[cfg(feature = "single-thread")]
+	#[test]
+	fn test_single_thread() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		
+		warmup().unwrap();
+		assert!(matches!(&*STATE.lock(), PrintingState::Idle));
+		
+		comfy_println!("Test message_1");
+		
+		// The queue is printed before returning, no need to wait for another thread.
+		test_utils::write_fail_once();
+		comfy_eprintln!("Test message_2");
+		assert_eq!(test_utils::get_queue().len(), 0);
+		assert!(matches!(&*STATE.lock(), PrintingState::Idle));
+		
+		pause();
+		comfy_println!("Test message_3");
+		assert_eq!(test_utils::get_queue().len(), 1);
+		resume();
+		assert_eq!(test_utils::get_queue().len(), 0);
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stderr, "Test message_2\n".to_string()),
+			(OutputKind::Stdout, "Test message_3\n".to_string()),
+		]);
+	}
+	
+	#[test]
+	fn test_resolve_targets() {
+		let _guard = test_utils::lock_tests();
+		let standard = Message::standard("Test message_1");
+		let error = Message::error("Test message_2");
+		
+		assert_eq!(resolve_targets(&standard), vec![Destination::Stream(OutputKind::Stdout)]);
+		assert_eq!(resolve_targets(&error), vec![Destination::Stream(OutputKind::Stderr)]);
+		
+		config::combined_stream::set(Some(OutputKind::Stdout));
+		config::always_log_to_disk::set(Some(OutputKind::Stderr));
+		
+		assert_eq!(resolve_targets(&standard), vec![Destination::Stream(OutputKind::Stdout)]);
+		assert_eq!(resolve_targets(&error), vec![Destination::Stream(OutputKind::Stdout), Destination::LogFile]);
+		
+		// A route only counts while its sink is set.
+		let routed = Message::error("AUDIT: Test message_3");
+		config::routes::add_route(Box::new(|msg| msg.str().contains("AUDIT")), "audit");
+		assert_eq!(resolve_targets(&routed), vec![Destination::Stream(OutputKind::Stdout), Destination::LogFile]);
+		
+		config::routes::set_sink("audit", Box::new(std::io::sink()));
+		assert_eq!(resolve_targets(&routed), vec![Destination::Route("audit".to_string()), Destination::LogFile]);
+		
+		// The terminal only counts while USE_CONTROLLING_TTY is enabled.
+		let tty = Message::to_tty_ln("Test message_4");
+		assert_eq!(resolve_targets(&tty), vec![Destination::Stream(OutputKind::Stdout), Destination::LogFile]);
+		
+		config::use_controlling_tty::set(true);
+		assert_eq!(resolve_targets(&tty), vec![Destination::Tty, Destination::LogFile]);
+		
+		#[cfg(unix)]
+		assert_eq!(resolve_targets(&Message::to_fd_ln(3, "Test message_5")), vec![Destination::Descriptor, Destination::LogFile]);
+		#[cfg(windows)]
+		assert_eq!(resolve_targets(&Message::to_handle_ln(std::ptr::null_mut(), "Test message_5")), vec![Destination::Descriptor, Destination::LogFile]);
+		
+		// Every message is recorded under its stream, nothing reaches the sinks, the terminal or the disk.
+		config::dry_run::set(true);
+		assert_eq!(resolve_targets(&routed), vec![Destination::DryRun(OutputKind::Stdout)]);
+		assert_eq!(resolve_targets(&tty), vec![Destination::DryRun(OutputKind::Stdout)]);
+		
+		config::combined_stream::set(None);
+		assert_eq!(resolve_targets(&tty), vec![Destination::DryRun(OutputKind::Stderr)]);
+	}
+	
+	#[test]
+	fn test_on_complete() {
+		let _guard = test_utils::lock_tests();
+		// Just so the error messages don't interfere with the test.
+		config::allow_logging_print_failures::set(false);
+		let (sender, receiver) = std::sync::mpsc::channel();
+		
+		{
+			let sender = sender.clone();
+			test_utils::write_fail_once();
+			comfy_print_async(Message::standard_ln("Test message_1")
+				.with_on_complete(move |result| sender.send(result.is_ok()).unwrap()));
+			
+			// Failed on the caller, the callback only runs once the message is printed from the queue.
+			assert_eq!(receiver.recv_timeout(std::time::Duration::from_secs(1)), Ok(true));
+			test_utils::yield_until_idle();
+			assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test message_1\n".to_string())]);
+		}
+		
+		{
+			let sender = sender.clone();
+			comfy_print_async(Message::standard_ln("Test message_2")
+				.with_on_complete(move |result| sender.send(result.is_ok()).unwrap()));
+			assert_eq!(receiver.try_recv(), Ok(true));
+		}
+		
+		{
+			config::max_queue_length::set(0);
+			test_utils::write_fail_once();
+			comfy_print_async(Message::standard_ln("Test message_3")
+				.with_on_complete(move |result| sender.send(result.is_ok()).unwrap()));
+			assert_eq!(receiver.try_recv(), Ok(false));
+		}
+	}
+	
+	#[test]
+	fn test_counters_saturate() {
+		let _guard = test_utils::lock_tests();
+		
+		DROPPED_COUNT.store(usize::MAX - 1, Ordering::Relaxed);
+		notify_dropped(OutputKind::Stdout, DropReason::Requested);
+		notify_dropped(OutputKind::Stdout, DropReason::Requested);
+		assert_eq!(dropped_count(), usize::MAX);
+		
+		SUPPRESSED_COUNT.store(usize::MAX, Ordering::Relaxed);
+		comfy_print_async(Message::standard_ln("Test message_1").with_dedup_key(1));
+		comfy_print_async(Message::standard_ln("Test message_1").with_dedup_key(1));
+		assert_eq!(suppressed_count(), usize::MAX);
+	}
+	
+	#[cfg(feature = "subscribe")]
+	#[test]
+	fn test_subscribe() {
+		let _guard = test_utils::lock_tests();
+		
+		let receiver = subscribe();
+		comfy_println!("Test message_1");
+		comfy_eprint!("Test message_2");
+		
+		let received = receiver.try_recv().unwrap();
+		assert_eq!((received.output_kind(), received.str()), (OutputKind::Stdout, "Test message_1"));
+		let received = receiver.try_recv().unwrap();
+		assert_eq!((received.output_kind(), received.str()), (OutputKind::Stderr, "Test message_2"));
+		
+		// A slow subscriber misses messages instead of blocking printing.
+		for index in 0..SUBSCRIBER_CAPACITY + 10 {
+			comfy_println!("Line_{index}");
+		}
+		
+		assert_eq!(receiver.try_iter().count(), SUBSCRIBER_CAPACITY);
+		
+		drop(receiver);
+		comfy_println!("Test message_3");
+		assert!(SUBSCRIBERS.lock().is_empty());
+	}
+	
+	#[test]
+	fn test_swap_stdout_sink() {
+		let _guard = test_utils::lock_tests();
+		config::allow_logging_print_failures::set(false);
+		config::on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		
+		struct BrokenSink;
+		
+		impl Write for BrokenSink {
+			fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+				return Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+			}
+			
+			fn flush(&mut self) -> std::io::Result<()> {
+				return Ok(());
+			}
+		}
+		
+		#[derive(Clone, Default)]
+		struct CapturingSink(std::sync::Arc<parking_lot::Mutex<Vec<u8>>>);
+		
+		impl Write for CapturingSink {
+			fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+				self.0.lock().extend_from_slice(buf);
+				return Ok(buf.len());
+			}
+			
+			fn flush(&mut self) -> std::io::Result<()> {
+				return Ok(());
+			}
+		}
+		
+		swap_stdout_sink(Box::new(std::io::sink()));
 		comfy_println!("Test message_1");
+		
+		swap_stdout_sink(Box::new(BrokenSink));
 		comfy_println!("Test message_2");
 		comfy_println!("Test message_3");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 2);
 		
-		assert_eq!(STATE.lock().is_busy(), true);
+		let captured = CapturingSink::default();
+		swap_stdout_sink(Box::new(captured.clone()));
+		test_utils::yield_until_idle();
+		
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(String::from_utf8(captured.0.lock().clone()).unwrap(), "Test message_2\nTest message_3\n");
+	}
+	
+	#[test]
+	fn test_write_zero() {
+		let _guard = test_utils::lock_tests();
+		config::allow_logging_print_failures::set(false);
+		config::on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		
+		struct ZeroSink(std::sync::Arc<AtomicUsize>);
+		
+		impl Write for ZeroSink {
+			fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+				self.0.fetch_add(1, Ordering::Relaxed);
+				return Ok(0);
+			}
+			
+			fn flush(&mut self) -> std::io::Result<()> {
+				return Ok(());
+			}
+		}
+		
+		let write_calls = std::sync::Arc::new(AtomicUsize::new(0));
+		swap_stdout_sink(Box::new(ZeroSink(write_calls.clone())));
 		
+		comfy_println!("Test message_1");
 		test_utils::yield_until_idle();
-		assert_eq!(STATE.lock().is_busy(), false);
-		assert_eq!(test_utils::get_queue().len(), 3);
-
+		
+		// Written once by the caller, then once by the drain.
+		assert_eq!(write_calls.load(Ordering::Relaxed), MAX_ZERO_WRITES * 2);
+		assert_eq!(test_utils::get_queue().len(), 1);
+	}
+	
+	#[test]
+	fn test_would_block() {
+		let _guard = test_utils::lock_tests();
+		config::max_retries::set(0);
+		
+		pause();
+		comfy_println!("Test message_1");
+		
+		WOULD_BLOCK_NEXT_WRITES.store(2, Ordering::Relaxed);
+		test_utils::reset_write_attempts();
+		resume();
+		test_utils::yield_until_idle();
+		
+		// No retry was left, yet the message was written on the third attempt, without diagnostics.
+		assert_eq!(test_utils::write_attempts(), 3);
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test message_1\n".to_string())]);
+		
+		// Past the bound, it counts as a regular failure.
+		pause();
+		comfy_println!("Test message_2");
+		config::on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		config::allow_logging_print_failures::set(false);
+		WOULD_BLOCK_NEXT_WRITES.store(WOULD_BLOCK_RETRIES + 1, Ordering::Relaxed);
+		resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 1);
+	}
+	
+	#[test]
+	fn test_escalate_after() {
+		let _guard = test_utils::lock_tests();
+		config::allow_logging_print_failures::set(false);
+		config::on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		
+		test_utils::set_toggle_write_fail(true);
+		comfy_print_async(Message::error_ln("Test message_1").escalate_after(std::time::Duration::from_millis(50)));
+		comfy_println!("Test message_2");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 2);
 		test_utils::set_toggle_write_fail(false);
-		comfy_println!("Test message_4");
-		assert_eq!(STATE.lock().is_busy(), true);
 		
+		// Before the deadline, a single failure stalls the drain.
+		FAIL_NEXT_WRITES.store(1, Ordering::Relaxed);
+		resume();
 		test_utils::yield_until_idle();
-		assert_eq!(STATE.lock().is_busy(), false);
-		assert_eq!(test_utils::get_queue().len(), 0);
+		assert_eq!(test_utils::get_queue().len(), 2);
+		
+		// After it, the message is retried until written.
+		std::thread::sleep(std::time::Duration::from_millis(60));
+		FAIL_NEXT_WRITES.store(ESCALATION_RETRIES, Ordering::Relaxed);
+		resume();
+		test_utils::yield_until_idle();
+		
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stderr, "Test message_1\n".to_string()),
+			(OutputKind::Stdout, "Test message_2\n".to_string()),
+		]);
 	}
-}
\ No newline at end of file
+	
+	#[test]
+	fn test_raw_write() {
+		let _guard = test_utils::lock_tests();
+		config::middleware::add(Box::new(|msg| {
+			let prefixed = format!("[app] {}", msg.str());
+			return msg.with_str(prefixed);
+		}));
+		
+		pause();
+		raw_write("Test message_1\n", OutputKind::Stdout).unwrap();
+		assert!(test_utils::get_queue().is_empty());
+		resume();
+		
+		assert!(shutdown(std::time::Duration::from_secs(1)));
+		raw_write("Test message_2\n", OutputKind::Stderr).unwrap();
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test message_1\n".to_string()),
+			(OutputKind::Stderr, "Test message_2\n".to_string()),
+		]);
+	}
+	
+	#[cfg(unix)]
+	#[test]
+	fn test_signal_safe_write() {
+		use std::os::fd::AsRawFd;
+		let _guard = test_utils::lock_tests();
+		
+		let (mut reader, writer) = std::io::pipe().unwrap();
+		write_all_to_fd(writer.as_raw_fd(), b"Test message_1\n");
+		write_all_to_fd(writer.as_raw_fd(), b"");
+		drop(writer);
+		
+		let mut output = Vec::new();
+		std::io::Read::read_to_end(&mut reader, &mut output).unwrap();
+		assert_eq!(output, b"Test message_1\n");
+		
+		// Lands on fd 2, bypassing the queue entirely.
+		let (mut reader, writer) = std::io::pipe().unwrap();
+		// SAFETY: `saved_stderr` is a new descriptor, restored onto fd 2 below.
+		let saved_stderr = unsafe { libc::dup(libc::STDERR_FILENO) };
+		assert!(saved_stderr >= 0);
+		// SAFETY: both descriptors are open.
+		assert!(unsafe { libc::dup2(writer.as_raw_fd(), libc::STDERR_FILENO) } >= 0);
+		
+		signal_safe_write(b"Test message_2\n", OutputKind::Stderr);
+		
+		// SAFETY: `saved_stderr` is still open.
+		assert!(unsafe { libc::dup2(saved_stderr, libc::STDERR_FILENO) } >= 0);
+		// SAFETY: fd 2 now refers to the same file, so `saved_stderr` is no longer needed.
+		unsafe { libc::close(saved_stderr) };
+		drop(writer);
+		
+		let mut output = Vec::new();
+		std::io::Read::read_to_end(&mut reader, &mut output).unwrap();
+		assert_eq!(output, b"Test message_2\n");
+		assert!(test_utils::take_written().is_empty());
+	}
+}
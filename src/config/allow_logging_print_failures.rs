@@ -1,5 +1,7 @@
 //! Determines whether or not errors generated by [Stdout::write()](std::io::Stdout::write()) and [Stderr::write()](std::io::Stderr::write()) errors should be added to the message queue.
 //! 
+//! Feature **no-diagnostics** compiles the diagnostics out entirely, making this config a no-op.
+//! 
 //! # Default: **true**
 
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -17,8 +19,11 @@ pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
 pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
 
 #[test]
+#[cfg_attr(feature = "no-diagnostics", ignore = "diagnostics are compiled out by feature no-diagnostics")]
+#[cfg_attr(all(feature = "single-thread", not(feature = "no-diagnostics")), ignore = "expects the queue to be printed by another thread")]
 fn test() {
 	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
 
 	{
 		set(true);
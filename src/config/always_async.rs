@@ -0,0 +1,59 @@
+//! Determines whether messages are always written by the thread responsible for printing the queue.
+//! - **false**: When the queue is empty, the caller writes the message itself, which keeps latency low but blocks the caller while writing.
+//! - **true**: Messages are always queued, then written by the background thread, which keeps the caller's latency predictable.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [ALWAYS_ASYNC](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [ALWAYS_ASYNC](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ALWAYS_ASYNC";
+
+/// Get global config [ALWAYS_ASYNC](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [ALWAYS_ASYNC](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	// While the state is locked no thread can start printing the queue, so only the caller could write.
+	{
+		let state_guard = async_impl::STATE.lock();
+		crate::comfy_println!("Test_01");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+		drop(state_guard);
+	}
+
+	{
+		set(true);
+		let state_guard = async_impl::STATE.lock();
+		crate::comfy_println!("Test_02");
+		assert!(test_utils::take_written().is_empty());
+		assert_eq!(test_utils::get_queue().len(), 1);
+		drop(state_guard);
+		
+		// The message was queued while nothing could print it, let the thread pick it up.
+		async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_02\n".to_string())]);
+	}
+}
@@ -0,0 +1,78 @@
+//! When set, every message of this [OutputKind] is also appended to the file at [LOG_IO_PATH](crate::config::log_io_path), 
+//! regardless of failures, e.g. to persist all errors while keeping stdout on the terminal.
+//! - Also appended to the matching [LOG_TARGETS](crate::config::log_targets).
+//! - Messages are appended after being successfully written to their stream, so failed messages are logged once they are printed from the queue.
+//! - Messages are written to disk through [Message::rendered()](crate::message::Message::rendered()), stream-only rendering doesn't apply.
+//! - Failing to write to the file is ignored.
+//! 
+//! # Default: None
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use crate::message::OutputKind;
+
+/// Current value of [ALWAYS_LOG_TO_DISK](self). 0 = None, 1 = Stdout, 2 = Stderr.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [ALWAYS_LOG_TO_DISK](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ALWAYS_LOG_TO_DISK";
+
+/// Get global config [ALWAYS_LOG_TO_DISK](self).
+pub fn get() -> Option<OutputKind> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => Some(OutputKind::Stdout),
+		2 => Some(OutputKind::Stderr),
+		_ => None, // 0
+	};
+}
+
+/// Set global config [ALWAYS_LOG_TO_DISK](self).
+pub fn set(new_value: Option<OutputKind>) {
+	let value = match new_value {
+		None => 0,
+		Some(OutputKind::Stdout) => 1,
+		Some(OutputKind::Stderr) => 2,
+	};
+	
+	CURRENT.store(value, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "Stderr");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(OutputKind::Stderr));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(OutputKind::Stdout));
+	}
+
+	{
+		let path = test_utils::temp_path("comfy_print_test_always_log_to_disk.txt");
+		let path = path.as_str();
+		let _ = std::fs::remove_file(path);
+		super::log_io_path::set(path).unwrap();
+		set(Some(OutputKind::Stderr));
+		super::allow_logging_print_failures::set(false);
+		
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprintln!("Test_02");
+		
+		test_utils::write_fail_once();
+		crate::comfy_eprintln!("Test_03");
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stderr, "Test_02\n".to_string()),
+			(OutputKind::Stderr, "Test_03\n".to_string()),
+		]);
+		
+		assert_eq!(std::fs::read_to_string(path).unwrap(), "Test_02\nTest_03\n");
+		std::fs::remove_file(path).unwrap();
+	}
+}
@@ -0,0 +1,60 @@
+//! Determines whether lines are prefixed with the stream they were meant for (`O: ` for stdout, `E: ` for stderr), 
+//! so that the origin of each line isn't lost when [COMBINED_STREAM](crate::config::combined_stream) merges both streams into one.
+//! - Only applies while [COMBINED_STREAM](crate::config::combined_stream) is set, messages written to their own stream are never tagged.
+//! - Each line of a message is tagged, a line printed across several messages (e.g. with `comfy_print!`) is tagged once per message.
+//! - Messages written to disk are never tagged.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [ANNOTATE_STREAM](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [ANNOTATE_STREAM](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ANNOTATE_STREAM";
+
+/// Get global config [ANNOTATE_STREAM](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [ANNOTATE_STREAM](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	{
+		set(true);
+		crate::comfy_println!("Test_01");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+	}
+
+	{
+		super::combined_stream::set(Some(OutputKind::Stdout));
+		crate::comfy_println!("Test_02\nTest_03");
+		crate::comfy_eprintln!("Test_04");
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "O: Test_02\nO: Test_03\n".to_string()),
+			(OutputKind::Stdout, "E: Test_04\n".to_string()),
+		]);
+	}
+
+	{
+		set(false);
+		crate::comfy_eprintln!("Test_05");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_05\n".to_string())]);
+	}
+}
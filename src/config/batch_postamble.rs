@@ -0,0 +1,51 @@
+//! Written once after the last message of each pass over the queue, e.g. a CI fold marker.
+//! - Written to the stream of the last message written in the pass.
+//! - Not written if the pass never attempted to write a message.
+//! - Messages printed directly by the caller, when the queue is empty, are not part of a pass.
+//! - Write failures are ignored, the marker is not stored in the queue.
+//! - An empty string disables it.
+//! 
+//! # Default: "" (disabled)
+
+use parking_lot::Mutex;
+
+/// Current value of [BATCH_POSTAMBLE](self).
+static CURRENT: Mutex<String> = Mutex::new(String::new());
+
+/// Environment variable name for global config [BATCH_POSTAMBLE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_BATCH_POSTAMBLE";
+
+/// The string stored in global config [BATCH_POSTAMBLE](self) will be appended to parameter `append_in_me`.
+pub fn get(append_in_me: &mut String) {
+	let guard = CURRENT.lock();
+	append_in_me.push_str(guard.as_str());
+	drop(guard);
+}
+
+/// Set global config [BATCH_POSTAMBLE](self).
+pub fn set(new_value: &str) {
+	let mut guard = CURRENT.lock();
+	guard.clear();
+	guard.push_str(new_value);
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	let _guard = crate::test_utils::lock_tests();
+	let mut postamble = String::new();
+	
+	{
+		set("---\n");
+		get(&mut postamble);
+		assert_eq!(postamble, "---\n");
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "===");
+		super::env_vars::load_all();
+		postamble.clear();
+		get(&mut postamble);
+		assert_eq!(postamble, "===");
+	}
+}
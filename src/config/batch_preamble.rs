@@ -0,0 +1,50 @@
+//! Written once before the first message of each pass over the queue, e.g. a CI fold marker.
+//! - Written to the stream of the first message in the pass.
+//! - Messages printed directly by the caller, when the queue is empty, are not part of a pass.
+//! - Write failures are ignored, the marker is not stored in the queue.
+//! - An empty string disables it.
+//! 
+//! # Default: "" (disabled)
+
+use parking_lot::Mutex;
+
+/// Current value of [BATCH_PREAMBLE](self).
+static CURRENT: Mutex<String> = Mutex::new(String::new());
+
+/// Environment variable name for global config [BATCH_PREAMBLE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_BATCH_PREAMBLE";
+
+/// The string stored in global config [BATCH_PREAMBLE](self) will be appended to parameter `append_in_me`.
+pub fn get(append_in_me: &mut String) {
+	let guard = CURRENT.lock();
+	append_in_me.push_str(guard.as_str());
+	drop(guard);
+}
+
+/// Set global config [BATCH_PREAMBLE](self).
+pub fn set(new_value: &str) {
+	let mut guard = CURRENT.lock();
+	guard.clear();
+	guard.push_str(new_value);
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	let _guard = crate::test_utils::lock_tests();
+	let mut preamble = String::new();
+	
+	{
+		set("---\n");
+		get(&mut preamble);
+		assert_eq!(preamble, "---\n");
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "===");
+		super::env_vars::load_all();
+		preamble.clear();
+		get(&mut preamble);
+		assert_eq!(preamble, "===");
+	}
+}
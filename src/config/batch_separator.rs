@@ -0,0 +1,65 @@
+//! Written between consecutive messages of each pass over the queue, e.g. a blank line to visually group burst output.
+//! - Unlike each message's own newline, it isn't written after the last message of the pass.
+//! - Written to the stream of the message before it, once that message was written and more messages are waiting in the queue.
+//! - Messages printed directly by the caller, when the queue is empty, are not part of a pass.
+//! - Write failures are ignored, the separator is not stored in the queue.
+//! - An empty string disables it.
+//! 
+//! # Default: "" (disabled)
+
+use parking_lot::Mutex;
+
+/// Current value of [BATCH_SEPARATOR](self).
+static CURRENT: Mutex<String> = Mutex::new(String::new());
+
+/// Environment variable name for global config [BATCH_SEPARATOR](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_BATCH_SEPARATOR";
+
+/// The string stored in global config [BATCH_SEPARATOR](self) will be appended to parameter `append_in_me`.
+pub fn get(append_in_me: &mut String) {
+	let guard = CURRENT.lock();
+	append_in_me.push_str(guard.as_str());
+	drop(guard);
+}
+
+/// Set global config [BATCH_SEPARATOR](self).
+pub fn set(new_value: &str) {
+	let mut guard = CURRENT.lock();
+	guard.clear();
+	guard.push_str(new_value);
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+	
+	{
+		std::env::set_var(ENV_NAME, "--");
+		super::env_vars::load_all();
+		let mut separator = String::new();
+		get(&mut separator);
+		assert_eq!(separator, "--");
+	}
+	
+	{
+		set("--\n");
+		
+		crate::async_impl::pause();
+		crate::comfy_println!("Test_01");
+		crate::comfy_println!("Test_02");
+		crate::comfy_println!("Test_03");
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stdout, "--\n".to_string()),
+			(OutputKind::Stdout, "Test_02\n".to_string()),
+			(OutputKind::Stdout, "--\n".to_string()),
+			(OutputKind::Stdout, "Test_03\n".to_string()),
+		]);
+	}
+}
@@ -0,0 +1,135 @@
+//! Overrides [FLUSH_STRATEGY](crate::config::flush_strategy) for each [OutputKind],
+//! e.g. to flush interactive stderr after every message while batching bulk stdout.
+//! - A stream without an override ([None]) follows [FLUSH_STRATEGY](crate::config::flush_strategy).
+//! - The stream is the one the message is written to, after applying [COMBINED_STREAM](crate::config::combined_stream).
+//! - From the environment: comma-separated `Kind=Strategy` pairs, e.g. `Stdout=OnDrainEnd,Stderr=EveryMessage`.
+//!
+//! # Default: No overrides
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use crate::config::flush_strategy::FlushStrategy;
+use crate::message::OutputKind;
+
+/// Current override for [std::io::stdout]. 0 = None, otherwise the [FlushStrategy] + 1.
+static CURRENT_STDOUT: AtomicU8 = AtomicU8::new(0);
+
+/// Current override for [std::io::stderr]. 0 = None, otherwise the [FlushStrategy] + 1.
+static CURRENT_STDERR: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [BUFFER_POLICY](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_BUFFER_POLICY";
+
+/// See [BUFFER_POLICY](self).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub struct BufferPolicy {
+	/// Strategy for [std::io::stdout], [None] follows [FLUSH_STRATEGY](crate::config::flush_strategy).
+	pub stdout: Option<FlushStrategy>,
+	/// Strategy for [std::io::stderr], [None] follows [FLUSH_STRATEGY](crate::config::flush_strategy).
+	pub stderr: Option<FlushStrategy>,
+}
+
+impl BufferPolicy {
+	/// The override for `stream`, if any.
+	pub fn for_stream(&self, stream: OutputKind) -> Option<FlushStrategy> {
+		return match stream {
+			OutputKind::Stdout => self.stdout,
+			OutputKind::Stderr => self.stderr,
+		};
+	}
+}
+
+impl FromStr for BufferPolicy {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut policy = BufferPolicy::default();
+
+		for pair in s.split(',').map(str::trim).filter(|pair| pair.is_empty() == false) {
+			let Some((kind, strategy)) = pair.split_once('=')
+					else { return Err(format!("Invalid string value for BufferPolicy, expected `Kind=Strategy`: {}", pair)); };
+
+			let strategy = Some(FlushStrategy::from_str(strategy.trim())?);
+			match OutputKind::from_str(kind.trim())? {
+				OutputKind::Stdout => policy.stdout = strategy,
+				OutputKind::Stderr => policy.stderr = strategy,
+			}
+		}
+
+		return Ok(policy);
+	}
+}
+
+/// Get global config [BUFFER_POLICY](self).
+pub fn get() -> BufferPolicy {
+	return BufferPolicy {
+		stdout: decode(CURRENT_STDOUT.load(Ordering::Relaxed)),
+		stderr: decode(CURRENT_STDERR.load(Ordering::Relaxed)),
+	};
+}
+
+/// Set global config [BUFFER_POLICY](self).
+pub fn set(new_value: BufferPolicy) {
+	CURRENT_STDOUT.store(encode(new_value.stdout), Ordering::Relaxed);
+	CURRENT_STDERR.store(encode(new_value.stderr), Ordering::Relaxed);
+}
+
+fn encode(strategy: Option<FlushStrategy>) -> u8 {
+	return match strategy {
+		None => 0,
+		Some(strategy) => strategy as u8 + 1,
+	};
+}
+
+fn decode(value: u8) -> Option<FlushStrategy> {
+	return match value {
+		1 => Some(FlushStrategy::EveryMessage),
+		2 => Some(FlushStrategy::OnDrainEnd),
+		3 => Some(FlushStrategy::Auto),
+		_ => None, // 0
+	};
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "Stdout:OnDrainEnd");
+		super::env_vars::load_all();
+		assert_eq!(get(), BufferPolicy::default());
+
+		std::env::set_var(ENV_NAME, "Stdout=OnDrainEnd, Stderr=0");
+		super::env_vars::load_all();
+		assert_eq!(get(), BufferPolicy { stdout: Some(FlushStrategy::OnDrainEnd), stderr: Some(FlushStrategy::EveryMessage) });
+
+		std::env::set_var(ENV_NAME, "Stderr=Auto");
+		super::env_vars::load_all();
+		assert_eq!(get(), BufferPolicy { stdout: None, stderr: Some(FlushStrategy::Auto) });
+	}
+
+	{
+		super::flush_strategy::set(FlushStrategy::EveryMessage);
+		set(BufferPolicy { stdout: Some(FlushStrategy::OnDrainEnd), stderr: None });
+		assert_eq!(super::flush_strategy::resolve(OutputKind::Stdout), FlushStrategy::OnDrainEnd);
+		assert_eq!(super::flush_strategy::resolve(OutputKind::Stderr), FlushStrategy::EveryMessage);
+	}
+
+	{
+		set(BufferPolicy { stdout: Some(FlushStrategy::OnDrainEnd), stderr: Some(FlushStrategy::EveryMessage) });
+
+		crate::async_impl::pause();
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprintln!("Test_02");
+		crate::comfy_println!("Test_03");
+		crate::comfy_eprintln!("Test_04");
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+
+		// Stdout is only flushed once the queue is empty.
+		assert_eq!(test_utils::take_flushed(), vec![OutputKind::Stderr, OutputKind::Stderr]);
+		assert_eq!(test_utils::take_written().len(), 4);
+	}
+}
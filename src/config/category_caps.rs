@@ -0,0 +1,85 @@
+//! Caps how many queued messages of each [category](crate::message::Message::with_category()) are kept, 
+//! e.g. so that a flood of telemetry doesn't starve user output.
+//! - Applies in addition to [MAX_QUEUE_LENGTH](crate::config::max_queue_length), to messages queued by [comfy_print_async()](crate::async_impl::comfy_print_async()).
+//! - Once a category reaches its cap, only messages of that category are dropped, with [DropReason::CategoryFull](crate::config::drop_channel::DropReason::CategoryFull):
+//!   the oldest one if [ON_QUEUE_FULL](crate::config::on_queue_full) is [KeepNewest](crate::config::on_queue_full::On_QueueFull::KeepNewest), 
+//!   otherwise the new one.
+//! - Categories without a cap, and messages without a category, are only capped by [MAX_QUEUE_LENGTH](crate::config::max_queue_length).
+//! - Not loaded from the environment.
+//!
+//! # Default: No caps
+
+use std::collections::BTreeMap;
+use parking_lot::Mutex;
+
+/// Current value of [CATEGORY_CAPS](self).
+static CURRENT: Mutex<BTreeMap<&'static str, usize>> = Mutex::new(BTreeMap::new());
+
+/// Set the cap of `category` in global config [CATEGORY_CAPS](self), returning the cap it replaces, if any.
+pub fn set(category: &'static str, cap: usize) -> Option<usize> {
+	return CURRENT.lock().insert(category, cap);
+}
+
+/// Get the cap of `category` from global config [CATEGORY_CAPS](self).
+pub fn get(category: &str) -> Option<usize> {
+	return CURRENT.lock().get(category).copied();
+}
+
+/// Remove the cap of `category` from global config [CATEGORY_CAPS](self).
+pub fn remove(category: &str) -> Option<usize> {
+	return CURRENT.lock().remove(category);
+}
+
+/// Remove every cap from global config [CATEGORY_CAPS](self).
+pub fn clear() {
+	CURRENT.lock().clear();
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::Message;
+	use crate::async_impl::comfy_print_async;
+	use super::drop_channel::{DropEvent, DropReason};
+	use super::on_queue_full::On_QueueFull;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	let (sender, receiver) = std::sync::mpsc::channel();
+	super::drop_channel::set(sender);
+	assert_eq!(set("telemetry", 2), None);
+	assert_eq!(get("telemetry"), Some(2));
+
+	{
+		crate::async_impl::pause();
+		for index in 1..=5 {
+			comfy_print_async(Message::standard_ln(format!("Telemetry_0{index}")).with_category("telemetry"));
+		}
+
+		comfy_print_async(Message::error_ln("User_01").with_category("user"));
+		comfy_print_async(Message::error_ln("Test_01"));
+
+		let queued = test_utils::get_queue().iter().map(|msg| msg.str().to_string()).collect::<Vec<_>>();
+		assert_eq!(queued, ["Telemetry_01", "Telemetry_02", "User_01", "Test_01"]);
+		assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![DropEvent { kind: OutputKind::Stdout, reason: DropReason::CategoryFull }; 3]);
+	}
+
+	{
+		super::on_queue_full::set(On_QueueFull::KeepNewest);
+		comfy_print_async(Message::standard_ln("Telemetry_06").with_category("telemetry"));
+
+		let queued = test_utils::get_queue().iter().map(|msg| msg.str().to_string()).collect::<Vec<_>>();
+		assert_eq!(queued, ["Telemetry_02", "User_01", "Test_01", "Telemetry_06"]);
+		assert_eq!(receiver.try_iter().count(), 1);
+	}
+
+	{
+		assert_eq!(remove("telemetry"), Some(2));
+		comfy_print_async(Message::standard_ln("Telemetry_07").with_category("telemetry"));
+		assert_eq!(test_utils::get_queue().len(), 5);
+
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written().len(), 5);
+	}
+}
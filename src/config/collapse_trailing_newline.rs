@@ -0,0 +1,46 @@
+//! Determines whether messages that already end with a newline skip the newline appended by `comfy_println!` | `comfy_eprintln!`.
+//! - Avoids the blank line printed when the formatted string already ends with `\n`.
+//! - Applies both to streams and to disk.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [COLLAPSE_TRAILING_NEWLINE](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [COLLAPSE_TRAILING_NEWLINE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_COLLAPSE_TRAILING_NEWLINE";
+
+/// Get global config [COLLAPSE_TRAILING_NEWLINE](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [COLLAPSE_TRAILING_NEWLINE](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::Message;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	{
+		assert_eq!(Message::standard_ln("Test_01\n").to_string(), "Test_01\n\n");
+		
+		set(true);
+		assert_eq!(Message::standard_ln("Test_01\n").to_string(), "Test_01\n");
+		assert_eq!(Message::standard_ln("Test_02").to_string(), "Test_02\n");
+		assert_eq!(Message::standard_ln("Test_03\n\n").to_string(), "Test_03\n\n");
+		assert_eq!(Message::standard("Test_04").to_string(), "Test_04");
+	}
+}
@@ -0,0 +1,79 @@
+//! When set, every message is written to this single stream, regardless of its [OutputKind].
+//! - Useful for in-process `2>&1`-style capture, for tools that only read one stream.
+//! - Messages keep their original [OutputKind], only the stream they are written to changes.
+//! 
+//! # Default: None
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use crate::message::OutputKind;
+
+/// Current value of [COMBINED_STREAM](self). 0 = None, 1 = Stdout, 2 = Stderr.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [COMBINED_STREAM](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_COMBINED_STREAM";
+
+/// Get global config [COMBINED_STREAM](self).
+pub fn get() -> Option<OutputKind> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => Some(OutputKind::Stdout),
+		2 => Some(OutputKind::Stderr),
+		_ => None, // 0
+	};
+}
+
+/// Set global config [COMBINED_STREAM](self).
+pub fn set(new_value: Option<OutputKind>) {
+	let value = match new_value {
+		None => 0,
+		Some(OutputKind::Stdout) => 1,
+		Some(OutputKind::Stderr) => 2,
+	};
+	
+	CURRENT.store(value, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		let current = get();
+		std::env::set_var(ENV_NAME, "Stdin");
+		super::env_vars::load_all();
+		assert_eq!(get(), current);
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "Stderr");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(OutputKind::Stderr));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(OutputKind::Stdout));
+	}
+
+	{
+		set(None);
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprintln!("Test_02");
+
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stderr, "Test_02\n".to_string()),
+		]);
+	}
+
+	{
+		set(Some(OutputKind::Stdout));
+		crate::comfy_println!("Test_03");
+		crate::comfy_eprintln!("Test_04");
+
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_03\n".to_string()),
+			(OutputKind::Stdout, "Test_04\n".to_string()),
+		]);
+	}
+}
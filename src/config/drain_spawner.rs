@@ -0,0 +1,86 @@
+//! When set, schedules printing the queue on a user-provided executor instead of spawning a thread, e.g. an existing thread pool.
+//! - Receives the task that prints the queue, which must eventually be run exactly once, on any thread.
+//! - Until the task finishes, the queue is considered busy: failed messages are queued and printed by that task.
+//! - May run the task inline, on the calling thread.
+//! - Replaces [PRINTER_IDLE_TIMEOUT](crate::config::printer_idle_timeout), which only applies to spawned threads.
+//!
+//! # Default: None (spawn a thread, or print on the caller's thread with feature **single-thread**)
+
+use parking_lot::RwLock;
+
+/// A task printing the queue, see [DRAIN_SPAWNER](self).
+pub type DrainTask = Box<dyn FnOnce() + Send>;
+
+/// A custom executor, see [DRAIN_SPAWNER](self).
+pub type DrainSpawner = Box<dyn Fn(DrainTask) + Send + Sync>;
+
+/// Current value of [DRAIN_SPAWNER](self).
+static CURRENT: RwLock<Option<DrainSpawner>> = RwLock::new(None);
+
+/// Set global config [DRAIN_SPAWNER](self), replacing the previous one.
+pub fn set(spawner: DrainSpawner) {
+	*CURRENT.write() = Some(spawner);
+}
+
+/// Remove global config [DRAIN_SPAWNER](self), going back to spawning threads.
+pub fn clear() {
+	*CURRENT.write() = None;
+}
+
+/// Whether global config [DRAIN_SPAWNER](self) is set.
+pub(crate) fn is_set() -> bool {
+	return CURRENT.read_recursive().is_some();
+}
+
+/// Hands `task` to [DRAIN_SPAWNER](self), or gives it back if it isn't set.
+///
+/// WARNING: Will lock [DRAIN_SPAWNER](self) for reading, recursively so that the spawner may print.
+pub(crate) fn spawn(task: DrainTask) -> Result<(), DrainTask> {
+	let guard = CURRENT.read_recursive();
+	let Some(spawner) = guard.as_ref()
+			else { return Err(task); };
+
+	spawner(task);
+	drop(guard);
+	return Ok(());
+}
+
+#[test]
+fn test() {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+
+	let (sender, receiver) = std::sync::mpsc::channel::<DrainTask>();
+	let tasks_run = Arc::new(AtomicUsize::new(0));
+	let tasks_run_clone = Arc::clone(&tasks_run);
+	let worker = std::thread::spawn(move || {
+		for task in receiver {
+			task();
+			tasks_run_clone.fetch_add(1, Ordering::Relaxed);
+		}
+	});
+
+	let worker_id = worker.thread().id();
+	set(Box::new(move |task| sender.send(task).unwrap()));
+
+	{
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+
+		assert_eq!(tasks_run.load(Ordering::Relaxed), 1);
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+		assert_ne!(std::thread::current().id(), worker_id);
+	}
+
+	{
+		// Dropping the spawner drops the sender, which stops the worker.
+		clear();
+		worker.join().unwrap();
+	}
+}
@@ -0,0 +1,105 @@
+//! Channel notified each time a message is dropped instead of being printed or stored in the queue.
+//! - Sending never blocks, if the receiver was dropped the notification is silently discarded.
+//! - Only user messages are reported, diagnostics that don't fit in the queue are not.
+//! 
+//! # Default: None
+
+use std::sync::mpsc::Sender;
+use parking_lot::Mutex;
+use crate::message::OutputKind;
+
+/// Current value of [DROP_CHANNEL](self).
+static CURRENT: Mutex<Option<Sender<DropEvent>>> = Mutex::new(None);
+
+/// Why a message was dropped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DropReason {
+	/// Printing failed and [MAX_QUEUE_LENGTH](crate::config::max_queue_length) is 0, so the message could not be stored.
+	QueueDisabled,
+	/// The queue reached [MAX_QUEUE_LENGTH](crate::config::max_queue_length), see [ON_QUEUE_FULL](crate::config::on_queue_full).
+	QueueFull,
+	/// Removed from the queue by [drop_oldest()](crate::async_impl::drop_oldest()).
+	Requested,
+	/// No thread could be spawned to print the queue, see [DropQueue](crate::config::on_spawn_failure::On_SpawnFailure::DropQueue).
+	SpawnFailed,
+	/// The message's category reached its cap, see [CATEGORY_CAPS](crate::config::category_caps).
+	CategoryFull,
+	/// Printed after [shutdown()](crate::async_impl::shutdown), see [ON_POST_SHUTDOWN](crate::config::on_post_shutdown).
+	PostShutdown,
+}
+
+/// Sent through [DROP_CHANNEL](self) each time a message is dropped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DropEvent {
+	/// Which stream the dropped message targeted.
+	pub kind: OutputKind,
+	/// Why the message was dropped.
+	pub reason: DropReason,
+}
+
+/// Set global config [DROP_CHANNEL](self), replacing the previous channel.
+pub fn set(new_value: Sender<DropEvent>) {
+	*CURRENT.lock() = Some(new_value);
+}
+
+/// Remove the channel stored in global config [DROP_CHANNEL](self).
+pub fn clear() {
+	*CURRENT.lock() = None;
+}
+
+/// WARNING: Will lock [DROP_CHANNEL](self).
+pub(crate) fn notify(kind: OutputKind, reason: DropReason) {
+	let guard = CURRENT.lock();
+	if let Some(sender) = guard.as_ref() {
+		let _ = sender.send(DropEvent { kind, reason });
+	}
+	
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::config;
+	let _guard = test_utils::lock_tests();
+
+	// Just so the error messages don't interfere with the test.
+	config::allow_logging_print_failures::set(false);
+	config::max_retries::set(0);
+	config::max_queue_length::set(2);
+
+	let (sender, receiver) = std::sync::mpsc::channel();
+	set(sender);
+
+	{
+		test_utils::set_toggle_write_fail(true);
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+		crate::comfy_println!("Test_02");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 2);
+		assert!(receiver.try_recv().is_err());
+	}
+
+	{
+		crate::comfy_println!("Test_03");
+		test_utils::yield_until_idle();
+		crate::comfy_eprintln!("Test_04");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 2);
+
+		assert_eq!(receiver.try_recv(), Ok(DropEvent { kind: OutputKind::Stdout, reason: DropReason::QueueFull }));
+		assert_eq!(receiver.try_recv(), Ok(DropEvent { kind: OutputKind::Stderr, reason: DropReason::QueueFull }));
+		assert!(receiver.try_recv().is_err());
+	}
+
+	{
+		test_utils::reset_globals();
+		config::max_queue_length::set(0);
+		set(std::sync::mpsc::channel().0); // disconnected channel, must be ignored.
+
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_05");
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+}
@@ -0,0 +1,66 @@
+//! Determines whether messages are actually written.
+//! - **false**: Messages are written to [std::io::stdout] | [std::io::stderr].
+//! - **true**: Messages go through everything else (middleware, routing, rendering), but instead of being written
+//!   they are stored in memory, retrieved with [dry_run_output()](crate::async_impl::dry_run_output()). 
+//!   Nothing is written to the log files either.
+//! 
+//! Useful for testing configs and routing.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [DRY_RUN](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [DRY_RUN](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_DRY_RUN";
+
+/// Get global config [DRY_RUN](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [DRY_RUN](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	{
+		set(true);
+		super::combined_stream::set(Some(OutputKind::Stdout));
+		super::indent::set(2);
+
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprintln!("Test_02");
+		crate::comfy_print!("Test_03");
+		test_utils::yield_until_idle();
+
+		assert_eq!(async_impl::dry_run_output(), vec![
+			(OutputKind::Stdout, "  Test_01\n".to_string()),
+			(OutputKind::Stdout, "  Test_02\n".to_string()),
+			(OutputKind::Stdout, "  Test_03".to_string()),
+		]);
+		assert!(async_impl::dry_run_output().is_empty());
+	}
+
+	{
+		set(false);
+		crate::comfy_println!("Test_04");
+		assert!(async_impl::dry_run_output().is_empty());
+		assert_eq!(test_utils::take_written().last(), Some(&(OutputKind::Stdout, "  Test_04\n".to_string())));
+	}
+}
@@ -0,0 +1,75 @@
+//! Determines the order in which [recent()](crate::async_impl::recent()) returns the messages written recently, e.g. for in-app log viewers showing the newest first.
+//! 0. **Chronological**: Oldest first.
+//! 1. **NewestFirst**: Newest first.
+//! 
+//! Writing to [std::io::stdout] | [std::io::stderr] is always chronological, regardless of this config.
+//! 
+//! # Default: [Chronological](EmitOrder::Chronological)
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Current value of [EMIT_ORDER](self).
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [EMIT_ORDER](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_EMIT_ORDER";
+
+/// See [EMIT_ORDER](self).
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum EmitOrder {
+	/// Oldest first.
+	Chronological = 0,
+	/// Newest first.
+	NewestFirst = 1,
+}
+
+impl FromStr for EmitOrder {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "Chronological" => Ok(EmitOrder::Chronological),
+			"1" | "NewestFirst" => Ok(EmitOrder::NewestFirst),
+			_ => Err(format!("Invalid string value for EmitOrder: {}", s)),
+		}
+	}
+}
+
+/// Get global config [EMIT_ORDER](self).
+pub fn get() -> EmitOrder {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => EmitOrder::NewestFirst,
+		_ => EmitOrder::Chronological, // 0
+	};
+}
+
+/// Set global config [EMIT_ORDER](self).
+pub fn set(new_value: EmitOrder) {
+	CURRENT.store(new_value as u8, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		let current = get();
+		std::env::set_var(ENV_NAME, "Reverse");
+		super::env_vars::load_all();
+		assert_eq!(get(), current);
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "NewestFirst");
+		super::env_vars::load_all();
+		assert_eq!(get(), EmitOrder::NewestFirst);
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), EmitOrder::Chronological);
+	}
+}
@@ -0,0 +1,62 @@
+//! Global switch for all output, e.g. to implement a `--quiet` flag.
+//! - **true**: Messages are printed.
+//! - **false**: Messages are discarded as soon as they're printed: they're neither written nor queued.
+//!   The macros still format their arguments.
+//! - Messages already in the queue are still printed.
+//! - Discarded messages are not counted as dropped, see [dropped_count()](crate::async_impl::dropped_count()).
+//! 
+//! # Default: **true**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [ENABLED](self).
+static CURRENT: AtomicBool = AtomicBool::new(true);
+
+/// Environment variable name for global config [ENABLED](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ENABLED";
+
+/// Get global config [ENABLED](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [ENABLED](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+	}
+
+	{
+		super::set_enabled(false);
+		assert_eq!(super::is_enabled(), false);
+
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprint!("Test_02");
+		async_impl::println_slice(&["Test_03"], OutputKind::Stdout);
+		async_impl::thread_buffer_print("Test_04", OutputKind::Stdout);
+		async_impl::thread_flush();
+		test_utils::yield_until_idle();
+
+		assert!(test_utils::take_written().is_empty());
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(async_impl::dropped_count(), 0);
+	}
+
+	{
+		super::set_enabled(true);
+		crate::comfy_println!("Test_05");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_05\n".to_string())]);
+	}
+}
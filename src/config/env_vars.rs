@@ -1,13 +1,27 @@
 //! Functions for loading [comfy_print](crate::config)'s global config variables from the environment.
 //! The key for each config is on a const string named `ENV_NAME` in its respective module. Example: [MAX_RETRIES](max_retries::ENV_NAME).
 //! Configs are not automatically loaded, you must call [load_all](load_all) to load them. But they do have default values.
+//! Use [load_all_strict](load_all_strict) to detect variables that are present but invalid.
 
 use std::env::VarError;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use super::*;
 use crate::config::on_max_retries_reached::On_MaxRetriesReached;
 use crate::config::on_queue_full::On_QueueFull;
 use crate::config::on_queue_printing_fail::On_QueuePrintingFail;
+use crate::config::flush_strategy::FlushStrategy;
+use crate::config::buffer_policy::BufferPolicy;
+use crate::config::emit_order::EmitOrder;
+use crate::config::on_nul_byte::On_NulByte;
+use crate::config::on_spawn_failure::On_SpawnFailure;
+use crate::config::on_post_shutdown::On_PostShutdown;
+#[cfg(feature = "gzip")]
+use crate::config::log_compression::LogCompression;
+use crate::message::OutputKind;
+
+/// Errors of the most recent call to [load_all], see [last_env_load_errors()].
+static LAST_LOAD_ERRORS: parking_lot::Mutex<Option<Vec<(&'static str, String)>>> = parking_lot::Mutex::new(None);
 
 /// Errors that can occur when loading a global config variable from the environment.
 #[derive(Debug)]
@@ -20,6 +34,16 @@ pub enum LoadVarError<T: FromStr> {
 	IOError(std::io::Error),
 }
 
+impl<T: FromStr> Display for LoadVarError<T> where T::Err: Display {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		return match self {
+			LoadVarError::VarError(err) => write!(f, "{err}"),
+			LoadVarError::ParseError(err) => write!(f, "Failed to parse value: {err}"),
+			LoadVarError::IOError(err) => write!(f, "{err}"),
+		};
+	}
+}
+
 /// Results for attempting to load each of [comfy_print](crate::config)'s configs from the environment.
 pub struct LoadVarsResult {
 	/// See [MAX_RETRIES](max_retries).
@@ -36,11 +60,154 @@ pub struct LoadVarsResult {
 	pub log_io_path: Result<String, LoadVarError<String>>,
 	/// See [ON_QUEUE_FULL](on_queue_full).
 	pub on_push_queue_full: Result<On_QueueFull, LoadVarError<On_QueueFull>>,
+	/// See [INDENT](indent).
+	pub indent: Result<usize, LoadVarError<usize>>,
+	/// See [WRAP_WIDTH](wrap_width).
+	pub wrap_width: Result<usize, LoadVarError<usize>>,
+	/// See [TRACK_STDERR_PRINTED](track_stderr_printed).
+	pub track_stderr_printed: Result<bool, LoadVarError<bool>>,
+	/// See [COMBINED_STREAM](combined_stream).
+	pub combined_stream: Result<OutputKind, LoadVarError<OutputKind>>,
+	/// See [FLUSH_STRATEGY](flush_strategy).
+	pub flush_strategy: Result<FlushStrategy, LoadVarError<FlushStrategy>>,
+	/// See [BATCH_PREAMBLE](batch_preamble).
+	pub batch_preamble: Result<String, LoadVarError<String>>,
+	/// See [BATCH_POSTAMBLE](batch_postamble).
+	pub batch_postamble: Result<String, LoadVarError<String>>,
+	/// See [PERSIST_QUEUE_PATH](persist_queue_path).
+	pub persist_queue_path: Result<String, LoadVarError<String>>,
+	/// See [EMIT_ORDER](emit_order).
+	pub emit_order: Result<EmitOrder, LoadVarError<EmitOrder>>,
+	/// See [ANNOTATE_STREAM](annotate_stream).
+	pub annotate_stream: Result<bool, LoadVarError<bool>>,
+	/// See [ALWAYS_ASYNC](always_async).
+	pub always_async: Result<bool, LoadVarError<bool>>,
+	/// See [LOG_MAX_AGE](log_max_age).
+	pub log_max_age: Result<u64, LoadVarError<u64>>,
+	/// See [ALWAYS_LOG_TO_DISK](always_log_to_disk).
+	pub always_log_to_disk: Result<OutputKind, LoadVarError<OutputKind>>,
+	/// See [COLLAPSE_TRAILING_NEWLINE](collapse_trailing_newline).
+	pub collapse_trailing_newline: Result<bool, LoadVarError<bool>>,
+	/// See [QUEUE_WARN_THRESHOLD](queue_warn_threshold).
+	pub queue_warn_threshold: Result<usize, LoadVarError<usize>>,
+	/// See [SHOW_LOCATION](show_location).
+	pub show_location: Result<bool, LoadVarError<bool>>,
+	/// See [SERIALIZE_STARTUP](serialize_startup).
+	pub serialize_startup: Result<bool, LoadVarError<bool>>,
+	/// See [BUFFER_POLICY](buffer_policy).
+	pub buffer_policy: Result<BufferPolicy, LoadVarError<BufferPolicy>>,
+	/// See [PRINTER_IDLE_TIMEOUT](printer_idle_timeout).
+	pub printer_idle_timeout: Result<u64, LoadVarError<u64>>,
+	/// See [ON_NUL_BYTE](on_nul_byte).
+	pub on_nul_byte: Result<On_NulByte, LoadVarError<On_NulByte>>,
+	/// See [DRY_RUN](dry_run).
+	pub dry_run: Result<bool, LoadVarError<bool>>,
+	/// See [ENABLED](enabled).
+	pub enabled: Result<bool, LoadVarError<bool>>,
+	/// See [LOG_COMPRESSION](log_compression).
+	#[cfg(feature = "gzip")]
+	pub log_compression: Result<LogCompression, LoadVarError<LogCompression>>,
+	/// See [BATCH_SEPARATOR](batch_separator).
+	pub batch_separator: Result<String, LoadVarError<String>>,
+	/// See [MAX_DIAGNOSTICS_IN_QUEUE](max_diagnostics_in_queue).
+	pub max_diagnostics_in_queue: Result<usize, LoadVarError<usize>>,
+	/// See [LOG_FILE_BOM](log_file_bom).
+	pub log_file_bom: Result<bool, LoadVarError<bool>>,
+	/// See [ON_SPAWN_FAILURE](on_spawn_failure).
+	pub on_spawn_failure: Result<On_SpawnFailure, LoadVarError<On_SpawnFailure>>,
+	/// See [HEARTBEAT_INTERVAL](heartbeat_interval).
+	pub heartbeat_interval: Result<u64, LoadVarError<u64>>,
+	/// See [MAX_OPEN_LOG_FILES](max_open_log_files).
+	pub max_open_log_files: Result<usize, LoadVarError<usize>>,
+	/// See [STDERR_VERBOSITY](stderr_verbosity).
+	pub stderr_verbosity: Result<u8, LoadVarError<u8>>,
+	/// See [FLUSH_TIMEOUT](flush_timeout).
+	pub flush_timeout: Result<u64, LoadVarError<u64>>,
+	/// See [USE_CONTROLLING_TTY](use_controlling_tty).
+	pub use_controlling_tty: Result<bool, LoadVarError<bool>>,
+	/// See [ON_POST_SHUTDOWN](on_post_shutdown).
+	pub on_post_shutdown: Result<On_PostShutdown, LoadVarError<On_PostShutdown>>,
+	/// See [PROGRESS_THROTTLE](progress_throttle).
+	pub progress_throttle: Result<u64, LoadVarError<u64>>,
+	/// See [SESSION_BANNER](session_banner).
+	pub session_banner: Result<String, LoadVarError<String>>,
+	/// See [GLOBAL_RETRY_BUDGET](global_retry_budget).
+	pub global_retry_budget: Result<usize, LoadVarError<usize>>,
+	/// See [FLUSH_EVERY](flush_every).
+	pub flush_every: Result<usize, LoadVarError<usize>>,
+	/// See [TRANSCRIPT_STDERR_PREFIX](transcript_stderr_prefix).
+	pub transcript_stderr_prefix: Result<String, LoadVarError<String>>,
+}
+
+impl LoadVarsResult {
+	/// Lists the variables that were present in the environment but failed to load, along with a description of each error.
+	/// 
+	/// Variables that were not present ([VarError::NotPresent]) are not considered errors.
+	pub fn errors(&self) -> Vec<(&'static str, String)> {
+		let mut errors = Vec::new();
+		push_err(&mut errors, max_retries::ENV_NAME, &self.max_retries);
+		push_err(&mut errors, max_queue_length::ENV_NAME, &self.max_queue_length);
+		push_err(&mut errors, allow_logging_print_failures::ENV_NAME, &self.allow_logging_print_failures);
+		push_err(&mut errors, on_queue_printing_fail::ENV_NAME, &self.on_retry_printing_fail);
+		push_err(&mut errors, on_max_retries_reached::ENV_NAME, &self.on_max_retries_reached);
+		push_err(&mut errors, log_io_path::ENV_NAME, &self.log_io_path);
+		push_err(&mut errors, on_queue_full::ENV_NAME, &self.on_push_queue_full);
+		push_err(&mut errors, indent::ENV_NAME, &self.indent);
+		push_err(&mut errors, wrap_width::ENV_NAME, &self.wrap_width);
+		push_err(&mut errors, track_stderr_printed::ENV_NAME, &self.track_stderr_printed);
+		push_err(&mut errors, combined_stream::ENV_NAME, &self.combined_stream);
+		push_err(&mut errors, flush_strategy::ENV_NAME, &self.flush_strategy);
+		push_err(&mut errors, batch_preamble::ENV_NAME, &self.batch_preamble);
+		push_err(&mut errors, batch_postamble::ENV_NAME, &self.batch_postamble);
+		push_err(&mut errors, persist_queue_path::ENV_NAME, &self.persist_queue_path);
+		push_err(&mut errors, emit_order::ENV_NAME, &self.emit_order);
+		push_err(&mut errors, annotate_stream::ENV_NAME, &self.annotate_stream);
+		push_err(&mut errors, always_async::ENV_NAME, &self.always_async);
+		push_err(&mut errors, log_max_age::ENV_NAME, &self.log_max_age);
+		push_err(&mut errors, always_log_to_disk::ENV_NAME, &self.always_log_to_disk);
+		push_err(&mut errors, collapse_trailing_newline::ENV_NAME, &self.collapse_trailing_newline);
+		push_err(&mut errors, queue_warn_threshold::ENV_NAME, &self.queue_warn_threshold);
+		push_err(&mut errors, show_location::ENV_NAME, &self.show_location);
+		push_err(&mut errors, serialize_startup::ENV_NAME, &self.serialize_startup);
+		push_err(&mut errors, buffer_policy::ENV_NAME, &self.buffer_policy);
+		push_err(&mut errors, printer_idle_timeout::ENV_NAME, &self.printer_idle_timeout);
+		push_err(&mut errors, on_nul_byte::ENV_NAME, &self.on_nul_byte);
+		push_err(&mut errors, dry_run::ENV_NAME, &self.dry_run);
+		push_err(&mut errors, enabled::ENV_NAME, &self.enabled);
+		#[cfg(feature = "gzip")]
+		push_err(&mut errors, log_compression::ENV_NAME, &self.log_compression);
+		push_err(&mut errors, batch_separator::ENV_NAME, &self.batch_separator);
+		push_err(&mut errors, max_diagnostics_in_queue::ENV_NAME, &self.max_diagnostics_in_queue);
+		push_err(&mut errors, log_file_bom::ENV_NAME, &self.log_file_bom);
+		push_err(&mut errors, on_spawn_failure::ENV_NAME, &self.on_spawn_failure);
+		push_err(&mut errors, heartbeat_interval::ENV_NAME, &self.heartbeat_interval);
+		push_err(&mut errors, max_open_log_files::ENV_NAME, &self.max_open_log_files);
+		push_err(&mut errors, stderr_verbosity::ENV_NAME, &self.stderr_verbosity);
+		push_err(&mut errors, flush_timeout::ENV_NAME, &self.flush_timeout);
+		push_err(&mut errors, use_controlling_tty::ENV_NAME, &self.use_controlling_tty);
+		push_err(&mut errors, on_post_shutdown::ENV_NAME, &self.on_post_shutdown);
+		push_err(&mut errors, progress_throttle::ENV_NAME, &self.progress_throttle);
+		push_err(&mut errors, session_banner::ENV_NAME, &self.session_banner);
+		push_err(&mut errors, global_retry_budget::ENV_NAME, &self.global_retry_budget);
+		push_err(&mut errors, flush_every::ENV_NAME, &self.flush_every);
+		push_err(&mut errors, transcript_stderr_prefix::ENV_NAME, &self.transcript_stderr_prefix);
+		return errors;
+
+		fn push_err<T: FromStr>(errors: &mut Vec<(&'static str, String)>, env_name: &'static str, result: &Result<T, LoadVarError<T>>) 
+			where T::Err: Display {
+			match result {
+				Ok(_) | Err(LoadVarError::VarError(VarError::NotPresent)) => {},
+				Err(err) => errors.push((env_name, err.to_string())),
+			}
+		}
+	}
 }
 
 
 /// Attempts to load all global config variables from the [environment](std::env). [comfy_print](crate::config)'s global config variables will be replaced by any values found in the environment.
 pub fn load_all() -> LoadVarsResult {
+	let update_guard = super::update_lock::write();
+	
 	let max_retries = get_var::<usize>(max_retries::ENV_NAME)
 			.inspect(|new_value| max_retries::set(*new_value));
 
@@ -70,7 +237,122 @@ pub fn load_all() -> LoadVarsResult {
 	let on_push_queue_full = get_var::<On_QueueFull>(on_queue_full::ENV_NAME)
 			.inspect(|new_value| on_queue_full::set(*new_value));
 
-	return LoadVarsResult {
+	let indent = get_var::<usize>(indent::ENV_NAME)
+			.inspect(|new_value| indent::set(*new_value));
+
+	let wrap_width = get_var::<usize>(wrap_width::ENV_NAME)
+			.inspect(|new_value| wrap_width::set(Some(*new_value)));
+
+	let track_stderr_printed = get_var::<bool>(track_stderr_printed::ENV_NAME)
+			.inspect(|new_value| track_stderr_printed::set(*new_value));
+
+	let combined_stream = get_var::<OutputKind>(combined_stream::ENV_NAME)
+			.inspect(|new_value| combined_stream::set(Some(*new_value)));
+
+	let flush_strategy = get_var::<FlushStrategy>(flush_strategy::ENV_NAME)
+			.inspect(|new_value| flush_strategy::set(*new_value));
+
+	let batch_preamble = get_var::<String>(batch_preamble::ENV_NAME)
+			.inspect(|new_value| batch_preamble::set(new_value.as_str()));
+
+	let batch_postamble = get_var::<String>(batch_postamble::ENV_NAME)
+			.inspect(|new_value| batch_postamble::set(new_value.as_str()));
+
+	let persist_queue_path = get_var::<String>(persist_queue_path::ENV_NAME)
+			.inspect(|new_value| persist_queue_path::set(new_value.as_str()));
+
+	let emit_order = get_var::<EmitOrder>(emit_order::ENV_NAME)
+			.inspect(|new_value| emit_order::set(*new_value));
+
+	let annotate_stream = get_var::<bool>(annotate_stream::ENV_NAME)
+			.inspect(|new_value| annotate_stream::set(*new_value));
+
+	let always_async = get_var::<bool>(always_async::ENV_NAME)
+			.inspect(|new_value| always_async::set(*new_value));
+
+	let log_max_age = get_var::<u64>(log_max_age::ENV_NAME)
+			.inspect(|new_value| log_max_age::set(Some(std::time::Duration::from_secs(*new_value))));
+
+	let always_log_to_disk = get_var::<OutputKind>(always_log_to_disk::ENV_NAME)
+			.inspect(|new_value| always_log_to_disk::set(Some(*new_value)));
+
+	let collapse_trailing_newline = get_var::<bool>(collapse_trailing_newline::ENV_NAME)
+			.inspect(|new_value| collapse_trailing_newline::set(*new_value));
+
+	let queue_warn_threshold = get_var::<usize>(queue_warn_threshold::ENV_NAME)
+			.inspect(|new_value| queue_warn_threshold::set(Some(*new_value)));
+
+	let show_location = get_var::<bool>(show_location::ENV_NAME)
+			.inspect(|new_value| show_location::set(*new_value));
+
+	let serialize_startup = get_var::<bool>(serialize_startup::ENV_NAME)
+			.inspect(|new_value| serialize_startup::set(*new_value));
+
+	let buffer_policy = get_var::<BufferPolicy>(buffer_policy::ENV_NAME)
+			.inspect(|new_value| buffer_policy::set(*new_value));
+
+	let printer_idle_timeout = get_var::<u64>(printer_idle_timeout::ENV_NAME)
+			.inspect(|new_value| printer_idle_timeout::set(std::time::Duration::from_millis(*new_value)));
+
+	let on_nul_byte = get_var::<On_NulByte>(on_nul_byte::ENV_NAME)
+			.inspect(|new_value| on_nul_byte::set(*new_value));
+
+	let dry_run = get_var::<bool>(dry_run::ENV_NAME)
+			.inspect(|new_value| dry_run::set(*new_value));
+
+	let enabled = get_var::<bool>(enabled::ENV_NAME)
+			.inspect(|new_value| enabled::set(*new_value));
+
+	#[cfg(feature = "gzip")]
+	let log_compression = get_var::<LogCompression>(log_compression::ENV_NAME)
+			.inspect(|new_value| log_compression::set(*new_value));
+
+	let batch_separator = get_var::<String>(batch_separator::ENV_NAME)
+			.inspect(|new_value| batch_separator::set(new_value.as_str()));
+
+	let max_diagnostics_in_queue = get_var::<usize>(max_diagnostics_in_queue::ENV_NAME)
+			.inspect(|new_value| max_diagnostics_in_queue::set(*new_value));
+
+	let log_file_bom = get_var::<bool>(log_file_bom::ENV_NAME)
+			.inspect(|new_value| log_file_bom::set(*new_value));
+
+	let on_spawn_failure = get_var::<On_SpawnFailure>(on_spawn_failure::ENV_NAME)
+			.inspect(|new_value| on_spawn_failure::set(*new_value));
+
+	let heartbeat_interval = get_var::<u64>(heartbeat_interval::ENV_NAME)
+			.inspect(|new_value| heartbeat_interval::set(Some(std::time::Duration::from_millis(*new_value))));
+
+	let max_open_log_files = get_var::<usize>(max_open_log_files::ENV_NAME)
+			.inspect(|new_value| max_open_log_files::set(*new_value));
+
+	let stderr_verbosity = get_var::<u8>(stderr_verbosity::ENV_NAME)
+			.inspect(|new_value| stderr_verbosity::set(*new_value));
+
+	let flush_timeout = get_var::<u64>(flush_timeout::ENV_NAME)
+			.inspect(|new_value| flush_timeout::set(Some(std::time::Duration::from_millis(*new_value))));
+
+	let use_controlling_tty = get_var::<bool>(use_controlling_tty::ENV_NAME)
+			.inspect(|new_value| use_controlling_tty::set(*new_value));
+
+	let on_post_shutdown = get_var::<On_PostShutdown>(on_post_shutdown::ENV_NAME)
+			.inspect(|new_value| on_post_shutdown::set(*new_value));
+
+	let progress_throttle = get_var::<u64>(progress_throttle::ENV_NAME)
+			.inspect(|new_value| progress_throttle::set(std::time::Duration::from_millis(*new_value)));
+
+	let session_banner = get_var::<String>(session_banner::ENV_NAME)
+			.inspect(|new_value| session_banner::set(Some(new_value.as_str())));
+
+	let global_retry_budget = get_var::<usize>(global_retry_budget::ENV_NAME)
+			.inspect(|new_value| global_retry_budget::set(Some(*new_value)));
+
+	let flush_every = get_var::<usize>(flush_every::ENV_NAME)
+			.inspect(|new_value| flush_every::set(*new_value));
+
+	let transcript_stderr_prefix = get_var::<String>(transcript_stderr_prefix::ENV_NAME)
+			.inspect(|new_value| transcript_stderr_prefix::set(new_value.as_str()));
+
+	let result = LoadVarsResult {
 		max_retries,
 		max_queue_length,
 		allow_logging_print_failures,
@@ -78,7 +360,51 @@ pub fn load_all() -> LoadVarsResult {
 		on_max_retries_reached,
 		log_io_path,
 		on_push_queue_full,
+		indent,
+		wrap_width,
+		track_stderr_printed,
+		combined_stream,
+		flush_strategy,
+		batch_preamble,
+		batch_postamble,
+		persist_queue_path,
+		emit_order,
+		annotate_stream,
+		always_async,
+		log_max_age,
+		always_log_to_disk,
+		collapse_trailing_newline,
+		queue_warn_threshold,
+		show_location,
+		serialize_startup,
+		buffer_policy,
+		printer_idle_timeout,
+		on_nul_byte,
+		dry_run,
+		enabled,
+		#[cfg(feature = "gzip")]
+		log_compression,
+		batch_separator,
+		max_diagnostics_in_queue,
+		log_file_bom,
+		on_spawn_failure,
+		heartbeat_interval,
+		max_open_log_files,
+		stderr_verbosity,
+		flush_timeout,
+		use_controlling_tty,
+		on_post_shutdown,
+		progress_throttle,
+		session_banner,
+		global_retry_budget,
+		flush_every,
+		transcript_stderr_prefix,
 	};
+	
+	drop(update_guard);
+	
+	*LAST_LOAD_ERRORS.lock() = Some(result.errors());
+	return result;
 
 	fn get_var<T: FromStr>(var_name: &'static str) -> Result<T, LoadVarError<T>> {
 		return match std::env::var(var_name) {
@@ -90,4 +416,91 @@ pub fn load_all() -> LoadVarsResult {
 			Err(err) => Err(LoadVarError::VarError(err)),
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// The [errors](LoadVarsResult::errors()) of the most recent call to [load_all] (or [load_all_strict]), 
+/// so that code far from the call site can check for misconfiguration.
+/// 
+/// Empty if the environment was never loaded, or since [clear_last_env_load_errors()].
+pub fn last_env_load_errors() -> Vec<(&'static str, String)> {
+	return LAST_LOAD_ERRORS.lock().clone().unwrap_or_default();
+}
+
+/// Forgets the errors returned by [last_env_load_errors()], until the next call to [load_all].
+pub fn clear_last_env_load_errors() {
+	*LAST_LOAD_ERRORS.lock() = None;
+}
+
+/// Same as [load_all], but fails if any variable was present in the environment but could not be loaded.
+/// 
+/// Valid variables are still loaded, even if others fail.
+/// 
+/// # Returns
+/// 
+/// * `Ok(())` if every variable present in the environment was loaded.
+/// * `Err(Vec<(env_name, error)>)` listing each variable that failed to load, see [LoadVarsResult::errors()].
+pub fn load_all_strict() -> Result<(), Vec<(&'static str, String)>> {
+	let errors = load_all().errors();
+	
+	if errors.is_empty() {
+		return Ok(());
+	} else {
+		return Err(errors);
+	}
+}
+
+#[test]
+fn test_load_all_strict() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		assert_eq!(load_all_strict(), Ok(()));
+	}
+
+	{
+		std::env::set_var(max_retries::ENV_NAME, "abc");
+		std::env::set_var(max_queue_length::ENV_NAME, "20");
+
+		let errors = load_all_strict().unwrap_err();
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].0, max_retries::ENV_NAME);
+		assert_eq!(max_retries::get(), 64);
+		assert_eq!(max_queue_length::get(), 20);
+	}
+
+	{
+		std::env::set_var(max_retries::ENV_NAME, "5");
+		assert_eq!(load_all_strict(), Ok(()));
+		assert_eq!(max_retries::get(), 5);
+	}
+}
+
+#[test]
+fn test_last_env_load_errors() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+	
+	{
+		assert!(last_env_load_errors().is_empty());
+		
+		std::env::set_var(max_retries::ENV_NAME, "abc");
+		let _ = load_all();
+		
+		let errors = last_env_load_errors();
+		assert_eq!(errors.len(), 1);
+		assert_eq!(errors[0].0, max_retries::ENV_NAME);
+		assert_eq!(last_env_load_errors(), errors);
+	}
+	
+	{
+		clear_last_env_load_errors();
+		assert!(last_env_load_errors().is_empty());
+		
+		std::env::set_var(max_retries::ENV_NAME, "abc");
+		let _ = load_all();
+		std::env::set_var(max_retries::ENV_NAME, "5");
+		let _ = load_all();
+		assert!(last_env_load_errors().is_empty());
+	}
+}
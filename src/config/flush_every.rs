@@ -0,0 +1,92 @@
+//! While printing the queue, flush only every N messages instead of after each of them.
+//! Each message is still written immediately, only the [flush](std::io::Write::flush) is batched, saving syscalls without delaying output as much as full buffering.
+//! - Only applies to streams where [FLUSH_STRATEGY](crate::config::flush_strategy) resolves to [EveryMessage](crate::config::flush_strategy::FlushStrategy::EveryMessage).
+//! - Messages are counted across both streams, the message reaching N flushes its own stream.
+//! - Messages not flushed yet are flushed once the queue is empty, or by [flush_with()](crate::async_impl::flush_with()).
+//! - Messages printed directly by the caller (when the queue is empty) are always flushed immediately.
+//! - 0 behaves like 1.
+//! 
+//! # Default: **1** (flush after each message)
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current value of [FLUSH_EVERY](self).
+static CURRENT: AtomicUsize = AtomicUsize::new(1);
+
+/// How many messages were written without being flushed.
+static UNFLUSHED: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variable name for global config [FLUSH_EVERY](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_FLUSH_EVERY";
+
+/// Get global config [FLUSH_EVERY](self).
+pub fn get() -> usize { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [FLUSH_EVERY](self).
+pub fn set(new_value: usize) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+/// Counts a message about to be written, returns whether it should be flushed.
+pub(crate) fn tick() -> bool {
+	let limit = get().max(1);
+	let previous = UNFLUSHED.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+		if count + 1 >= limit {
+			return Some(0);
+		} else {
+			return Some(count + 1);
+		}
+	}).unwrap_or_default();
+	
+	return previous + 1 >= limit;
+}
+
+/// Forgets the messages written since the last flush, call after flushing both streams.
+pub(crate) fn reset() {
+	UNFLUSHED.store(0, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "8");
+		super::env_vars::load_all();
+		assert_eq!(get(), 8);
+	}
+
+	{
+		set(3);
+		crate::async_impl::pause();
+		for index in 1..=7 {
+			crate::comfy_println!("Test_{index:02}");
+		}
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		
+		assert_eq!(test_utils::take_written().len(), 7);
+		assert_eq!(test_utils::take_flushed(), [OutputKind::Stdout, OutputKind::Stdout]);
+	}
+
+	{
+		// The queue being empty flushed the 7th message, so counting starts over.
+		crate::async_impl::pause();
+		crate::comfy_println!("Test_08");
+		crate::comfy_println!("Test_09");
+		crate::comfy_println!("Test_10");
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_flushed(), [OutputKind::Stdout]);
+	}
+
+	{
+		set(0);
+		crate::async_impl::pause();
+		crate::comfy_println!("Test_11");
+		crate::comfy_println!("Test_12");
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_flushed(), [OutputKind::Stdout, OutputKind::Stdout]);
+	}
+}
@@ -0,0 +1,148 @@
+//! Determines when [std::io::stdout] | [std::io::stderr] are flushed while printing the queue.
+//! 0. **EveryMessage**: Flush after each message.
+//! 1. **OnDrainEnd**: Flush once the queue is empty, batching the messages printed in between.
+//! 2. **Auto**: **EveryMessage** for streams that are terminals, **OnDrainEnd** otherwise (pipes, files).
+//! 	- Whether each stream is a terminal is [detected](std::io::IsTerminal) once, then cached.
+//! 
+//! [BUFFER_POLICY](crate::config::buffer_policy) overrides this for each stream.
+//! 
+//! Messages printed directly by the caller (when the queue is empty) are always flushed immediately.
+//! 
+//! # Default: [EveryMessage](FlushStrategy::EveryMessage)
+
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use crate::message::OutputKind;
+
+/// Current value of [FLUSH_STRATEGY](self).
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Whether [std::io::stdout] is a terminal, detected on the first call to [resolve()].
+static STDOUT_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+/// Whether [std::io::stderr] is a terminal, detected on the first call to [resolve()].
+static STDERR_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+/// Environment variable name for global config [FLUSH_STRATEGY](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_FLUSH_STRATEGY";
+
+/// See [FLUSH_STRATEGY](self).
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlushStrategy {
+	/// Flush after each message.
+	EveryMessage = 0,
+	/// Flush once the queue is empty.
+	OnDrainEnd = 1,
+	/// [EveryMessage](FlushStrategy::EveryMessage) for terminals, [OnDrainEnd](FlushStrategy::OnDrainEnd) otherwise.
+	Auto = 2,
+}
+
+impl FromStr for FlushStrategy {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "EveryMessage" => Ok(FlushStrategy::EveryMessage),
+			"1" | "OnDrainEnd" => Ok(FlushStrategy::OnDrainEnd),
+			"2" | "Auto" => Ok(FlushStrategy::Auto),
+			_ => Err(format!("Invalid string value for FlushStrategy: {}", s)),
+		}
+	}
+}
+
+/// Get global config [FLUSH_STRATEGY](self).
+pub fn get() -> FlushStrategy {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => FlushStrategy::OnDrainEnd,
+		2 => FlushStrategy::Auto,
+		_ => FlushStrategy::EveryMessage, // 0
+	};
+}
+
+/// Set global config [FLUSH_STRATEGY](self).
+pub fn set(new_value: FlushStrategy) {
+	CURRENT.store(new_value as u8, Ordering::Relaxed);
+}
+
+/// The strategy to use for `stream`, applying [BUFFER_POLICY](crate::config::buffer_policy) and resolving [Auto](FlushStrategy::Auto). 
+/// Never returns [Auto](FlushStrategy::Auto).
+pub fn resolve(stream: OutputKind) -> FlushStrategy {
+	let strategy = super::buffer_policy::get()
+			.for_stream(stream)
+			.unwrap_or_else(get);
+	
+	return match strategy {
+		FlushStrategy::Auto => {
+			let is_terminal = match stream {
+				OutputKind::Stdout => *STDOUT_IS_TERMINAL.get_or_init(|| std::io::stdout().is_terminal()),
+				OutputKind::Stderr => *STDERR_IS_TERMINAL.get_or_init(|| std::io::stderr().is_terminal()),
+			};
+			
+			resolve_auto(is_terminal)
+		},
+		strategy => strategy,
+	};
+}
+
+fn resolve_auto(is_terminal: bool) -> FlushStrategy {
+	if is_terminal {
+		return FlushStrategy::EveryMessage;
+	} else {
+		return FlushStrategy::OnDrainEnd;
+	}
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		let current = get();
+		std::env::set_var(ENV_NAME, "AlwaysFlush");
+		super::env_vars::load_all();
+		assert_eq!(get(), current);
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "OnDrainEnd");
+		super::env_vars::load_all();
+		assert_eq!(get(), FlushStrategy::OnDrainEnd);
+
+		std::env::set_var(ENV_NAME, "2");
+		super::env_vars::load_all();
+		assert_eq!(get(), FlushStrategy::Auto);
+	}
+
+	{
+		assert_eq!(resolve_auto(true), FlushStrategy::EveryMessage);
+		assert_eq!(resolve_auto(false), FlushStrategy::OnDrainEnd);
+	}
+
+	{
+		set(FlushStrategy::EveryMessage);
+		assert_eq!(resolve(OutputKind::Stdout), FlushStrategy::EveryMessage);
+
+		set(FlushStrategy::OnDrainEnd);
+		assert_eq!(resolve(OutputKind::Stderr), FlushStrategy::OnDrainEnd);
+
+		set(FlushStrategy::Auto);
+		let expected = resolve_auto(std::io::stdout().is_terminal());
+		assert_eq!(resolve(OutputKind::Stdout), expected);
+	}
+
+	{
+		test_utils::set_toggle_write_fail(true);
+		crate::comfy_println!("Test_01");
+		crate::comfy_println!("Test_02");
+		
+		test_utils::set_toggle_write_fail(false);
+		crate::comfy_println!("Test_03");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+}
@@ -0,0 +1,73 @@
+//! Deadline of [flush_with()](crate::async_impl::flush_with()) when it's called without one, 
+//! so that flushing can't block forever if a stream never recovers.
+//! - Once it passes, [flush_with()](crate::async_impl::flush_with()) returns [TimedOut](std::io::ErrorKind::TimedOut), 
+//!   messages that were not printed are kept in the queue.
+//! - Stored in whole milliseconds, durations shorter than a millisecond disable it.
+//! - The environment variable is read in milliseconds.
+//! 
+//! # Default: None (no deadline)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Current value of [FLUSH_TIMEOUT](self) in milliseconds, 0 means None.
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+
+/// Environment variable name for global config [FLUSH_TIMEOUT](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_FLUSH_TIMEOUT";
+
+/// Get global config [FLUSH_TIMEOUT](self).
+pub fn get() -> Option<Duration> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		0 => None,
+		millis => Some(Duration::from_millis(millis)),
+	};
+}
+
+/// Set global config [FLUSH_TIMEOUT](self).
+pub fn set(new_value: Option<Duration>) {
+	let millis = new_value.map(|duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)).unwrap_or(0);
+	CURRENT.store(millis, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use std::time::Instant;
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+	super::on_queue_printing_fail::set(super::on_queue_printing_fail::On_QueuePrintingFail::Return);
+
+	{
+		std::env::set_var(ENV_NAME, "250");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(Duration::from_millis(250)));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), None);
+	}
+
+	{
+		set(Some(Duration::from_millis(20)));
+		test_utils::set_toggle_write_fail(true);
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+
+		let start = Instant::now();
+		let result = async_impl::flush_with(usize::MAX, None);
+		assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+		assert!(start.elapsed() < Duration::from_secs(5));
+
+		assert_eq!(test_utils::get_queue().len(), 1);
+		assert_eq!(async_impl::STATE.lock().is_busy(), false);
+	}
+
+	{
+		test_utils::set_toggle_write_fail(false);
+		assert!(async_impl::flush_with(usize::MAX, None).is_ok());
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+	}
+}
@@ -0,0 +1,95 @@
+//! Maximum number of retries across all messages, e.g. so that a stream that stays broken doesn't cause endless retry work 
+//! when many messages are printed.
+//! - Each retry of a failed write consumes one, regardless of [MAX_RETRIES](crate::config::max_retries) 
+//!   or of which message is retried, including retries of [flush_with()](crate::async_impl::flush_with()).
+//! - Once exhausted, the next failure acts as if [MAX_RETRIES](crate::config::max_retries) was reached:
+//!   [ON_MAX_RETRIES_REACHED](crate::config::on_max_retries_reached) applies, or [flush_with()](crate::async_impl::flush_with()) returns the error.
+//! - Any successful write restores the whole budget.
+//! - **None** disables the budget.
+//!
+//! # Default: None
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current value of [GLOBAL_RETRY_BUDGET](self), [usize::MAX] means None.
+static CURRENT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Retries consumed since the last successful write.
+pub(crate) static SPENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variable name for global config [GLOBAL_RETRY_BUDGET](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_GLOBAL_RETRY_BUDGET";
+
+/// Get global config [GLOBAL_RETRY_BUDGET](self).
+pub fn get() -> Option<usize> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		usize::MAX => None,
+		budget => Some(budget),
+	};
+}
+
+/// Set global config [GLOBAL_RETRY_BUDGET](self). `Some(usize::MAX)` is the same as `None`.
+pub fn set(new_value: Option<usize>) { CURRENT.store(new_value.unwrap_or(usize::MAX), Ordering::Relaxed); }
+
+/// Consumes one retry, returns false if the budget was already exhausted.
+pub(crate) fn spend() -> bool {
+	let Some(budget) = get()
+			else { return true; };
+	
+	return SPENT.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |spent| (spent < budget).then_some(spent + 1)).is_ok();
+}
+
+/// Restores the whole budget, after a successful write.
+pub(crate) fn refill() {
+	SPENT.store(0, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	use super::on_max_retries_reached::On_MaxRetriesReached;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+	super::on_max_retries_reached::set(On_MaxRetriesReached::Return);
+
+	{
+		std::env::set_var(ENV_NAME, "8");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(8));
+	}
+
+	set(Some(3));
+
+	{
+		test_utils::reset_write_attempts();
+		test_utils::set_toggle_write_fail(true);
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+
+		// The first write, then one per retry, far below MAX_RETRIES.
+		assert_eq!(test_utils::write_attempts(), 1 + 1 + 3);
+		assert_eq!(test_utils::get_queue().len(), 1);
+	}
+
+	{
+		// Exhausted: the next drain gives up after its first failure.
+		test_utils::reset_write_attempts();
+		crate::async_impl::comfy_print_async(crate::message::Message::standard_ln("Test_02"));
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::write_attempts(), 1);
+		assert_eq!(test_utils::get_queue().len(), 2);
+	}
+
+	{
+		test_utils::set_toggle_write_fail(false);
+		crate::comfy_println!("Test_03");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stdout, "Test_02\n".to_string()),
+			(OutputKind::Stdout, "Test_03\n".to_string()),
+		]);
+		assert_eq!(SPENT.load(Ordering::Relaxed), 0);
+	}
+}
@@ -0,0 +1,52 @@
+//! How often [ON_HEARTBEAT](crate::config::on_heartbeat) is invoked while printing the queue keeps failing, 
+//! e.g. so that a watchdog knows [comfy_print](crate) is still trying when the terminal is broken.
+//! - Measured from the first failure since the queue was last empty.
+//! - Checked each time writing a message from the queue fails, so it's only as precise as the retries are frequent.
+//! - Stored in whole milliseconds, durations shorter than a millisecond disable the heartbeat.
+//! - The environment variable is read in milliseconds.
+//! 
+//! # Default: None (no heartbeat)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Current value of [HEARTBEAT_INTERVAL](self) in milliseconds, 0 means None.
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+
+/// Environment variable name for global config [HEARTBEAT_INTERVAL](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_HEARTBEAT_INTERVAL";
+
+/// Get global config [HEARTBEAT_INTERVAL](self).
+pub fn get() -> Option<Duration> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		0 => None,
+		millis => Some(Duration::from_millis(millis)),
+	};
+}
+
+/// Set global config [HEARTBEAT_INTERVAL](self).
+pub fn set(new_value: Option<Duration>) {
+	let millis = new_value.map(|duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)).unwrap_or(0);
+	CURRENT.store(millis, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "250");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(Duration::from_millis(250)));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), None);
+	}
+
+	{
+		set(Some(Duration::from_micros(500)));
+		assert_eq!(get(), None);
+	}
+}
@@ -0,0 +1,55 @@
+//! Number of spaces inserted before each line of every message.
+//! - Lines are split on `\n`, empty lines are not indented.
+//! - Can be overridden per message with [Message::with_indent()](crate::message::Message::with_indent()).
+//! 
+//! # Default: **0**
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current value of [INDENT](self).
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variable name for global config [INDENT](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_INDENT";
+
+/// Get global config [INDENT](self).
+pub fn get() -> usize { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [INDENT](self).
+pub fn set(new_value: usize) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::Message;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "4");
+		super::env_vars::load_all();
+		assert_eq!(get(), 4);
+
+		std::env::set_var(ENV_NAME, "2");
+		super::env_vars::load_all();
+		assert_eq!(get(), 2);
+	}
+
+	{
+		set(0);
+		assert_eq!(Message::standard_ln("Line_01\nLine_02\nLine_03").to_string(), "Line_01\nLine_02\nLine_03\n");
+	}
+
+	{
+		set(2);
+		assert_eq!(Message::standard_ln("Line_01\nLine_02\nLine_03").to_string(), "  Line_01\n  Line_02\n  Line_03\n");
+		assert_eq!(Message::standard("Line_01\n\nLine_03\n").to_string(), "  Line_01\n\n  Line_03\n");
+	}
+
+	{
+		let msg = Message::error_ln("Line_01\nLine_02\nLine_03").with_indent(4);
+		assert_eq!(msg.to_string(), "    Line_01\n    Line_02\n    Line_03\n");
+
+		let msg = Message::error_ln("Line_01\nLine_02").with_indent(0);
+		assert_eq!(msg.to_string(), "Line_01\nLine_02\n");
+	}
+}
@@ -0,0 +1,282 @@
+//! Serializes [comfy_print](crate::config)'s global config variables to/from a single JSON object.
+//! Useful for persisting user preferences, independently of the [environment](super::env_vars).
+//! 
+//! Only the settable globals are included, callbacks are not serialized.
+//! 
+//! Requires feature **json**.
+
+use serde::{Deserialize, Serialize};
+use super::*;
+use crate::config::on_max_retries_reached::On_MaxRetriesReached;
+use crate::config::on_queue_full::On_QueueFull;
+use crate::config::on_queue_printing_fail::On_QueuePrintingFail;
+use crate::config::flush_strategy::FlushStrategy;
+use crate::config::buffer_policy::BufferPolicy;
+use crate::config::emit_order::EmitOrder;
+use crate::config::on_nul_byte::On_NulByte;
+use crate::config::on_spawn_failure::On_SpawnFailure;
+use crate::config::on_post_shutdown::On_PostShutdown;
+#[cfg(feature = "gzip")]
+use crate::config::log_compression::LogCompression;
+use crate::message::OutputKind;
+
+/// Errors that can occur when loading the global config from JSON.
+#[derive(Debug)]
+pub enum JsonError {
+	/// The string could not be deserialized into the config object.
+	ParseError(serde_json::Error),
+	/// The [LOG_IO_PATH](log_io_path) was rejected by [log_io_path::set()].
+	IOError(std::io::Error),
+}
+
+/// Every settable global config variable, as it's represented in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct JsonConfig {
+	max_queue_length: usize,
+	max_retries: usize,
+	allow_logging_print_failures: bool,
+	on_queue_full: On_QueueFull,
+	on_max_retries_reached: On_MaxRetriesReached,
+	on_queue_printing_fail: On_QueuePrintingFail,
+	log_io_path: String,
+	indent: usize,
+	wrap_width: Option<usize>,
+	track_stderr_printed: bool,
+	combined_stream: Option<OutputKind>,
+	flush_strategy: FlushStrategy,
+	batch_preamble: String,
+	batch_postamble: String,
+	persist_queue_path: String,
+	emit_order: EmitOrder,
+	annotate_stream: bool,
+	always_async: bool,
+	log_max_age: Option<std::time::Duration>,
+	always_log_to_disk: Option<OutputKind>,
+	collapse_trailing_newline: bool,
+	queue_warn_threshold: Option<usize>,
+	show_location: bool,
+	serialize_startup: bool,
+	buffer_policy: BufferPolicy,
+	printer_idle_timeout: std::time::Duration,
+	on_nul_byte: On_NulByte,
+	dry_run: bool,
+	enabled: bool,
+	#[cfg(feature = "gzip")]
+	log_compression: LogCompression,
+	batch_separator: String,
+	max_diagnostics_in_queue: usize,
+	log_file_bom: bool,
+	on_spawn_failure: On_SpawnFailure,
+	heartbeat_interval: Option<std::time::Duration>,
+	max_open_log_files: usize,
+	stderr_verbosity: u8,
+	flush_timeout: Option<std::time::Duration>,
+	use_controlling_tty: bool,
+	on_post_shutdown: On_PostShutdown,
+	progress_throttle: std::time::Duration,
+	session_banner: Option<String>,
+	global_retry_budget: Option<usize>,
+	flush_every: usize,
+	transcript_stderr_prefix: String,
+}
+
+impl JsonConfig {
+	fn current() -> Self {
+		let mut log_io_path = String::new();
+		log_io_path::get(&mut log_io_path);
+		
+		return Self {
+			max_queue_length: max_queue_length::get(),
+			max_retries: max_retries::get(),
+			allow_logging_print_failures: allow_logging_print_failures::get(),
+			on_queue_full: on_queue_full::get(),
+			on_max_retries_reached: on_max_retries_reached::get(),
+			on_queue_printing_fail: on_queue_printing_fail::get(),
+			log_io_path,
+			indent: indent::get(),
+			wrap_width: wrap_width::get(),
+			track_stderr_printed: track_stderr_printed::get(),
+			combined_stream: combined_stream::get(),
+			flush_strategy: flush_strategy::get(),
+			batch_preamble: { let mut preamble = String::new(); batch_preamble::get(&mut preamble); preamble },
+			batch_postamble: { let mut postamble = String::new(); batch_postamble::get(&mut postamble); postamble },
+			persist_queue_path: { let mut path = String::new(); persist_queue_path::get(&mut path); path },
+			emit_order: emit_order::get(),
+			annotate_stream: annotate_stream::get(),
+			always_async: always_async::get(),
+			log_max_age: log_max_age::get(),
+			always_log_to_disk: always_log_to_disk::get(),
+			collapse_trailing_newline: collapse_trailing_newline::get(),
+			queue_warn_threshold: queue_warn_threshold::get(),
+			show_location: show_location::get(),
+			serialize_startup: serialize_startup::get(),
+			buffer_policy: buffer_policy::get(),
+			printer_idle_timeout: printer_idle_timeout::get(),
+			on_nul_byte: on_nul_byte::get(),
+			dry_run: dry_run::get(),
+			enabled: enabled::get(),
+			#[cfg(feature = "gzip")]
+			log_compression: log_compression::get(),
+			batch_separator: { let mut separator = String::new(); batch_separator::get(&mut separator); separator },
+			max_diagnostics_in_queue: max_diagnostics_in_queue::get(),
+			log_file_bom: log_file_bom::get(),
+			on_spawn_failure: on_spawn_failure::get(),
+			heartbeat_interval: heartbeat_interval::get(),
+			max_open_log_files: max_open_log_files::get(),
+			stderr_verbosity: stderr_verbosity::get(),
+			flush_timeout: flush_timeout::get(),
+			use_controlling_tty: use_controlling_tty::get(),
+			on_post_shutdown: on_post_shutdown::get(),
+			progress_throttle: progress_throttle::get(),
+			session_banner: session_banner::get(),
+			global_retry_budget: global_retry_budget::get(),
+			flush_every: flush_every::get(),
+			transcript_stderr_prefix: { let mut prefix = String::new(); transcript_stderr_prefix::get(&mut prefix); prefix },
+		};
+	}
+}
+
+/// Serializes all of [comfy_print](crate::config)'s global config variables into a JSON object.
+pub fn to_json() -> String {
+	return serde_json::to_string(&JsonConfig::current())
+			.expect("JsonConfig only contains primitives and unit enums, serializing it cannot fail.");
+}
+
+/// Replaces [comfy_print](crate::config)'s global config variables with the values in `json`.
+/// 
+/// The whole object is validated before any variable is replaced, on error none of the globals are changed.
+/// An empty `log_io_path` leaves [LOG_IO_PATH](log_io_path) untouched.
+pub fn from_json(json: &str) -> Result<(), JsonError> {
+	let config = serde_json::from_str::<JsonConfig>(json)
+			.map_err(JsonError::ParseError)?;
+	
+	let update_guard = super::update_lock::write();
+	
+	if config.log_io_path.is_empty() == false {
+		log_io_path::set(config.log_io_path.as_str())
+				.map_err(JsonError::IOError)?;
+	}
+	
+	max_queue_length::set(config.max_queue_length);
+	max_retries::set(config.max_retries);
+	allow_logging_print_failures::set(config.allow_logging_print_failures);
+	on_queue_full::set(config.on_queue_full);
+	on_max_retries_reached::set(config.on_max_retries_reached);
+	on_queue_printing_fail::set(config.on_queue_printing_fail);
+	indent::set(config.indent);
+	wrap_width::set(config.wrap_width);
+	track_stderr_printed::set(config.track_stderr_printed);
+	combined_stream::set(config.combined_stream);
+	flush_strategy::set(config.flush_strategy);
+	batch_preamble::set(config.batch_preamble.as_str());
+	batch_postamble::set(config.batch_postamble.as_str());
+	persist_queue_path::set(config.persist_queue_path.as_str());
+	emit_order::set(config.emit_order);
+	annotate_stream::set(config.annotate_stream);
+	always_async::set(config.always_async);
+	log_max_age::set(config.log_max_age);
+	always_log_to_disk::set(config.always_log_to_disk);
+	collapse_trailing_newline::set(config.collapse_trailing_newline);
+	queue_warn_threshold::set(config.queue_warn_threshold);
+	show_location::set(config.show_location);
+	serialize_startup::set(config.serialize_startup);
+	buffer_policy::set(config.buffer_policy);
+	printer_idle_timeout::set(config.printer_idle_timeout);
+	on_nul_byte::set(config.on_nul_byte);
+	dry_run::set(config.dry_run);
+	enabled::set(config.enabled);
+	#[cfg(feature = "gzip")]
+	log_compression::set(config.log_compression);
+	batch_separator::set(config.batch_separator.as_str());
+	max_diagnostics_in_queue::set(config.max_diagnostics_in_queue);
+	log_file_bom::set(config.log_file_bom);
+	on_spawn_failure::set(config.on_spawn_failure);
+	heartbeat_interval::set(config.heartbeat_interval);
+	max_open_log_files::set(config.max_open_log_files);
+	stderr_verbosity::set(config.stderr_verbosity);
+	flush_timeout::set(config.flush_timeout);
+	use_controlling_tty::set(config.use_controlling_tty);
+	on_post_shutdown::set(config.on_post_shutdown);
+	progress_throttle::set(config.progress_throttle);
+	session_banner::set(config.session_banner.as_deref());
+	global_retry_budget::set(config.global_retry_budget);
+	flush_every::set(config.flush_every);
+	transcript_stderr_prefix::set(config.transcript_stderr_prefix.as_str());
+	drop(update_guard);
+	return Ok(());
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		max_queue_length::set(20);
+		max_retries::set(3);
+		allow_logging_print_failures::set(false);
+		on_queue_full::set(On_QueueFull::KeepNewest);
+		on_max_retries_reached::set(On_MaxRetriesReached::WriteToDisk);
+		on_queue_printing_fail::set(On_QueuePrintingFail::Return);
+		log_io_path::set("test.txt").unwrap();
+		indent::set(2);
+		wrap_width::set(Some(80));
+		track_stderr_printed::set(true);
+		combined_stream::set(Some(OutputKind::Stderr));
+		flush_strategy::set(FlushStrategy::Auto);
+		batch_preamble::set("::group::Output\n");
+		batch_postamble::set("::endgroup::\n");
+		persist_queue_path::set("queue.txt");
+		emit_order::set(EmitOrder::NewestFirst);
+		annotate_stream::set(true);
+		always_async::set(true);
+		log_max_age::set(Some(std::time::Duration::from_secs(3600)));
+		always_log_to_disk::set(Some(OutputKind::Stderr));
+		collapse_trailing_newline::set(true);
+		queue_warn_threshold::set(Some(512));
+		show_location::set(true);
+		serialize_startup::set(true);
+		buffer_policy::set(BufferPolicy { stdout: Some(FlushStrategy::OnDrainEnd), stderr: None });
+		printer_idle_timeout::set(std::time::Duration::from_millis(250));
+		on_nul_byte::set(On_NulByte::Replace('?'));
+		dry_run::set(true);
+		enabled::set(false);
+		#[cfg(feature = "gzip")]
+		log_compression::set(LogCompression::Gzip);
+		batch_separator::set("--\n");
+		max_diagnostics_in_queue::set(8);
+		log_file_bom::set(true);
+		on_spawn_failure::set(On_SpawnFailure::RetryLater);
+		heartbeat_interval::set(Some(std::time::Duration::from_millis(250)));
+		max_open_log_files::set(4);
+		stderr_verbosity::set(1);
+		flush_timeout::set(Some(std::time::Duration::from_millis(500)));
+		use_controlling_tty::set(true);
+		on_post_shutdown::set(On_PostShutdown::DirectWrite);
+		progress_throttle::set(std::time::Duration::from_millis(16));
+		session_banner::set(Some("=== {pid} ==="));
+		global_retry_budget::set(Some(8));
+		flush_every::set(4);
+		transcript_stderr_prefix::set("E| ");
+	}
+
+	let expected = JsonConfig::current();
+	let json = to_json();
+
+	{
+		test_utils::reset_globals();
+		log_io_path::set("test_05.txt").unwrap();
+		assert_ne!(JsonConfig::current(), expected);
+	}
+
+	{
+		from_json(json.as_str()).unwrap();
+		assert_eq!(JsonConfig::current(), expected);
+		assert_eq!(to_json(), json);
+	}
+
+	{
+		assert!(matches!(from_json("{\"max_retries\": 5}"), Err(JsonError::ParseError(_))));
+		assert_eq!(JsonConfig::current(), expected);
+	}
+}
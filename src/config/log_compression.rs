@@ -0,0 +1,96 @@
+//! Determines how the files at [LOG_IO_PATH](crate::config::log_io_path) and [LOG_TARGETS](crate::config::log_targets) are compressed.
+//! 0. **None**: Messages are appended as plain text.
+//! 1. **Gzip**: Messages are appended to the file at the same path with `.gz` added (e.g. `errors.log.gz`), as a gzip stream.
+//!
+//! - Each batch of messages written at once is a separate gzip member,
+//!   concatenated members are still a valid gzip stream (e.g. decompressed by `gzip -d`).
+//! - [On_QueueFull::Spill](crate::config::on_queue_full::On_QueueFull::Spill) still spills to the uncompressed path, so it can be replayed.
+//! - Requires feature **gzip**.
+//!
+//! # Default: [None](LogCompression::None)
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Current value of [LOG_COMPRESSION](self).
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [LOG_COMPRESSION](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_LOG_COMPRESSION";
+
+/// See [LOG_COMPRESSION](self).
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogCompression {
+	/// Append plain text.
+	None = 0,
+	/// Append gzip members to the path with `.gz` added.
+	Gzip = 1,
+}
+
+impl FromStr for LogCompression {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "None" => Ok(LogCompression::None),
+			"1" | "Gzip" => Ok(LogCompression::Gzip),
+			_ => Err(format!("Invalid string value for LogCompression: {}", s)),
+		}
+	}
+}
+
+/// Get global config [LOG_COMPRESSION](self).
+pub fn get() -> LogCompression {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => LogCompression::Gzip,
+		_ => LogCompression::None, // 0
+	};
+}
+
+/// Set global config [LOG_COMPRESSION](self).
+pub fn set(new_value: LogCompression) {
+	CURRENT.store(new_value as u8, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use std::io::Read;
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "Gzip");
+		super::env_vars::load_all();
+		assert_eq!(get(), LogCompression::Gzip);
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), LogCompression::None);
+	}
+
+	{
+		let path = test_utils::temp_path("comfy_print_test_log_compression.txt");
+		let gz_path = format!("{path}.gz");
+		let _ = std::fs::remove_file(&gz_path);
+		super::log_io_path::set(&path).unwrap();
+		super::always_log_to_disk::set(Some(OutputKind::Stdout));
+		set(LogCompression::Gzip);
+
+		crate::comfy_println!("Test_01");
+		crate::comfy_println!("Test_02");
+		test_utils::yield_until_idle();
+
+		assert_eq!(std::path::Path::new(&path).exists(), false);
+
+		let mut decompressed = String::new();
+		flate2::read::MultiGzDecoder::new(std::fs::File::open(&gz_path).unwrap())
+			.read_to_string(&mut decompressed)
+			.unwrap();
+		assert_eq!(decompressed, "Test_01\nTest_02\n");
+
+		std::fs::remove_file(&gz_path).unwrap();
+	}
+}
@@ -0,0 +1,72 @@
+//! Determines whether the UTF-8 byte order mark (`EF BB BF`) is written at the start of log files, which some Windows tools expect.
+//! - Only written when the file is created, or truncated by [LOG_MAX_AGE](crate::config::log_max_age), never when appending to an existing file.
+//! - Applies to [LOG_IO_PATH](crate::config::log_io_path) and [LOG_TARGETS](crate::config::log_targets).
+//! - With [LOG_COMPRESSION](crate::config::log_compression), it's written to the compressed text, not to the `.gz` file itself.
+//! 
+//! # Default: **false**
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [LOG_FILE_BOM](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [LOG_FILE_BOM](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_LOG_FILE_BOM";
+
+/// The UTF-8 byte order mark.
+pub const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Get global config [LOG_FILE_BOM](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [LOG_FILE_BOM](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+/// Writes [BOM] to `writer` if [LOG_FILE_BOM](self) is enabled and the file `is_new`.
+pub(crate) fn write_if_new(writer: &mut impl Write, is_new: bool) -> Result<(), std::io::Error> {
+	if is_new && get() {
+		writer.write_all(BOM)?;
+	}
+	
+	return Ok(());
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+	
+	let path = test_utils::temp_path("comfy_print_test_log_file_bom.txt");
+	let path = path.as_str();
+	let _ = std::fs::remove_file(path);
+	
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+	}
+	
+	{
+		super::log_io_path::set(path).unwrap();
+		super::always_log_to_disk::set(Some(OutputKind::Stdout));
+		
+		crate::comfy_println!("Test_01");
+		crate::comfy_println!("Test_02");
+		test_utils::yield_until_idle();
+		
+		let mut expected = BOM.to_vec();
+		expected.extend_from_slice(b"Test_01\nTest_02\n");
+		assert_eq!(std::fs::read(path).unwrap(), expected);
+	}
+	
+	{
+		std::fs::write(path, "Test_03\n").unwrap();
+		crate::comfy_println!("Test_04");
+		test_utils::yield_until_idle();
+		assert_eq!(std::fs::read_to_string(path).unwrap(), "Test_03\nTest_04\n");
+	}
+	
+	std::fs::remove_file(path).unwrap();
+}
@@ -22,28 +22,15 @@ pub fn get(append_in_me: &mut String) {
 
 /// Set global config [DISK_LOG_PATH](self).
 ///
-/// Path must include file name and extension.
+/// Path must include file name and extension, an empty path unsets it.
 /// 
 /// # Returns
 /// 
 /// * `Ok(())` if the path was successfully set.
 /// * `Err(std::io::Error)` if the path was invalid or if the directory couldn't be created.
 pub fn set(new_value: &str) -> Result<(), std::io::Error> {
-	let full_path = std::path::Path::new(new_value);
-	let Some(dir) = full_path.parent()
-			else {
-				return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!(
-					"Invalid path for DISK_LOG_PATH: {new_value}")));
-			};
-
-	match dir.try_exists() {
-		Ok(true) => {},
-		Ok(false) => {
-			if let Err(err) = std::fs::create_dir_all(dir) {
-				return Err(err);
-			}
-		},
-		Err(err) => return Err(err),
+	if new_value.is_empty() == false {
+		create_parent_dir(new_value)?;
 	}
 
 	let mut guard = CURRENT.lock();
@@ -53,24 +40,71 @@ pub fn set(new_value: &str) -> Result<(), std::io::Error> {
 	return Ok(());
 }
 
+/// Creates the directory `path` is in, if it doesn't exist.
+pub(crate) fn create_parent_dir(path: &str) -> Result<(), std::io::Error> {
+	let full_path = std::path::Path::new(path);
+	let Some(dir) = full_path.parent()
+			else {
+				return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!(
+					"Invalid path for a log file: {path}")));
+			};
+
+	return match dir.try_exists() {
+		Ok(true) => Ok(()),
+		Ok(false) => std::fs::create_dir_all(dir),
+		Err(err) => Err(err),
+	};
+}
+
+/// Opens the file for appending, truncating it first if it's older than [LOG_MAX_AGE](crate::config::log_max_age).
+/// 
+/// Writes the [LOG_FILE_BOM](crate::config::log_file_bom) if the file is new.
 pub(crate) fn get_file() -> Result<std::fs::File, std::io::Error> {
 	let guard = CURRENT.lock();
-	let path = std::path::Path::new(guard.as_str());
+	let opened = open_file(std::path::Path::new(guard.as_str()));
+	drop(guard);
+	
+	let (mut file, is_new) = opened?;
+	crate::config::log_file_bom::write_if_new(&mut file, is_new)?;
+	return Ok(file);
+}
 
-	return std::fs::OpenOptions::new()
+/// Opens the file at `path` for appending, truncating it first if it's older than [LOG_MAX_AGE](crate::config::log_max_age).
+/// 
+/// Also returns whether the file is new: it didn't exist, was empty or was truncated.
+pub(crate) fn open_file(path: &std::path::Path) -> Result<(std::fs::File, bool), std::io::Error> {
+	let metadata = std::fs::metadata(path).ok();
+	let is_expired = metadata.as_ref()
+		.and_then(|metadata| metadata.modified().ok())
+		.is_some_and(crate::config::log_max_age::is_expired);
+	
+	if is_expired {
+		std::fs::File::create(path)?;
+	}
+	
+	let is_new = is_expired || metadata.is_none_or(|metadata| metadata.len() == 0);
+	let file = std::fs::OpenOptions::new()
 			.append(true)
 			.create(true)
-			.open(path);
+			.open(path)?;
+	
+	return Ok((file, is_new));
 }
 
 #[test]
 fn test() {
+	let _guard = crate::test_utils::lock_tests();
 	let mut path = String::new();
 	
 	{
 		set("test.txt").unwrap();
 		get(&mut path);
 		assert_eq!(path, "test.txt");
+
+		set("").unwrap();
+		path.clear();
+		get(&mut path);
+		assert_eq!(path, "");
 	}
 
 	{
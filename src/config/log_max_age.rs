@@ -0,0 +1,95 @@
+//! Maximum age of the file at [LOG_IO_PATH](crate::config::log_io_path), based on when it was last modified.
+//! - Before writing to the file, it's truncated if it was last modified longer ago than this.
+//! - Stored in whole seconds, durations shorter than a second disable truncation.
+//! - The environment variable is read in seconds.
+//! 
+//! # Default: None (never truncated)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Current value of [LOG_MAX_AGE](self) in seconds, 0 means None.
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+
+/// Environment variable name for global config [LOG_MAX_AGE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_LOG_MAX_AGE";
+
+/// Get global config [LOG_MAX_AGE](self).
+pub fn get() -> Option<Duration> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		0 => None,
+		seconds => Some(Duration::from_secs(seconds)),
+	};
+}
+
+/// Set global config [LOG_MAX_AGE](self).
+pub fn set(new_value: Option<Duration>) {
+	let seconds = new_value.map(|duration| duration.as_secs()).unwrap_or(0);
+	CURRENT.store(seconds, Ordering::Relaxed);
+}
+
+/// Whether a file last modified at `modified` is older than [LOG_MAX_AGE](self).
+pub(crate) fn is_expired(modified: std::time::SystemTime) -> bool {
+	let Some(max_age) = get()
+			else { return false; };
+	
+	return match modified.elapsed() {
+		Ok(age) => age > max_age,
+		Err(_) => false, // modified in the future
+	};
+}
+
+#[test]
+fn test() {
+	use std::io::Write;
+	use std::time::SystemTime;
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "3600");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(Duration::from_secs(3600)));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), None);
+	}
+
+	let path = test_utils::temp_path("comfy_print_test_log_max_age.txt");
+	let path = path.as_str();
+	super::log_io_path::set(path).unwrap();
+	std::fs::write(path, "Old_01\n").unwrap();
+	let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+	std::fs::File::options().write(true).open(path).unwrap().set_modified(an_hour_ago).unwrap();
+
+	{
+		set(None);
+		let mut file = super::log_io_path::get_file().unwrap();
+		writeln!(file, "New_01").unwrap();
+		drop(file);
+		assert_eq!(std::fs::read_to_string(path).unwrap(), "Old_01\nNew_01\n");
+	}
+
+	std::fs::File::options().write(true).open(path).unwrap().set_modified(an_hour_ago).unwrap();
+
+	{
+		set(Some(Duration::from_secs(7200)));
+		let mut file = super::log_io_path::get_file().unwrap();
+		writeln!(file, "New_02").unwrap();
+		drop(file);
+		assert_eq!(std::fs::read_to_string(path).unwrap(), "Old_01\nNew_01\nNew_02\n");
+	}
+
+	std::fs::File::options().write(true).open(path).unwrap().set_modified(an_hour_ago).unwrap();
+
+	{
+		set(Some(Duration::from_secs(60)));
+		let mut file = super::log_io_path::get_file().unwrap();
+		writeln!(file, "New_03").unwrap();
+		drop(file);
+		assert_eq!(std::fs::read_to_string(path).unwrap(), "New_03\n");
+	}
+
+	std::fs::remove_file(path).unwrap();
+}
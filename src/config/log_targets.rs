@@ -0,0 +1,248 @@
+//! Named files that messages written to disk are appended to, each receiving only messages of its [OutputKind], or all of them.
+//! E.g. errors in `errors.log` and everything in `all.log`.
+//! - Applies to [WriteToDisk](crate::config::on_max_retries_reached::On_MaxRetriesReached::WriteToDisk)
+//!   and [ALWAYS_LOG_TO_DISK](crate::config::always_log_to_disk).
+//! - [LOG_IO_PATH](crate::config::log_io_path), if set, is the default target: it receives every message.
+//! - A message is considered written if at least one matching target accepted it.
+//!   Messages that match no target are considered written.
+//! - Not loaded from the environment.
+//!
+//! # Default: Empty (only [LOG_IO_PATH](crate::config::log_io_path))
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use parking_lot::Mutex;
+use crate::message::{Message, OutputKind};
+
+/// Current value of [LOG_TARGETS](self).
+static CURRENT: Mutex<BTreeMap<String, LogTarget>> = Mutex::new(BTreeMap::new());
+
+/// A file in [LOG_TARGETS](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogTarget {
+	/// Path to the file, must include file name and extension.
+	pub path: String,
+	/// Which messages are appended to the file, [None] means all of them.
+	pub filter: Option<OutputKind>,
+}
+
+/// An opened target, see [LogFiles].
+pub(crate) enum LogWriter {
+	Plain(std::fs::File),
+	/// Finishes the gzip member when dropped.
+	#[cfg(feature = "gzip")]
+	Gzip(flate2::write::GzEncoder<std::fs::File>),
+}
+
+impl LogWriter {
+	/// Opens the file at `path`, or at `path` with `.gz` added if [LOG_COMPRESSION](crate::config::log_compression) is enabled.
+	/// 
+	/// Writes the [LOG_FILE_BOM](crate::config::log_file_bom) if the file is new.
+	fn open(path: &str) -> Result<Self, std::io::Error> {
+		#[cfg(feature = "gzip")]
+		if super::log_compression::get() == super::log_compression::LogCompression::Gzip {
+			let (file, is_new) = super::log_io_path::open_file(std::path::Path::new(format!("{path}.gz").as_str()))?;
+			let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+			super::log_file_bom::write_if_new(&mut encoder, is_new)?;
+			return Ok(LogWriter::Gzip(encoder));
+		}
+
+		let (mut file, is_new) = super::log_io_path::open_file(std::path::Path::new(path))?;
+		super::log_file_bom::write_if_new(&mut file, is_new)?;
+		return Ok(LogWriter::Plain(file));
+	}
+}
+
+impl Write for LogWriter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		return match self {
+			LogWriter::Plain(file) => file.write(buf),
+			#[cfg(feature = "gzip")]
+			LogWriter::Gzip(encoder) => encoder.write(buf),
+		};
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		return match self {
+			LogWriter::Plain(file) => file.flush(),
+			#[cfg(feature = "gzip")]
+			LogWriter::Gzip(encoder) => encoder.flush(),
+		};
+	}
+}
+
+/// Add a target named `name` to global config [LOG_TARGETS](self), replacing the target with the same name, if any.
+///
+/// # Returns
+///
+/// * `Ok(())` if the target was added.
+/// * `Err(std::io::Error)` if the path was invalid or if the directory couldn't be created.
+pub fn add(name: &str, target: LogTarget) -> Result<(), std::io::Error> {
+	super::log_io_path::create_parent_dir(target.path.as_str())?;
+	CURRENT.lock().insert(name.to_string(), target);
+	return Ok(());
+}
+
+/// Get the target named `name` from global config [LOG_TARGETS](self).
+pub fn get(name: &str) -> Option<LogTarget> {
+	return CURRENT.lock().get(name).cloned();
+}
+
+/// Remove the target named `name` from global config [LOG_TARGETS](self).
+pub fn remove(name: &str) -> Option<LogTarget> {
+	return CURRENT.lock().remove(name);
+}
+
+/// Remove every target from global config [LOG_TARGETS](self).
+pub fn clear() {
+	CURRENT.lock().clear();
+}
+
+/// Every target of a batch of messages written to disk, see [all()].
+/// 
+/// Targets are opened lazily by [append()], keeping at most [MAX_OPEN_LOG_FILES](crate::config::max_open_log_files) of them open.
+/// They're closed when dropped.
+pub(crate) struct LogFiles {
+	targets: Vec<(Option<OutputKind>, String)>,
+	/// Opened targets, by index in [LogFiles::targets], least recently used first.
+	open: Vec<(usize, LogWriter)>,
+	/// How many times a target was opened.
+	#[cfg(test)]
+	pub(crate) opened_total: usize,
+}
+
+impl LogFiles {
+	/// The writer of the target at `index`, opening it if needed, then closing the least recently used one if above the limit.
+	fn writer(&mut self, index: usize) -> Result<&mut LogWriter, std::io::Error> {
+		if let Some(position) = self.open.iter().position(|(open_index, _)| *open_index == index) {
+			let entry = self.open.remove(position);
+			self.open.push(entry);
+		} else {
+			let writer = LogWriter::open(self.targets[index].1.as_str())?;
+			#[cfg(test)]
+			{ self.opened_total += 1; }
+			
+			let limit = super::max_open_log_files::get().max(1);
+			while self.open.len() >= limit {
+				self.open.remove(0);
+			}
+			
+			self.open.push((index, writer));
+		}
+		
+		let (_, writer) = self.open.last_mut().expect("just pushed");
+		return Ok(writer);
+	}
+	
+	#[cfg(test)]
+	pub(crate) fn open_count(&self) -> usize {
+		return self.open.len();
+	}
+}
+
+/// Every target, including [LOG_IO_PATH](crate::config::log_io_path), to be opened when messages are [appended](append()).
+///
+/// # Returns
+///
+/// * `Err(std::io::Error)` if there's no target.
+///
+/// WARNING: Will lock [LOG_IO_PATH](crate::config::log_io_path), then [LOG_TARGETS](self).
+pub(crate) fn all() -> Result<LogFiles, std::io::Error> {
+	let mut default_path = String::new();
+	super::log_io_path::get(&mut default_path);
+
+	let mut targets = Vec::new();
+	if default_path.is_empty() == false {
+		targets.push((None, default_path));
+	}
+
+	let guard = CURRENT.lock();
+	targets.extend(guard.values().map(|target| (target.filter, target.path.clone())));
+	drop(guard);
+
+	if targets.is_empty() {
+		return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "No log target is set."));
+	}
+
+	return Ok(LogFiles {
+		targets,
+		open: Vec::new(),
+		#[cfg(test)]
+		opened_total: 0,
+	});
+}
+
+/// Appends `msg` to each of `files` whose filter matches it, opening them if needed, see [all()].
+/// 
+/// - A target that fails to open counts as failing to write.
+/// - Lines starting with [SPILL_MARKER](crate::async_impl::SPILL_MARKER) are escaped, so that they're never replayed as spilled messages.
+pub(crate) fn append(files: &mut LogFiles, msg: &Message) -> Result<(), std::io::Error> {
+	let rendered = msg.rendered();
+	let rendered = crate::async_impl::escape_spill_marker(&rendered);
+	let mut last_err = None;
+	let mut any_written = false;
+
+	for index in 0..files.targets.len() {
+		if files.targets[index].0.is_some_and(|kind| kind != msg.output_kind()) {
+			continue;
+		}
+		
+		match files.writer(index).and_then(|file| file.write_all(rendered.as_bytes())) {
+			Ok(()) => any_written = true,
+			Err(err) => last_err = Some(err),
+		}
+	}
+
+	return match last_err {
+		Some(err) if any_written == false => Err(err),
+		_ => Ok(()),
+	};
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	let errors_path = test_utils::temp_path("comfy_print_test_log_targets_errors.txt");
+	let all_path = test_utils::temp_path("comfy_print_test_log_targets_all.txt");
+	let (errors_path, all_path) = (errors_path.as_str(), all_path.as_str());
+	let _ = std::fs::remove_file(errors_path);
+	let _ = std::fs::remove_file(all_path);
+
+	{
+		add("errors", LogTarget { path: errors_path.to_string(), filter: Some(OutputKind::Stderr) }).unwrap();
+		add("all", LogTarget { path: all_path.to_string(), filter: None }).unwrap();
+		assert_eq!(get("errors"), Some(LogTarget { path: errors_path.to_string(), filter: Some(OutputKind::Stderr) }));
+
+		super::always_log_to_disk::set(Some(OutputKind::Stderr));
+		crate::comfy_eprintln!("Test_01");
+		crate::comfy_println!("Test_02");
+		test_utils::yield_until_idle();
+
+		assert_eq!(std::fs::read_to_string(errors_path).unwrap(), "Test_01\n");
+		assert_eq!(std::fs::read_to_string(all_path).unwrap(), "Test_01\n");
+	}
+
+	{
+		let mut files = all().unwrap();
+		assert_eq!(files.targets.len(), 2);
+
+		append(&mut files, &Message::standard_ln("Test_03")).unwrap();
+		append(&mut files, &Message::error_ln("Test_04")).unwrap();
+		drop(files);
+
+		assert_eq!(std::fs::read_to_string(errors_path).unwrap(), "Test_01\nTest_04\n");
+		assert_eq!(std::fs::read_to_string(all_path).unwrap(), "Test_01\nTest_03\nTest_04\n");
+	}
+
+	{
+		assert_eq!(remove("all").map(|target| target.path), Some(all_path.to_string()));
+		clear();
+		assert_eq!(get("errors"), None);
+		assert!(all().is_err());
+	}
+
+	std::fs::remove_file(errors_path).unwrap();
+	std::fs::remove_file(all_path).unwrap();
+}
@@ -0,0 +1,36 @@
+//! Maximum number of diagnostics that can be stored in the queue at the same time, see [ALLOW_LOGGING_PRINT_FAILURES](crate::config::allow_logging_print_failures).
+//! - Stops a persistent failure from crowding out real messages with diagnostics describing it.
+//! - Further diagnostics are suppressed, see [suppressed_diagnostics_count()](crate::async_impl::suppressed_diagnostics_count()).
+//! - Diagnostics also count towards [MAX_QUEUE_LENGTH](crate::config::max_queue_length).
+//! 
+//! # Default: [usize::MAX] (no limit)
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current value of [MAX_DIAGNOSTICS_IN_QUEUE](self).
+static CURRENT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Environment variable name for global config [MAX_DIAGNOSTICS_IN_QUEUE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_MAX_DIAGNOSTICS_IN_QUEUE";
+
+/// Get global config [MAX_DIAGNOSTICS_IN_QUEUE](self).
+pub fn get() -> usize { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [MAX_DIAGNOSTICS_IN_QUEUE](self).
+pub fn set(new_value: usize) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "4");
+		super::env_vars::load_all();
+		assert_eq!(get(), 4);
+		
+		std::env::set_var(ENV_NAME, "-4");
+		super::env_vars::load_all();
+		assert_eq!(get(), 4);
+	}
+}
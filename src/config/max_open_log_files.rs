@@ -0,0 +1,72 @@
+//! Maximum number of [LOG_TARGETS](crate::config::log_targets) files (including [LOG_IO_PATH](crate::config::log_io_path)) kept open at the same time while writing messages to disk.
+//! - Targets are only opened once a message matches them.
+//! - Beyond the limit, the least recently used file is closed, then reopened if needed again.
+//!   With [LOG_COMPRESSION](crate::config::log_compression), each reopening starts a new gzip member.
+//! - Files are closed once the batch of messages being written is done, regardless of the limit.
+//! - 0 behaves like 1.
+//! 
+//! # Default: **16**
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current value of [MAX_OPEN_LOG_FILES](self).
+static CURRENT: AtomicUsize = AtomicUsize::new(16);
+
+/// Environment variable name for global config [MAX_OPEN_LOG_FILES](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_MAX_OPEN_LOG_FILES";
+
+/// Get global config [MAX_OPEN_LOG_FILES](self).
+pub fn get() -> usize { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [MAX_OPEN_LOG_FILES](self).
+pub fn set(new_value: usize) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::Message;
+	use super::log_targets::{self, LogTarget};
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "4");
+		super::env_vars::load_all();
+		assert_eq!(get(), 4);
+	}
+
+	let paths = ["test_max_open_log_files_0.txt", "test_max_open_log_files_1.txt", "test_max_open_log_files_2.txt"];
+	for (index, path) in paths.iter().enumerate() {
+		let _ = std::fs::remove_file(path);
+		log_targets::add(&format!("target_{index}"), LogTarget { path: path.to_string(), filter: None }).unwrap();
+	}
+
+	{
+		set(2);
+		let mut files = log_targets::all().unwrap();
+
+		for msg in [Message::standard_ln("Test_01"), Message::error_ln("Test_02")] {
+			log_targets::append(&mut files, &msg).unwrap();
+			assert_eq!(files.open_count(), 2);
+		}
+
+		// Every message reopens the targets closed to make room for the others.
+		assert_eq!(files.opened_total, 6);
+		drop(files);
+
+		for path in paths {
+			assert_eq!(std::fs::read_to_string(path).unwrap(), "Test_01\nTest_02\n");
+		}
+	}
+
+	{
+		set(3);
+		let mut files = log_targets::all().unwrap();
+		log_targets::append(&mut files, &Message::standard_ln("Test_03")).unwrap();
+		log_targets::append(&mut files, &Message::standard_ln("Test_04")).unwrap();
+		assert_eq!(files.opened_total, 3);
+	}
+
+	for path in paths {
+		std::fs::remove_file(path).unwrap();
+	}
+}
@@ -2,6 +2,7 @@
 //! - When printing fails, messages will be stored in a shared queue.
 //! - [comfy_print](crate) will attempt to print the queue later. See [config::on_retry_printing_fail].
 //! - If the queue is full, [ON_QUEUE_FULL](config::on_queue_full) will decide what happens to future messages being pushed in the queue..
+//! - Can be temporarily raised with [reserve_extra()], e.g. before a known burst of output.
 //! 
 //! # Default: **1024**
 
@@ -10,6 +11,9 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 /// Current value of [MAX_QUEUE_LENGTH](self).
 static CURRENT: AtomicUsize = AtomicUsize::new(1024);
 
+/// Sum of the capacity reserved by every live [ExtraCapacityGuard].
+static RESERVED_EXTRA: AtomicUsize = AtomicUsize::new(0);
+
 /// Environment variable name for global config [MAX_QUEUE_LENGTH](self).
 pub const ENV_NAME: &str = "COMFY_PRINT_MAX_QUEUE_LENGTH";
 
@@ -19,11 +23,39 @@ pub fn get() -> usize { return CURRENT.load(Ordering::Relaxed); }
 /// Set global config [MAX_QUEUE_LENGTH](self).
 pub fn set(new_value: usize) { CURRENT.store(new_value, Ordering::Relaxed); }
 
+/// The limit actually enforced on the queue: [MAX_QUEUE_LENGTH](self) plus any capacity reserved with [reserve_extra()].
+pub fn effective() -> usize { return get().saturating_add(RESERVED_EXTRA.load(Ordering::Relaxed)); }
+
+/// Raises the [effective] queue limit by `extra` until the returned guard is dropped.
+/// 
+/// - The reservation is kept apart from [MAX_QUEUE_LENGTH](self): calling [set()] while reserved changes the base value, 
+///   and dropping the guard only removes `extra` from the limit.
+/// - Reservations stack, each guard only removes its own `extra`.
+/// - Messages already in the queue are kept when the guard is dropped, even if they exceed the limit.
+pub fn reserve_extra(extra: usize) -> ExtraCapacityGuard {
+	RESERVED_EXTRA.fetch_add(extra, Ordering::Relaxed);
+	return ExtraCapacityGuard { extra };
+}
+
+/// Returned by [reserve_extra()], releases the reserved capacity on drop.
+#[must_use = "the extra capacity is released as soon as the guard is dropped"]
+pub struct ExtraCapacityGuard {
+	extra: usize,
+}
+
+impl Drop for ExtraCapacityGuard {
+	fn drop(&mut self) {
+		RESERVED_EXTRA.fetch_sub(self.extra, Ordering::Relaxed);
+	}
+}
+
 #[test]
+#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
 fn test() {
 	use crate::test_utils;
 	use crate::message::OutputKind;
 	use crate::config;
+	let _guard = test_utils::lock_tests();
 
 	{
 		std::env::set_var(ENV_NAME, "20");
@@ -63,6 +95,7 @@ fn test() {
 		assert_eq!(test_utils::get_queue().len(), 0);
 	}
 
+	#[cfg(not(feature = "no-diagnostics"))]
 	{
 		config::allow_logging_print_failures::set(true);
 
@@ -70,7 +103,7 @@ fn test() {
 		assert_eq!(get(), 10);
 
 		test_utils::set_toggle_write_fail(true);
-		for index in 1..=8 {
+		for index in 1..=12 {
 			crate::comfy_println!("Test_{:02}", index);
 		}
 		
@@ -78,8 +111,9 @@ fn test() {
 
 		let queue = test_utils::get_queue();
 		assert_eq!(queue.len(), 10);
+		// Failing to print the diagnostic at the front of the queue does not generate further diagnostics.
 		let err_count = queue.iter().filter(|msg| matches!(msg.output_kind(), OutputKind::Stderr)).count();
-		assert_eq!(err_count, 2);
+		assert_eq!(err_count, 1);
 		drop(queue);
 		
 		test_utils::set_toggle_write_fail(false);
@@ -111,4 +145,32 @@ fn test() {
 		test_utils::yield_until_idle();
 		assert_eq!(test_utils::get_queue().len(), 0);
 	}
+
+	{
+		set(3);
+		test_utils::set_toggle_write_fail(true);
+		let reserved = reserve_extra(5);
+		assert_eq!(effective(), 8);
+		
+		for index in 1..=8 {
+			crate::comfy_println!("Test_{:02}", index);
+		}
+		
+		assert_eq!(test_utils::get_queue().len(), 8);
+		
+		// Changing the base while reserved is not undone by the guard
+		set(4);
+		assert_eq!(effective(), 9);
+		drop(reserved);
+		assert_eq!(get(), 4);
+		assert_eq!(effective(), 4);
+		
+		crate::comfy_println!("Test_09");
+		assert_eq!(test_utils::get_queue().len(), 8);
+		
+		test_utils::set_toggle_write_fail(false);
+		crate::comfy_println!("Test_10");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
 }
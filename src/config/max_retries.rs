@@ -20,9 +20,11 @@ pub fn get() -> usize { return CURRENT.load(Ordering::Relaxed); }
 pub fn set(new_value: usize) { CURRENT.store(new_value, Ordering::Relaxed); }
 
 #[test]
+#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
 fn test() {
 	use crate::test_utils;
 	use crate::config;
+	let _guard = test_utils::lock_tests();
 	
 	// Just so the error messages don't interfere with the test.
 	config::allow_logging_print_failures::set(false);
@@ -0,0 +1,83 @@
+//! Ordered chain of transforms applied to every message printed through [comfy_print_async()](crate::async_impl::comfy_print_async()), before it's written or queued.
+//! - Each middleware receives the output of the previous one, in the order they were added.
+//! - Useful for composing transforms, e.g. redacting secrets then adding a prefix.
+//! - If a middleware panics, the message is replaced by a diagnostic describing the panic (or an empty message if diagnostics are disabled), 
+//!   see [try_format()](crate::message::try_format()).
+//! 
+//! # Default: Empty
+
+use parking_lot::RwLock;
+use crate::message::Message;
+
+/// A transform in the [MIDDLEWARE](self) chain.
+pub type Middleware = Box<dyn Fn(Message) -> Message + Send + Sync>;
+
+/// Current value of [MIDDLEWARE](self).
+static CHAIN: RwLock<Vec<Middleware>> = RwLock::new(Vec::new());
+
+/// Append `middleware` to the end of global config [MIDDLEWARE](self).
+pub fn add(middleware: Middleware) {
+	CHAIN.write().push(middleware);
+}
+
+/// Remove every middleware from global config [MIDDLEWARE](self).
+pub fn clear() {
+	CHAIN.write().clear();
+}
+
+/// Runs `msg` through each middleware, in order.
+/// 
+/// WARNING: Will lock [MIDDLEWARE](self) for reading, recursively so that a middleware may print.
+pub(crate) fn apply(msg: Message) -> Message {
+	let guard = CHAIN.read_recursive();
+	if guard.is_empty() {
+		drop(guard);
+		return msg;
+	}
+	
+	let msg = crate::message::try_format(|| guard.iter().fold(msg, |msg, middleware| middleware(msg)));
+	drop(guard);
+	return msg;
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		add(Box::new(|msg| {
+			let redacted = msg.str().replace("hunter2", "*******");
+			return msg.with_str(redacted);
+		}));
+		
+		add(Box::new(|msg| {
+			let prefixed = format!("[app] {}", msg.str());
+			return msg.with_str(prefixed);
+		}));
+		
+		crate::comfy_println!("password: hunter2");
+		crate::comfy_eprint!("Test_01");
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "[app] password: *******\n".to_string()),
+			(OutputKind::Stderr, "[app] Test_01".to_string()),
+		]);
+	}
+
+	{
+		clear();
+		add(Box::new(|_| panic!("Middleware panicked")));
+		crate::comfy_println!("Test_02");
+		
+		let written = test_utils::take_written();
+		assert_eq!(written.len(), 1);
+		assert_eq!(written[0].0, OutputKind::Stderr);
+		
+		if cfg!(feature = "no-diagnostics") {
+			assert_eq!(written[0].1, "");
+		} else {
+			assert!(written[0].1.contains("Middleware panicked"));
+		}
+	}
+}
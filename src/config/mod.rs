@@ -3,17 +3,89 @@
 //! At runtime, [comfy_print](crate) allows configuring its behavior through global variables.
 //! These variables can be set manually by calling the `set` function of each variable's module. Example: [max_queue_length::set()].
 //! You may also read these variables from the environment by calling [env_vars::load_all()].
+//! To change several variables at once, without [comfy_print](crate) observing only some of the changes, use [apply()].
 //! 
 //! See each module's documentation for more information.
 
 #![allow(non_camel_case_types)]
 
 pub mod env_vars;
+pub mod update_lock;
 
 pub mod max_queue_length;
+pub mod max_diagnostics_in_queue;
 pub mod max_retries;
+pub mod global_retry_budget;
 pub mod allow_logging_print_failures;
 pub mod on_queue_full;
 pub mod on_max_retries_reached;
 pub mod on_queue_printing_fail;
-pub mod log_io_path;
\ No newline at end of file
+pub mod log_io_path;
+pub mod log_targets;
+pub mod max_open_log_files;
+#[cfg(feature = "gzip")]
+#[cfg_attr(docsrs, doc(cfg(feature = "gzip")))]
+pub mod log_compression;
+pub mod indent;
+pub mod wrap_width;
+pub mod track_stderr_printed;
+pub mod stderr_verbosity;
+pub mod combined_stream;
+pub mod use_controlling_tty;
+pub mod flush_strategy;
+pub mod flush_every;
+pub mod flush_timeout;
+pub mod buffer_policy;
+pub mod batch_preamble;
+pub mod batch_postamble;
+pub mod session_banner;
+pub mod batch_separator;
+pub mod persist_queue_path;
+pub mod emit_order;
+pub mod annotate_stream;
+pub mod transcript_stderr_prefix;
+pub mod always_async;
+pub mod log_max_age;
+pub mod log_file_bom;
+pub mod always_log_to_disk;
+pub mod collapse_trailing_newline;
+pub mod queue_warn_threshold;
+pub mod show_location;
+pub mod middleware;
+pub mod renderer;
+pub mod routes;
+pub mod category_caps;
+pub mod on_nul_byte;
+pub mod dry_run;
+pub mod enabled;
+pub mod serialize_startup;
+pub mod printer_idle_timeout;
+pub mod progress_throttle;
+pub mod on_spawn_failure;
+pub mod on_post_shutdown;
+pub mod heartbeat_interval;
+pub mod drop_channel;
+pub mod queue_warn_channel;
+pub mod on_queue_empty;
+pub mod on_heartbeat;
+pub mod on_state_change;
+pub mod drain_spawner;
+#[cfg(any(test, feature = "test-sync-drain"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-sync-drain")))]
+pub mod test_sync_drain;
+
+pub use update_lock::apply;
+pub use drop_channel::set as set_drop_channel;
+pub use middleware::add as add_middleware;
+pub use renderer::set as set_renderer;
+pub use routes::add_route;
+pub use category_caps::set as set_category_cap;
+pub use drain_spawner::set as set_drain_spawner;
+pub use enabled::{get as is_enabled, set as set_enabled};
+
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod json;
+
+#[cfg(feature = "json")]
+pub use json::{from_json, to_json};
@@ -0,0 +1,103 @@
+//! Callback invoked every [HEARTBEAT_INTERVAL](crate::config::heartbeat_interval) while printing the queue keeps failing.
+//! - Receives the length of the queue.
+//! - Invoked on the thread printing the queue, without holding the queue's lock, so it may print.
+//! - Never invoked if [HEARTBEAT_INTERVAL](crate::config::heartbeat_interval) is None.
+//!
+//! # Default: None
+
+use std::time::Instant;
+use parking_lot::{Mutex, RwLock};
+
+/// A callback for [ON_HEARTBEAT](self).
+pub type OnHeartbeat = Box<dyn Fn(usize) + Send + Sync>;
+
+/// Current value of [ON_HEARTBEAT](self).
+static CURRENT: RwLock<Option<OnHeartbeat>> = RwLock::new(None);
+
+/// When the last heartbeat was due, [None] while the queue isn't failing.
+static LAST_BEAT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Set global config [ON_HEARTBEAT](self), replacing the previous callback.
+pub fn set(callback: OnHeartbeat) {
+	*CURRENT.write() = Some(callback);
+}
+
+/// Remove the callback stored in global config [ON_HEARTBEAT](self).
+pub fn clear() {
+	*CURRENT.write() = None;
+}
+
+/// Called each time writing a message from the queue fails, invokes the callback if [HEARTBEAT_INTERVAL](crate::config::heartbeat_interval) passed since the last one.
+///
+/// WARNING: Will lock [LAST_BEAT], then may lock [ON_HEARTBEAT](self) for reading, recursively so that the callback may print.
+pub(crate) fn on_failure(queue_len: usize) {
+	let Some(interval) = super::heartbeat_interval::get()
+			else { return; };
+
+	let now = Instant::now();
+	let mut last_guard = LAST_BEAT.lock();
+	let is_due = match *last_guard {
+		Some(last) => now.duration_since(last) >= interval,
+		None => {
+			*last_guard = Some(now);
+			false
+		}
+	};
+
+	if is_due {
+		*last_guard = Some(now);
+	}
+
+	drop(last_guard);
+
+	if is_due {
+		let guard = CURRENT.read_recursive();
+		if let Some(callback) = guard.as_ref() {
+			callback(queue_len);
+		}
+
+		drop(guard);
+	}
+}
+
+/// Called once the queue is empty, so that the next failure starts a new interval.
+///
+/// WARNING: Will lock [LAST_BEAT].
+pub(crate) fn on_empty() {
+	*LAST_BEAT.lock() = None;
+}
+
+#[test]
+fn test() {
+	use std::sync::Arc;
+	use std::time::Duration;
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+	super::on_queue_printing_fail::set(super::on_queue_printing_fail::On_QueuePrintingFail::Return);
+
+	let lengths = Arc::new(Mutex::new(Vec::new()));
+	let lengths_clone = Arc::clone(&lengths);
+	set(Box::new(move |queue_len| lengths_clone.lock().push(queue_len)));
+	super::heartbeat_interval::set(Some(Duration::from_millis(1)));
+
+	{
+		test_utils::set_toggle_write_fail(true);
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+		assert!(lengths.lock().is_empty());
+
+		std::thread::sleep(Duration::from_millis(5));
+		crate::comfy_println!("Test_02");
+		test_utils::yield_until_idle();
+		assert_eq!(*lengths.lock(), vec![2]);
+	}
+
+	{
+		test_utils::set_toggle_write_fail(false);
+		crate::comfy_println!("Test_03");
+		test_utils::yield_until_idle();
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(LAST_BEAT.lock().is_none(), true);
+	}
+}
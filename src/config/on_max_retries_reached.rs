@@ -2,6 +2,9 @@
 //! 0. **Return**: do nothing.
 //! 1. **WriteToDisk**: Attempt to write stored messages to path specified by [LOG_IO_PATH](crate::config::log_io_path).
 //! 	- Any messages successfully written to disk will be removed from the queue.
+//! 2. **Abort**: Same as **WriteToDisk**, then if any message is left in the queue, 
+//!    [abort](std::process::abort) the process, for programs that would rather crash than lose output.
+//! 	- A last diagnostic is written straight to stderr before aborting, see [signal_safe_write()](crate::async_impl::signal_safe_write()).
 //! 
 //! # Default: [Return](On_MaxRetriesReached::Return)
 
@@ -17,11 +20,14 @@ pub const ENV_NAME: &str = "COMFY_PRINT_ON_MAX_RETRIES_REACHED";
 /// See [ON_MAX_RETRIES_REACHED](self).
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub enum On_MaxRetriesReached {
 	/// If [MAX_RETRIES](crate::config::max_retries) is reached, do nothing.
 	Return = 0,
 	/// If [MAX_RETRIES](crate::config::max_retries) is reached, attempt to write stored messages to disk.
 	WriteToDisk = 1,
+	/// If [MAX_RETRIES](crate::config::max_retries) is reached, attempt to write stored messages to disk, then abort if any is left.
+	Abort = 2,
 }
 
 impl FromStr for On_MaxRetriesReached {
@@ -31,6 +37,7 @@ impl FromStr for On_MaxRetriesReached {
 		match s {
 			"0" | "Return" => Ok(On_MaxRetriesReached::Return),
 			"1" | "WriteToDisk" => Ok(On_MaxRetriesReached::WriteToDisk),
+			"2" | "Abort" => Ok(On_MaxRetriesReached::Abort),
 			_ => Err(format!("Invalid string value for On_MaxRetriesReached: {}", s)),
 		}
 	}
@@ -40,6 +47,7 @@ impl FromStr for On_MaxRetriesReached {
 pub fn get() -> On_MaxRetriesReached {
 	return match CURRENT.load(Ordering::Relaxed) {
 		1 => On_MaxRetriesReached::WriteToDisk,
+		2 => On_MaxRetriesReached::Abort,
 		_ => On_MaxRetriesReached::Return, // 0
 	};
 }
@@ -53,6 +61,7 @@ pub fn set(new_value: On_MaxRetriesReached) {
 fn test() {
 	use crate::test_utils;
 	use crate::config;
+	let _guard = test_utils::lock_tests();
 	
 	// Just so the error messages don't interfere with the test.
 	config::allow_logging_print_failures::set(false);
@@ -85,6 +94,10 @@ fn test() {
 		std::env::set_var(ENV_NAME, "1");
 		super::env_vars::load_all();
 		assert_eq!(get(), On_MaxRetriesReached::WriteToDisk);
+		
+		std::env::set_var(ENV_NAME, "Abort");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_MaxRetriesReached::Abort);
 	}
 
 	{
@@ -108,9 +121,11 @@ fn test() {
 
 /// This tests requires Administrator privileges to run.
 #[test]
+#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
 fn test_file_write() {
 	use crate::test_utils;
 	use crate::config;
+	let _guard = test_utils::lock_tests();
 	
 	// Just so the error messages don't interfere with the test.
 	config::allow_logging_print_failures::set(false);
@@ -141,3 +156,37 @@ fn test_file_write() {
 		std::fs::remove_file(path).unwrap();
 	}
 }
+
+/// Runs itself in a subprocess, which must abort.
+#[test]
+fn test_abort() {
+	use crate::test_utils;
+	use crate::config;
+	const CHILD_ENV_NAME: &str = "COMFY_PRINT_TEST_ABORT_CHILD";
+	
+	if std::env::var_os(CHILD_ENV_NAME).is_some() {
+		let _guard = test_utils::lock_tests();
+		config::allow_logging_print_failures::set(false);
+		config::max_retries::set(0);
+		set(On_MaxRetriesReached::Abort);
+		
+		test_utils::set_toggle_write_fail(true);
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+		
+		// Unreachable if the process aborted.
+		std::process::exit(0);
+	}
+	
+	let output = std::process::Command::new(std::env::current_exe().unwrap())
+		.args(["--exact", "config::on_max_retries_reached::test_abort", "--test-threads=1"])
+		.env(CHILD_ENV_NAME, "1")
+		.output()
+		.unwrap();
+	
+	assert_eq!(output.status.success(), false);
+	assert!(String::from_utf8_lossy(&output.stderr).contains("aborting"));
+	
+	#[cfg(unix)]
+	assert_eq!(std::os::unix::process::ExitStatusExt::signal(&output.status), Some(libc::SIGABRT));
+}
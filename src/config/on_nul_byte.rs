@@ -0,0 +1,132 @@
+//! Determines what to do with NUL bytes (`\0`) inside messages written to [std::io::stdout] | [std::io::stderr], 
+//! which some terminals and log parsers choke on.
+//! 0. **Pass**: Write them as is.
+//! 1. **Strip**: Remove them.
+//! 2. **Replace(char)**: Replace each of them with the given character.
+//! 
+//! Applied after every other stream-only config, including [RENDERER](crate::config::renderer).
+//! From the environment, **Replace** is written as `Replace(c)`, e.g. `Replace(?)`.
+//! 
+//! # Default: [Pass](On_NulByte::Pass)
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Current value of [ON_NUL_BYTE](self). [PASS] or [STRIP], otherwise the replacement char.
+static CURRENT: AtomicU32 = AtomicU32::new(PASS);
+
+/// Above [char::MAX], so it can't be confused with a replacement.
+const PASS: u32 = u32::MAX;
+/// See [PASS].
+const STRIP: u32 = u32::MAX - 1;
+
+/// Environment variable name for global config [ON_NUL_BYTE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ON_NUL_BYTE";
+
+/// See [ON_NUL_BYTE](self).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum On_NulByte {
+	/// Write NUL bytes as is.
+	Pass,
+	/// Remove NUL bytes.
+	Strip,
+	/// Replace each NUL byte with the given character.
+	Replace(char),
+}
+
+impl FromStr for On_NulByte {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "Pass" => return Ok(On_NulByte::Pass),
+			"1" | "Strip" => return Ok(On_NulByte::Strip),
+			_ => {}
+		}
+
+		let replacement = s.strip_prefix("Replace(")
+			.and_then(|rest| rest.strip_suffix(')'))
+			.and_then(|inner| {
+				let mut chars = inner.chars();
+				let char = chars.next()?;
+				return chars.next().is_none().then_some(char);
+			});
+
+		return match replacement {
+			Some(char) => Ok(On_NulByte::Replace(char)),
+			None => Err(format!("Invalid string value for On_NulByte: {}", s)),
+		};
+	}
+}
+
+/// Get global config [ON_NUL_BYTE](self).
+pub fn get() -> On_NulByte {
+	return match CURRENT.load(Ordering::Relaxed) {
+		PASS => On_NulByte::Pass,
+		STRIP => On_NulByte::Strip,
+		code => char::from_u32(code).map_or(On_NulByte::Pass, On_NulByte::Replace),
+	};
+}
+
+/// Set global config [ON_NUL_BYTE](self).
+pub fn set(new_value: On_NulByte) {
+	let value = match new_value {
+		On_NulByte::Pass => PASS,
+		On_NulByte::Strip => STRIP,
+		On_NulByte::Replace(char) => char as u32,
+	};
+
+	CURRENT.store(value, Ordering::Relaxed);
+}
+
+/// Applies [ON_NUL_BYTE](self) to `text`.
+pub(crate) fn apply(text: String) -> String {
+	if text.contains('\0') == false {
+		return text;
+	}
+
+	return match get() {
+		On_NulByte::Pass => text,
+		On_NulByte::Strip => text.replace('\0', ""),
+		On_NulByte::Replace(char) => text.replace('\0', char.encode_utf8(&mut [0; 4])),
+	};
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "Replace(?)");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_NulByte::Replace('?'));
+
+		std::env::set_var(ENV_NAME, "Replace(??)");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_NulByte::Replace('?'));
+
+		std::env::set_var(ENV_NAME, "1");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_NulByte::Strip);
+	}
+
+	{
+		set(On_NulByte::Pass);
+		crate::comfy_println!("Test\0_01");
+
+		set(On_NulByte::Strip);
+		crate::comfy_println!("Test\0_02");
+
+		set(On_NulByte::Replace('\u{FFFD}'));
+		crate::comfy_println!("Test\0_03\0");
+
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test\0_01\n".to_string()),
+			(OutputKind::Stdout, "Test_02\n".to_string()),
+			(OutputKind::Stdout, "Test\u{FFFD}_03\u{FFFD}\n".to_string()),
+		]);
+	}
+}
@@ -0,0 +1,145 @@
+//! Determines what happens to messages printed after [shutdown()](crate::async_impl::shutdown) was called, e.g. by threads that outlive it.
+//! 0. **Restart**: Print them as usual, restarting the printing of the queue if needed.
+//! 1. **Drop**: Drop them, reported through [DROP_CHANNEL](crate::config::drop_channel).
+//! 2. **DirectWrite**: Write them on the caller's thread, bypassing the queue, so that last-gasp diagnostics still appear.
+//!    This is best-effort: a message that fails to be written is dropped, it's never queued nor retried.
+//! 3. **Panic**: Panic, e.g. to catch prints that are expected to never happen so late.
+//!
+//! - Applies from the moment [shutdown()](crate::async_impl::shutdown) is called, including while it waits for the queue.
+//!   Messages already in the queue are printed as usual.
+//! - [Captured](crate::async_impl::capture()) messages are captured regardless.
+//!
+//! # Default: [Restart](On_PostShutdown::Restart)
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Current value of [ON_POST_SHUTDOWN](self).
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [ON_POST_SHUTDOWN](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ON_POST_SHUTDOWN";
+
+/// See [ON_POST_SHUTDOWN](self).
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum On_PostShutdown {
+	/// Print as usual.
+	Restart = 0,
+	/// Drop the message.
+	Drop = 1,
+	/// Write the message on the caller's thread, without queueing it.
+	DirectWrite = 2,
+	/// Panic.
+	Panic = 3,
+}
+
+impl FromStr for On_PostShutdown {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "Restart" => Ok(On_PostShutdown::Restart),
+			"1" | "Drop" => Ok(On_PostShutdown::Drop),
+			"2" | "DirectWrite" => Ok(On_PostShutdown::DirectWrite),
+			"3" | "Panic" => Ok(On_PostShutdown::Panic),
+			_ => Err(format!("Invalid string value for On_PostShutdown: {}", s)),
+		}
+	}
+}
+
+/// Get global config [ON_POST_SHUTDOWN](self).
+pub fn get() -> On_PostShutdown {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => On_PostShutdown::Drop,
+		2 => On_PostShutdown::DirectWrite,
+		3 => On_PostShutdown::Panic,
+		_ => On_PostShutdown::Restart, // 0
+	};
+}
+
+/// Set global config [ON_POST_SHUTDOWN](self).
+pub fn set(new_value: On_PostShutdown) {
+	CURRENT.store(new_value as u8, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	use crate::async_impl;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+
+	{
+		std::env::set_var(ENV_NAME, "DirectWrite");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_PostShutdown::DirectWrite);
+
+		std::env::set_var(ENV_NAME, "1");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_PostShutdown::Drop);
+		std::env::remove_var(ENV_NAME);
+	}
+
+	assert!(async_impl::shutdown(std::time::Duration::from_secs(5)));
+
+	{
+		set(On_PostShutdown::Restart);
+		async_impl::pause();
+		crate::comfy_println!("Test_01");
+		assert_eq!(test_utils::get_queue().len(), 1);
+
+		async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+	}
+
+	{
+		set(On_PostShutdown::Drop);
+		crate::comfy_println!("Test_02");
+		assert!(test_utils::get_queue().is_empty());
+		assert!(test_utils::take_written().is_empty());
+		assert_eq!(async_impl::dropped_count(), 1);
+
+		// The bulk entry points follow the same policy.
+		async_impl::println_slice(&["Test_06"], OutputKind::Stdout);
+		assert_eq!(async_impl::enqueue_many(vec![crate::message::Message::standard("Test_07")]), (0, 1));
+		async_impl::thread_buffer_print("Test_08", OutputKind::Stdout);
+		async_impl::thread_flush();
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(async_impl::dropped_count(), 4);
+	}
+
+	{
+		set(On_PostShutdown::DirectWrite);
+		async_impl::pause();
+		crate::comfy_eprintln!("Test_03");
+
+		// Written despite the pause, since it bypasses the queue.
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, "Test_03\n".to_string())]);
+
+		test_utils::write_fail_once();
+		crate::comfy_eprintln!("Test_04");
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(async_impl::dropped_count(), 5);
+
+		async_impl::println_slice(&["Test_09"], OutputKind::Stderr);
+		assert_eq!(async_impl::enqueue_many(vec![crate::message::Message::standard("Test_10")]), (1, 0));
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stderr, "Test_09\n".to_string()),
+			(OutputKind::Stdout, "Test_10".to_string()),
+		]);
+		async_impl::resume();
+	}
+
+	{
+		set(On_PostShutdown::Panic);
+		assert!(std::panic::catch_unwind(|| crate::comfy_println!("Test_05")).is_err());
+		assert!(std::panic::catch_unwind(|| async_impl::println_slice(&["Test_11"], OutputKind::Stdout)).is_err());
+		assert!(test_utils::take_written().is_empty());
+	}
+}
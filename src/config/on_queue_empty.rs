@@ -0,0 +1,73 @@
+//! Callback invoked each time printing the queue empties it, e.g. to let a monitoring thread know that all output was written.
+//! - Only invoked on the transition from non-empty to empty, not when a pass finds the queue already empty.
+//! - Messages printed directly by the caller (when the queue is empty) don't invoke it.
+//! - Invoked on the thread printing the queue, after the last message and [BATCH_POSTAMBLE](crate::config::batch_postamble) were written.
+//!
+//! # Default: None
+
+use parking_lot::RwLock;
+
+/// A callback for [ON_QUEUE_EMPTY](self).
+pub type OnQueueEmpty = Box<dyn Fn() + Send + Sync>;
+
+/// Current value of [ON_QUEUE_EMPTY](self).
+static CURRENT: RwLock<Option<OnQueueEmpty>> = RwLock::new(None);
+
+/// Set global config [ON_QUEUE_EMPTY](self), replacing the previous callback.
+pub fn set(callback: OnQueueEmpty) {
+	*CURRENT.write() = Some(callback);
+}
+
+/// Remove the callback stored in global config [ON_QUEUE_EMPTY](self).
+pub fn clear() {
+	*CURRENT.write() = None;
+}
+
+/// WARNING: Will lock [ON_QUEUE_EMPTY](self) for reading, recursively so that the callback may print.
+pub(crate) fn notify() {
+	let guard = CURRENT.read_recursive();
+	if let Some(callback) = guard.as_ref() {
+		callback();
+	}
+
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let calls_clone = Arc::clone(&calls);
+	set(Box::new(move || { calls_clone.fetch_add(1, Ordering::Relaxed); }));
+
+	{
+		crate::async_impl::pause();
+		for index in 1..=3 {
+			crate::comfy_println!("Test_0{index}");
+		}
+
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written().len(), 3);
+		assert_eq!(calls.load(Ordering::Relaxed), 1);
+	}
+
+	{
+		// Printed directly, the queue stays empty.
+		crate::comfy_println!("Test_04");
+		test_utils::yield_until_idle();
+		assert_eq!(calls.load(Ordering::Relaxed), 1);
+	}
+
+	{
+		super::allow_logging_print_failures::set(false);
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_05");
+		test_utils::yield_until_idle();
+		assert_eq!(calls.load(Ordering::Relaxed), 2);
+	}
+}
@@ -2,6 +2,8 @@
 //! This only applies to user-generated messages, errors generated by [Stdout::write()](std::io::Stdout::write()) and [Stderr::write()](std::io::Stderr::write()) will always follow [KeepOldest](On_QueueFull::KeepOldest), meaning they will never replace user messages.
 //! 0. **KeepOldest**: Do not store the message.
 //! 1. **KeepNewest**: Remove the oldest message from the queue and store the new message.
+//! 2. **Spill**: Append the new message to the file at [LOG_IO_PATH](crate::config::log_io_path) instead, 
+//!    to be read back by [replay_spill()](crate::async_impl::replay_spill()). The message is dropped if writing to the file fails.
 //! 
 //! # Default: [KeepOldest](On_QueueFull::KeepOldest)
 //! 
@@ -21,11 +23,15 @@ pub const ENV_NAME: &str = "COMFY_PRINT_ON_QUEUE_FULL";
 /// See [ON_QUEUE_FULL](self).
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub enum On_QueueFull {
 	/// If the queue is full, do not store new messages.
 	KeepOldest = 0,
 	/// If the queue is full, remove the oldest message and store the new message.
 	KeepNewest = 1,
+	/// If the queue is full, append new messages to the file at [LOG_IO_PATH](crate::config::log_io_path), 
+	/// see [replay_spill()](crate::async_impl::replay_spill()).
+	Spill = 2,
 }
 
 impl FromStr for On_QueueFull {
@@ -35,6 +41,7 @@ impl FromStr for On_QueueFull {
 		match s {
 			"0" | "KeepOldest" => Ok(On_QueueFull::KeepOldest),
 			"1" | "KeepNewest" => Ok(On_QueueFull::KeepNewest),
+			"2" | "Spill" => Ok(On_QueueFull::Spill),
 			_ => Err(format!("Invalid string value for On_QueueFull: {}", s)),
 		}
 	}
@@ -44,6 +51,7 @@ impl FromStr for On_QueueFull {
 pub fn get() -> On_QueueFull {
 	return match CURRENT.load(Ordering::Relaxed) {
 		1 => On_QueueFull::KeepNewest,
+		2 => On_QueueFull::Spill,
 		_ => On_QueueFull::KeepOldest, // 0
 	};
 }
@@ -57,6 +65,7 @@ pub fn set(new_value: On_QueueFull) {
 fn test() {
 	use crate::test_utils;
 	use crate::config;
+	let _guard = test_utils::lock_tests();
 
 	// Just so the error messages don't interfere with the test.
 	config::allow_logging_print_failures::set(false);
@@ -99,6 +108,10 @@ fn test() {
 		std::env::set_var(ENV_NAME, "KeepNewest");
 		super::env_vars::load_all();
 		assert_eq!(get(), On_QueueFull::KeepNewest);
+
+		std::env::set_var(ENV_NAME, "Spill");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_QueueFull::Spill);
 	}
 
 	{
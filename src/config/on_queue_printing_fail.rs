@@ -4,6 +4,8 @@
 //!		- The counter is reset if writing succeeds. 
 //! 1. **Return**: do nothing. [comfy_print](crate) will attempt to print the queue next time you use one of the macros.
 //! 
+//! Can be overridden per message with [Message::with_fail_policy()](crate::message::Message::with_fail_policy()).
+//! 
 //! # Default: [TryUntilMaxRetries](On_QueuePrintingFail::TryUntilMaxRetries)
 
 use std::str::FromStr;
@@ -18,6 +20,7 @@ pub const ENV_NAME: &str = "COMFY_PRINT_ON_QUEUE_PRINTING_FAIL";
 /// See [ON_QUEUE_PRINTING_FAIL](self).
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub enum On_QueuePrintingFail {
 	/// If printing fails, attempt to print the queue until [MAX_RETRIES](crate::config::max_retries) is reached.
 	TryUntilMaxRetries = 0,
@@ -51,9 +54,11 @@ pub fn set(new_value: On_QueuePrintingFail) {
 }
 
 #[test]
+#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
 fn test() {
 	use crate::test_utils;
 	use crate::config;
+	let _guard = test_utils::lock_tests();
 
 	// Just so the error messages don't interfere with the test.
 	config::allow_logging_print_failures::set(false);
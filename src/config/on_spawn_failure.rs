@@ -0,0 +1,124 @@
+//! Determines what to do when spawning the thread responsible for printing the queue fails, e.g. under thread exhaustion.
+//! 0. **Synchronous**: Print the queue on the caller's thread, blocking it.
+//! 1. **DropQueue**: Drop every message in the queue, reported through [DROP_CHANNEL](crate::config::drop_channel).
+//! 2. **RetryLater**: Leave the queue as is, the next print retries spawning the thread.
+//!    Only the first failure in a row is reported, so that repeated failures don't fill the queue with diagnostics.
+//! 
+//! Doesn't apply with feature **single-thread** or [DRAIN_SPAWNER](crate::config::drain_spawner), which never spawn threads.
+//! 
+//! # Default: [Synchronous](On_SpawnFailure::Synchronous)
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Current value of [ON_SPAWN_FAILURE](self).
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Environment variable name for global config [ON_SPAWN_FAILURE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_ON_SPAWN_FAILURE";
+
+/// See [ON_SPAWN_FAILURE](self).
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum On_SpawnFailure {
+	/// Print the queue on the caller's thread.
+	Synchronous = 0,
+	/// Drop every message in the queue.
+	DropQueue = 1,
+	/// Leave the queue for the next print to retry spawning.
+	RetryLater = 2,
+}
+
+impl FromStr for On_SpawnFailure {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "Synchronous" => Ok(On_SpawnFailure::Synchronous),
+			"1" | "DropQueue" => Ok(On_SpawnFailure::DropQueue),
+			"2" | "RetryLater" => Ok(On_SpawnFailure::RetryLater),
+			_ => Err(format!("Invalid string value for On_SpawnFailure: {}", s)),
+		}
+	}
+}
+
+/// Get global config [ON_SPAWN_FAILURE](self).
+pub fn get() -> On_SpawnFailure {
+	return match CURRENT.load(Ordering::Relaxed) {
+		1 => On_SpawnFailure::DropQueue,
+		2 => On_SpawnFailure::RetryLater,
+		_ => On_SpawnFailure::Synchronous, // 0
+	};
+}
+
+/// Set global config [ON_SPAWN_FAILURE](self).
+pub fn set(new_value: On_SpawnFailure) {
+	CURRENT.store(new_value as u8, Ordering::Relaxed);
+}
+
+#[test]
+#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+fn test() {
+	use std::sync::atomic::Ordering;
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	use crate::async_impl::{self, tests::FAIL_NEXT_SPAWNS};
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+
+	{
+		std::env::set_var(ENV_NAME, "RetryLater");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_SpawnFailure::RetryLater);
+
+		std::env::set_var(ENV_NAME, "1");
+		super::env_vars::load_all();
+		assert_eq!(get(), On_SpawnFailure::DropQueue);
+		std::env::remove_var(ENV_NAME);
+	}
+
+	{
+		set(On_SpawnFailure::Synchronous);
+		FAIL_NEXT_SPAWNS.store(1, Ordering::Relaxed);
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_01");
+
+		// Printed before returning, without waiting for another thread.
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+	}
+
+	{
+		set(On_SpawnFailure::DropQueue);
+		FAIL_NEXT_SPAWNS.store(1, Ordering::Relaxed);
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_02");
+
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(async_impl::dropped_count(), 1);
+		assert!(test_utils::take_written().is_empty());
+	}
+
+	{
+		set(On_SpawnFailure::RetryLater);
+		FAIL_NEXT_SPAWNS.store(2, Ordering::Relaxed);
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_03");
+		crate::comfy_println!("Test_04");
+
+		// Both spawns failed, the queue waits for the next print.
+		assert_eq!(test_utils::get_queue().len(), 2);
+		assert!(test_utils::take_written().is_empty());
+
+		crate::comfy_println!("Test_05");
+		test_utils::yield_until_idle();
+
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_03\n".to_string()),
+			(OutputKind::Stdout, "Test_04\n".to_string()),
+			(OutputKind::Stdout, "Test_05\n".to_string()),
+		]);
+	}
+}
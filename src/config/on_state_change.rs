@@ -0,0 +1,74 @@
+//! Callback invoked each time the state of the queue's printer changes, with the names of the previous and the new state, 
+//! e.g. to trace when a printing thread is spawned or released.
+//! - The names are the ones returned by [state_name()](crate::async_impl::state_name).
+//! - Invoked on the thread that changed the state, after releasing it, so the callback may print.
+//!   Keep in mind that printing may change the state again, invoking the callback recursively.
+//! - Transitions between states of the same name are not reported, e.g. a thread replacing another.
+//! - A printing thread that finished is still `"Threaded"` until the state changes again, e.g. by [shutdown()](crate::async_impl::shutdown).
+//!
+//! # Default: None
+
+use parking_lot::RwLock;
+
+/// A callback for [ON_STATE_CHANGE](self), receives the names of the previous and the new state.
+pub type OnStateChange = Box<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Current value of [ON_STATE_CHANGE](self).
+static CURRENT: RwLock<Option<OnStateChange>> = RwLock::new(None);
+
+/// Set global config [ON_STATE_CHANGE](self), replacing the previous callback.
+pub fn set(callback: OnStateChange) {
+	*CURRENT.write() = Some(callback);
+}
+
+/// Remove the callback stored in global config [ON_STATE_CHANGE](self).
+pub fn clear() {
+	*CURRENT.write() = None;
+}
+
+/// WARNING: Will lock [ON_STATE_CHANGE](self) for reading, recursively so that the callback may print.
+pub(crate) fn notify(from: &str, to: &str) {
+	let guard = CURRENT.read_recursive();
+	if let Some(callback) = guard.as_ref() {
+		callback(from, to);
+	}
+
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	use std::sync::Arc;
+	use parking_lot::Mutex;
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+	
+	// A thread left by previous tests is only released by [shutdown()](crate::async_impl::shutdown).
+	assert!(crate::async_impl::shutdown(std::time::Duration::from_secs(5)));
+
+	let transitions = Arc::new(Mutex::new(Vec::<(String, String)>::new()));
+	let transitions_clone = Arc::clone(&transitions);
+	set(Box::new(move |from, to| transitions_clone.lock().push((from.to_string(), to.to_string()))));
+
+	{
+		// Printed directly, the state never changes.
+		crate::comfy_println!("Test_01");
+		test_utils::yield_until_idle();
+		assert!(transitions.lock().is_empty());
+	}
+
+	{
+		super::allow_logging_print_failures::set(false);
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_02");
+		test_utils::yield_until_idle();
+		assert!(crate::async_impl::shutdown(std::time::Duration::from_secs(5)));
+		assert_eq!(crate::async_impl::state_name(), "Idle");
+
+		let printer = if cfg!(feature = "single-thread") { "Synchronous" } else { "Threaded" };
+		let expected = vec![("Idle", printer), (printer, "Idle")];
+		let transitions = std::mem::take(&mut *transitions.lock());
+		assert_eq!(transitions.iter().map(|(from, to)| (from.as_str(), to.as_str())).collect::<Vec<_>>(), expected);
+		assert_eq!(test_utils::take_written().iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>(), ["Test_01\n", "Test_02\n"]);
+	}
+}
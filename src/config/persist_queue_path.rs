@@ -0,0 +1,50 @@
+//! Path to the file where [persist_queue()](crate::async_impl::persist_queue()) stores unprinted messages, 
+//! so that [restore_queue()](crate::async_impl::restore_queue()) can print them after a restart.
+//! - Path must include file name and extension.
+//! - If the directory doesn't exist, it will be created when persisting.
+//! - An empty string disables persistence.
+//! 
+//! # Default: "" (disabled)
+
+use parking_lot::Mutex;
+
+/// Current value of [PERSIST_QUEUE_PATH](self).
+static CURRENT: Mutex<String> = Mutex::new(String::new());
+
+/// Environment variable name for global config [PERSIST_QUEUE_PATH](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_PERSIST_QUEUE_PATH";
+
+/// The path stored in global config [PERSIST_QUEUE_PATH](self) will be appended to parameter `append_in_me`.
+pub fn get(append_in_me: &mut String) {
+	let guard = CURRENT.lock();
+	append_in_me.push_str(guard.as_str());
+	drop(guard);
+}
+
+/// Set global config [PERSIST_QUEUE_PATH](self).
+pub fn set(new_value: &str) {
+	let mut guard = CURRENT.lock();
+	guard.clear();
+	guard.push_str(new_value);
+	drop(guard);
+}
+
+#[test]
+fn test() {
+	let _guard = crate::test_utils::lock_tests();
+	let mut path = String::new();
+	
+	{
+		set("queue.txt");
+		get(&mut path);
+		assert_eq!(path, "queue.txt");
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "pending/queue.txt");
+		super::env_vars::load_all();
+		path.clear();
+		get(&mut path);
+		assert_eq!(path, "pending/queue.txt");
+	}
+}
@@ -0,0 +1,52 @@
+//! How long the thread printing the queue stays alive, parked, after the queue is empty.
+//! - Messages that fail to be printed during that time wake the thread up instead of spawning a new one,
+//!   which amortizes the cost of spawning threads under bursty load.
+//! - The thread exits once it stays idle for this long.
+//! - Has no effect with feature **single-thread**, where no thread is spawned.
+//! - Stored in whole milliseconds, the environment variable is read in milliseconds.
+//!
+//! # Default: Zero (the thread exits as soon as the queue is empty)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Current value of [PRINTER_IDLE_TIMEOUT](self) in milliseconds.
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+
+/// Environment variable name for global config [PRINTER_IDLE_TIMEOUT](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_PRINTER_IDLE_TIMEOUT";
+
+/// Get global config [PRINTER_IDLE_TIMEOUT](self).
+pub fn get() -> Duration {
+	return Duration::from_millis(CURRENT.load(Ordering::Relaxed));
+}
+
+/// Set global config [PRINTER_IDLE_TIMEOUT](self).
+pub fn set(new_value: Duration) {
+	let millis = u64::try_from(new_value.as_millis()).unwrap_or(u64::MAX);
+	CURRENT.store(millis, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "250");
+		super::env_vars::load_all();
+		assert_eq!(get(), Duration::from_millis(250));
+
+		std::env::set_var(ENV_NAME, "-1");
+		super::env_vars::load_all();
+		assert_eq!(get(), Duration::from_millis(250));
+	}
+
+	{
+		set(Duration::from_micros(1500));
+		assert_eq!(get(), Duration::from_millis(1));
+
+		set(Duration::MAX);
+		assert_eq!(get(), Duration::from_millis(u64::MAX));
+	}
+}
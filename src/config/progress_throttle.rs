@@ -0,0 +1,68 @@
+//! Minimum time between two [progress](crate::async_impl::print_progress()) lines, e.g. for progress bars updated thousands of times per second.
+//! - Updates arriving sooner than that aren't written, only the latest one is kept.
+//! - The kept update is written before the next regular message, or by [flush_with()](crate::async_impl::flush_with()) 
+//!   and [shutdown()](crate::async_impl::shutdown()), so the final state is never lost. The next progress line allowed through replaces it.
+//! - Stored in whole milliseconds, the environment variable is read in milliseconds.
+//!
+//! # Default: Zero (every update is written)
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Current value of [PROGRESS_THROTTLE](self) in milliseconds.
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+
+/// Environment variable name for global config [PROGRESS_THROTTLE](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_PROGRESS_THROTTLE";
+
+/// Get global config [PROGRESS_THROTTLE](self).
+pub fn get() -> Duration {
+	return Duration::from_millis(CURRENT.load(Ordering::Relaxed));
+}
+
+/// Set global config [PROGRESS_THROTTLE](self).
+pub fn set(new_value: Duration) {
+	let millis = u64::try_from(new_value.as_millis()).unwrap_or(u64::MAX);
+	CURRENT.store(millis, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "16");
+		super::env_vars::load_all();
+		assert_eq!(get(), Duration::from_millis(16));
+	}
+
+	set(Duration::from_secs(3600));
+
+	{
+		for index in 0..1000 {
+			async_impl::print_progress(format!("Test_{index}").as_str());
+		}
+
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "\rTest_0".to_string())]);
+
+		crate::comfy_println!("Done_01");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "\rTest_999".to_string()),
+			(OutputKind::Stdout, "\nDone_01\n".to_string()),
+		]);
+	}
+
+	{
+		for index in 0..1000 {
+			async_impl::print_progress(format!("Test_{index}").as_str());
+		}
+
+		async_impl::flush_with(0, None).unwrap();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "\rTest_999".to_string())]);
+	}
+}
@@ -0,0 +1,30 @@
+//! Channel notified when the queue first reaches [QUEUE_WARN_THRESHOLD](crate::config::queue_warn_threshold), with the length of the queue at that moment.
+//! - Sending never blocks, if the receiver was dropped the notification is silently discarded.
+//! 
+//! # Default: None
+
+use std::sync::mpsc::Sender;
+use parking_lot::Mutex;
+
+/// Current value of [QUEUE_WARN_CHANNEL](self).
+static CURRENT: Mutex<Option<Sender<usize>>> = Mutex::new(None);
+
+/// Set global config [QUEUE_WARN_CHANNEL](self), replacing the previous channel.
+pub fn set(new_value: Sender<usize>) {
+	*CURRENT.lock() = Some(new_value);
+}
+
+/// Remove the channel stored in global config [QUEUE_WARN_CHANNEL](self).
+pub fn clear() {
+	*CURRENT.lock() = None;
+}
+
+/// WARNING: Will lock [QUEUE_WARN_CHANNEL](self).
+pub(crate) fn notify(queue_len: usize) {
+	let guard = CURRENT.lock();
+	if let Some(sender) = guard.as_ref() {
+		let _ = sender.send(queue_len);
+	}
+	
+	drop(guard);
+}
@@ -0,0 +1,92 @@
+//! Queue length at which [QUEUE_WARN_CHANNEL](crate::config::queue_warn_channel) is notified, 
+//! giving an early warning before the queue reaches [MAX_QUEUE_LENGTH](crate::config::max_queue_length) and starts dropping messages.
+//! - Notified once when the queue reaches the threshold, then again only after the queue shrinks back below it.
+//! - **0** disables the warning.
+//! 
+//! # Default: **0** (None)
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Current value of [QUEUE_WARN_THRESHOLD](self), 0 means None.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the queue reached the threshold since it was last below it.
+static IS_ABOVE: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [QUEUE_WARN_THRESHOLD](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_QUEUE_WARN_THRESHOLD";
+
+/// Get global config [QUEUE_WARN_THRESHOLD](self).
+pub fn get() -> Option<usize> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		0 => None,
+		threshold => Some(threshold),
+	};
+}
+
+/// Set global config [QUEUE_WARN_THRESHOLD](self). `Some(0)` is the same as `None`.
+pub fn set(new_value: Option<usize>) { CURRENT.store(new_value.unwrap_or(0), Ordering::Relaxed); }
+
+/// Must be called each time the length of the queue changes, while the queue is still locked.
+pub(crate) fn observe(queue_len: usize) {
+	match get() {
+		Some(threshold) if queue_len >= threshold => {
+			if IS_ABOVE.swap(true, Ordering::Relaxed) == false {
+				super::queue_warn_channel::notify(queue_len);
+			}
+		}
+		_ => {
+			IS_ABOVE.store(false, Ordering::Relaxed);
+		}
+	}
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::config;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "100");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(100));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), None);
+	}
+
+	// Just so the error messages don't interfere with the test.
+	config::allow_logging_print_failures::set(false);
+	set(Some(3));
+	let (sender, receiver) = std::sync::mpsc::channel();
+	config::queue_warn_channel::set(sender);
+
+	{
+		crate::async_impl::pause();
+		crate::comfy_println!("Test_01");
+		crate::comfy_println!("Test_02");
+		assert!(receiver.try_recv().is_err());
+
+		crate::comfy_println!("Test_03");
+		crate::comfy_println!("Test_04");
+		crate::comfy_println!("Test_05");
+		assert_eq!(receiver.try_recv(), Ok(3));
+		assert!(receiver.try_recv().is_err());
+	}
+
+	{
+		crate::async_impl::resume();
+		test_utils::yield_until_idle();
+		assert!(receiver.try_recv().is_err());
+
+		crate::async_impl::pause();
+		for _ in 0..3 {
+			crate::comfy_println!("Test_06");
+		}
+
+		assert_eq!(receiver.try_recv(), Ok(3));
+		assert!(receiver.try_recv().is_err());
+	}
+}
@@ -0,0 +1,85 @@
+//! When set, replaces the default rendering of messages written to [std::io::stdout] | [std::io::stderr].
+//! - Receives the message and a writer for its text, which is then written to the message's stream.
+//! - Replaces every stream-only config, like [WRAP_WIDTH](crate::config::wrap_width) and [ANNOTATE_STREAM](crate::config::annotate_stream).
+//! - Messages written to disk are still rendered through [Message::rendered()].
+//! - An error returned by the renderer is handled as a failure to write the message, as is a panic.
+//!
+//! # Default: None
+
+use std::io::Write;
+use parking_lot::RwLock;
+use crate::message::{panic_payload_str, Message};
+
+/// A custom renderer, see [RENDERER](self).
+pub type Renderer = Box<dyn Fn(&Message, &mut dyn Write) -> std::io::Result<()> + Send + Sync>;
+
+/// Current value of [RENDERER](self).
+static CURRENT: RwLock<Option<Renderer>> = RwLock::new(None);
+
+/// Set global config [RENDERER](self), replacing the previous one.
+pub fn set(renderer: Renderer) {
+	*CURRENT.write() = Some(renderer);
+}
+
+/// Remove global config [RENDERER](self), going back to the default rendering.
+pub fn clear() {
+	*CURRENT.write() = None;
+}
+
+/// Renders `msg` through [RENDERER](self), [None] if it isn't set.
+///
+/// WARNING: Will lock [RENDERER](self) for reading, recursively so that the renderer may print.
+pub(crate) fn render(msg: &Message) -> Option<std::io::Result<String>> {
+	let guard = CURRENT.read_recursive();
+	let renderer = guard.as_ref()?;
+
+	let mut buffer = Vec::new();
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| renderer(msg, &mut buffer)));
+	drop(guard);
+
+	return match result {
+		Ok(Ok(())) => Some(Ok(String::from_utf8_lossy(&buffer).into_owned())),
+		Ok(Err(err)) => Some(Err(err)),
+		Err(payload) => {
+			let reason = panic_payload_str(payload.as_ref());
+			Some(Err(std::io::Error::other(format!("`comfy_print::config::renderer`: The renderer panicked.\nError: {reason}."))))
+		}
+	};
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		set(Box::new(|msg, writer| write!(writer, "{}", msg.rendered().to_uppercase())));
+
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprint!("Test_02");
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "TEST_01\n".to_string()),
+			(OutputKind::Stderr, "TEST_02".to_string()),
+		]);
+	}
+
+	{
+		super::allow_logging_print_failures::set(false);
+		super::on_queue_printing_fail::set(super::on_queue_printing_fail::On_QueuePrintingFail::Return);
+		set(Box::new(|_, _| Err(std::io::Error::other("Renderer failed"))));
+
+		crate::comfy_println!("Test_03");
+		test_utils::yield_until_idle();
+		assert!(test_utils::take_written().is_empty());
+		assert_eq!(test_utils::get_queue().len(), 1);
+
+		clear();
+		crate::comfy_println!("Test_04");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_03\n".to_string()),
+			(OutputKind::Stdout, "Test_04\n".to_string()),
+		]);
+	}
+}
@@ -0,0 +1,147 @@
+//! Routes messages matching a predicate to a named sink instead of their stream, e.g. lines containing `AUDIT` to an audit file.
+//! - Evaluated once per message, when it's printed through [comfy_print_async()](crate::async_impl::comfy_print_async()), after [MIDDLEWARE](crate::config::middleware).
+//! - Routes are tried in the order they were added, the first match wins. Messages matching no route go to their stream.
+//! - A message routed to a sink that isn't [set](set_sink()) when it's written goes to its stream instead.
+//! - Routed messages are rendered as they would be for their stream, and are still retried from the queue if writing to the sink fails.
+//! 
+//! # Default: No routes, no sinks
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use parking_lot::{Mutex, RwLock};
+use crate::message::Message;
+
+/// A predicate selecting the messages of a route, see [ROUTES](self).
+pub type RoutePredicate = Box<dyn Fn(&Message) -> bool + Send + Sync>;
+
+/// Routes of [ROUTES](self), with the id of their sink.
+static ROUTES: RwLock<Vec<(RoutePredicate, String)>> = RwLock::new(Vec::new());
+
+/// Sinks of [ROUTES](self), by id.
+static SINKS: Mutex<BTreeMap<String, Box<dyn Write + Send>>> = Mutex::new(BTreeMap::new());
+
+/// Append a route to global config [ROUTES](self): messages matching `predicate` are written to the sink `sink_id`.
+pub fn add_route(predicate: RoutePredicate, sink_id: &str) {
+	ROUTES.write().push((predicate, sink_id.to_string()));
+}
+
+/// Set the sink `sink_id` of global config [ROUTES](self), returning the sink it replaces, if any.
+pub fn set_sink(sink_id: &str, sink: Box<dyn Write + Send>) -> Option<Box<dyn Write + Send>> {
+	return SINKS.lock().insert(sink_id.to_string(), sink);
+}
+
+/// Remove the sink `sink_id` from global config [ROUTES](self).
+pub fn remove_sink(sink_id: &str) -> Option<Box<dyn Write + Send>> {
+	return SINKS.lock().remove(sink_id);
+}
+
+/// Remove every route and sink from global config [ROUTES](self).
+pub fn clear() {
+	ROUTES.write().clear();
+	SINKS.lock().clear();
+}
+
+/// The sink id of the first route matching `msg`, if any.
+///
+/// WARNING: Will lock [ROUTES] for reading, recursively so that a predicate may print.
+pub(crate) fn resolve(msg: &Message) -> Option<String> {
+	let guard = ROUTES.read_recursive();
+	let sink_id = guard.iter()
+		.find(|(predicate, _)| predicate(msg))
+		.map(|(_, sink_id)| sink_id.clone());
+	
+	drop(guard);
+	return sink_id;
+}
+
+/// Whether the sink `sink_id` is set.
+///
+/// WARNING: Will lock [SINKS].
+pub(crate) fn is_set(sink_id: &str) -> bool {
+	return SINKS.lock().contains_key(sink_id);
+}
+
+/// Writes `rendered` to the sink `sink_id`, [None] if it isn't set.
+///
+/// WARNING: Will lock [SINKS].
+pub(crate) fn write(sink_id: &str, rendered: &str, should_flush: bool) -> Option<std::io::Result<()>> {
+	let mut guard = SINKS.lock();
+	let sink = guard.get_mut(sink_id)?;
+	
+	let mut result = sink.write_all(rendered.as_bytes());
+	if result.is_ok() && should_flush {
+		result = sink.flush();
+	}
+	
+	drop(guard);
+	return Some(result);
+}
+
+#[test]
+fn test() {
+	use std::sync::Arc;
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	#[derive(Clone, Default)]
+	struct CapturingSink(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for CapturingSink {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().extend_from_slice(buf);
+			return Ok(buf.len());
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			return Ok(());
+		}
+	}
+
+	let audit = CapturingSink::default();
+
+	{
+		set_sink("audit", Box::new(audit.clone()));
+		add_route(Box::new(|msg| msg.str().contains("AUDIT")), "audit");
+
+		crate::comfy_println!("AUDIT: Test_01");
+		crate::comfy_eprintln!("Test_02");
+		crate::comfy_eprintln!("AUDIT: Test_03");
+
+		assert_eq!(String::from_utf8(audit.0.lock().clone()).unwrap(), "AUDIT: Test_01\nAUDIT: Test_03\n");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, "Test_02\n".to_string())]);
+	}
+
+	{
+		// The banner and the stderr flag follow the message to its sink, not its stream.
+		audit.0.lock().clear();
+		super::session_banner::set(Some("Test_banner"));
+		super::session_banner::rearm();
+		super::track_stderr_printed::set(true);
+		crate::async_impl::STDERR_PRINTED.store(false, std::sync::atomic::Ordering::Relaxed);
+		
+		crate::comfy_eprintln!("AUDIT: Test_05");
+		assert_eq!(String::from_utf8(audit.0.lock().clone()).unwrap(), "Test_banner\nAUDIT: Test_05\n");
+		assert!(test_utils::take_written().is_empty());
+		assert_eq!(crate::async_impl::stderr_was_printed(), false);
+	}
+
+	{
+		// The bulk entry points are routed like any other message.
+		audit.0.lock().clear();
+		crate::async_impl::println_slice(&["AUDIT: Test_06", "Test_07"], OutputKind::Stdout);
+		crate::async_impl::enqueue_many(vec![Message::error_ln("AUDIT: Test_08")]);
+		crate::async_impl::thread_buffer_print("AUDIT: Test_09\n", OutputKind::Stdout);
+		crate::async_impl::thread_flush();
+		test_utils::yield_until_idle();
+		
+		assert_eq!(String::from_utf8(audit.0.lock().clone()).unwrap(), "AUDIT: Test_06\nAUDIT: Test_08\nAUDIT: Test_09\n");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_07\n".to_string())]);
+	}
+
+	{
+		assert!(remove_sink("audit").is_some());
+		crate::comfy_println!("AUDIT: Test_04");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "AUDIT: Test_04\n".to_string())]);
+	}
+}
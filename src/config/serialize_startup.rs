@@ -0,0 +1,98 @@
+//! Determines whether messages printed at startup are always queued, until the queue is printed for the first time.
+//! - Without it, the first messages are written directly by their callers, which may happen out of order if several threads print at once while another message is being queued.
+//! - Once the first pass over the queue finishes, messages are written directly again when the queue is empty.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [SERIALIZE_STARTUP](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Whether the queue was printed at least once, ending the startup period.
+pub(crate) static STARTUP_DONE: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [SERIALIZE_STARTUP](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_SERIALIZE_STARTUP";
+
+/// Get global config [SERIALIZE_STARTUP](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [SERIALIZE_STARTUP](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+/// Whether messages must be queued instead of written directly.
+pub(crate) fn is_active() -> bool {
+	return get() && STARTUP_DONE.load(Ordering::Relaxed) == false;
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	{
+		set(true);
+		
+		// While the state is locked no thread can start printing the queue, so only the caller could write.
+		let state_guard = async_impl::STATE.lock();
+		crate::comfy_println!("Test_01");
+		crate::comfy_println!("Test_02");
+		assert!(test_utils::take_written().is_empty());
+		drop(state_guard);
+		
+		async_impl::resume();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stdout, "Test_01\n".to_string()),
+			(OutputKind::Stdout, "Test_02\n".to_string()),
+		]);
+		
+		// Startup is over, the caller writes directly again.
+		let state_guard = async_impl::STATE.lock();
+		crate::comfy_println!("Test_03");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_03\n".to_string())]);
+		drop(state_guard);
+	}
+
+	{
+		test_utils::reset_globals();
+		set(true);
+		
+		let threads: Vec<_> = (0..4)
+			.map(|thread_index| std::thread::spawn(move || {
+				for index in 0..25 {
+					crate::comfy_println!("{thread_index}-{index}");
+				}
+			}))
+			.collect();
+		
+		threads.into_iter().for_each(|thread| thread.join().unwrap());
+		test_utils::yield_until_idle();
+		
+		let written = test_utils::take_written();
+		assert_eq!(written.len(), 100);
+		
+		for thread_index in 0..4 {
+			let prefix = format!("{thread_index}-");
+			let indices: Vec<usize> = written.iter()
+				.filter_map(|(_, text)| text.strip_prefix(prefix.as_str()))
+				.map(|index| index.trim_end().parse().unwrap())
+				.collect();
+			
+			assert_eq!(indices, (0..25).collect::<Vec<_>>());
+		}
+	}
+}
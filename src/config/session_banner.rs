@@ -0,0 +1,103 @@
+//! Written once per process, right before the first message written to a stream, to tell process runs apart, 
+//! e.g. in a log file that many runs append to.
+//! - `{pid}` is replaced by the process id, `{timestamp}` by the seconds since the Unix epoch.
+//! - Written on its own line, wherever that first message goes (its stream, or its [route](crate::config::routes), descriptor or terminal), 
+//!   and to disk along with it if [ALWAYS_LOG_TO_DISK](crate::config::always_log_to_disk) applies.
+//! - If writing it fails, it's written again before the next message instead.
+//! - Setting it after the first message was written has no effect.
+//!
+//! # Default: None
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::Mutex;
+
+/// Current value of [SESSION_BANNER](self).
+static CURRENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Whether the first message of the process was written, so the banner is written at most once.
+pub(crate) static FIRST_WRITE_DONE: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [SESSION_BANNER](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_SESSION_BANNER";
+
+/// Get global config [SESSION_BANNER](self).
+pub fn get() -> Option<String> {
+	return CURRENT.lock().clone();
+}
+
+/// Set global config [SESSION_BANNER](self).
+pub fn set(new_value: Option<&str>) {
+	*CURRENT.lock() = new_value.map(str::to_string);
+}
+
+/// The banner to write before the first message of the process, with its placeholders replaced, 
+/// or [None] if it isn't the first message or no banner is set.
+/// 
+/// Call [rearm()] if writing it fails.
+/// 
+/// WARNING: May lock [SESSION_BANNER](self).
+pub(crate) fn take_first() -> Option<String> {
+	if FIRST_WRITE_DONE.swap(true, Ordering::Relaxed) {
+		return None;
+	}
+	
+	let banner = get()?;
+	let timestamp = std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map_or(0, |elapsed| elapsed.as_secs());
+	
+	let mut banner = banner
+		.replace("{pid}", std::process::id().to_string().as_str())
+		.replace("{timestamp}", timestamp.to_string().as_str());
+	
+	if banner.ends_with('\n') == false {
+		banner.push('\n');
+	}
+	
+	return Some(banner);
+}
+
+/// Writes the banner again before the next message, after it failed to be written.
+pub(crate) fn rearm() {
+	FIRST_WRITE_DONE.store(false, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "=== {pid} ===");
+		super::env_vars::load_all();
+		assert_eq!(get().as_deref(), Some("=== {pid} ==="));
+	}
+
+	{
+		set(Some("=== session {pid} started at {timestamp} ==="));
+		crate::comfy_println!("Test_01");
+		crate::comfy_eprintln!("Test_02");
+		test_utils::yield_until_idle();
+
+		let written = test_utils::take_written();
+		assert_eq!(written.len(), 3);
+		assert_eq!(written[0].0, OutputKind::Stdout);
+		assert!(written[0].1.starts_with(format!("=== session {} started at ", std::process::id()).as_str()));
+		assert!(written[0].1.ends_with(" ===\n"));
+		assert_eq!(written[1..], [(OutputKind::Stdout, "Test_01\n".to_string()), (OutputKind::Stderr, "Test_02\n".to_string())]);
+	}
+
+	{
+		rearm();
+		set(Some("=== session ===\n"));
+		super::allow_logging_print_failures::set(false);
+		test_utils::write_fail_once();
+		crate::comfy_eprintln!("Test_03");
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stderr, "=== session ===\n".to_string()),
+			(OutputKind::Stderr, "Test_03\n".to_string()),
+		]);
+	}
+}
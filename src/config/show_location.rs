@@ -0,0 +1,51 @@
+//! Determines whether messages printed through [comfy_print](crate)'s macros are prefixed with the location of the macro call, as `[file:line] `.
+//! - Useful for finding where a message came from while debugging.
+//! - Messages built manually only have a location if set through [Message::with_location()](crate::message::Message::with_location()).
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [SHOW_LOCATION](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [SHOW_LOCATION](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_SHOW_LOCATION";
+
+/// Get global config [SHOW_LOCATION](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [SHOW_LOCATION](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::{Message, OutputKind};
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	{
+		crate::comfy_println!("Test_01");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+	}
+
+	{
+		set(true);
+		let line = line!() + 1;
+		crate::comfy_eprintln!("Test_02");
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, format!("[src/config/show_location.rs:{line}] Test_02\n"))]);
+		
+		assert_eq!(Message::standard("Test_03").to_string(), "Test_03");
+		assert_eq!(Message::standard("Test_04\nTest_05").with_location("main.rs:1").with_indent(2).to_string(), "  [main.rs:1] Test_04\n  Test_05");
+	}
+}
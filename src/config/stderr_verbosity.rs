@@ -0,0 +1,70 @@
+//! Highest [verbosity](crate::message::Message::with_verbosity()) of the messages printed to [std::io::stderr], more verbose ones are discarded.
+//! - Meant to map directly to a CLI's `-v`/`-vv` flags: e.g. 0 without flags, 1 with `-v`, 2 with `-vv`.
+//! - Messages to [std::io::stdout] are unaffected.
+//! - Discarded messages are not reported to [DROP_CHANNEL](crate::config::drop_channel), they were never meant to be printed.
+//! 
+//! # Default: **255** (every message is printed)
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use crate::message::{Message, OutputKind};
+
+/// Current value of [STDERR_VERBOSITY](self).
+static CURRENT: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Environment variable name for global config [STDERR_VERBOSITY](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_STDERR_VERBOSITY";
+
+/// Get global config [STDERR_VERBOSITY](self).
+pub fn get() -> u8 { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [STDERR_VERBOSITY](self).
+pub fn set(new_value: u8) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+/// Whether `msg` passes [STDERR_VERBOSITY](self).
+pub(crate) fn permits(msg: &Message) -> bool {
+	return msg.output_kind() == OutputKind::Stdout || msg.verbosity() <= get();
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl::comfy_print_async;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "2");
+		super::env_vars::load_all();
+		assert_eq!(get(), 2);
+
+		std::env::set_var(ENV_NAME, "256");
+		super::env_vars::load_all();
+		assert_eq!(get(), 2);
+	}
+
+	{
+		set(1);
+		comfy_print_async(Message::error_ln("Test_01").with_verbosity(1));
+		comfy_print_async(Message::error_ln("Test_02").with_verbosity(2));
+		comfy_print_async(Message::standard_ln("Test_03").with_verbosity(2));
+
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stderr, "Test_01\n".to_string()),
+			(OutputKind::Stdout, "Test_03\n".to_string()),
+		]);
+	}
+
+	{
+		set(0);
+		assert_eq!(crate::async_impl::enqueue_many(vec![Message::error_ln("Test_04").with_verbosity(1)]), (0, 0));
+		crate::async_impl::println_slice(&["Test_05"], OutputKind::Stderr);
+		crate::async_impl::thread_buffer_print("Test_06", OutputKind::Stderr);
+		crate::async_impl::thread_flush();
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![
+			(OutputKind::Stderr, "Test_05\n".to_string()),
+			(OutputKind::Stderr, "Test_06".to_string()),
+		]);
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(crate::async_impl::dropped_count(), 0);
+	}
+}
@@ -0,0 +1,50 @@
+//! Deterministic mode for tests: every print that leaves messages in the queue drains it on the calling thread before returning,
+//! instead of handing it to another thread, so tests don't need to wait for the queue to be printed.
+//! - Takes precedence over [DRAIN_SPAWNER](crate::config::drain_spawner) and [PRINTER_IDLE_TIMEOUT](crate::config::printer_idle_timeout).
+//! - Messages that keep failing still follow [ON_QUEUE_PRINTING_FAIL](crate::config::on_queue_printing_fail) 
+//!   and [MAX_RETRIES](crate::config::max_retries), so the queue may not be empty afterward.
+//! - Requires feature **test-sync-drain**, not meant for production builds.
+//! - Not loaded from the environment.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [TEST_SYNC_DRAIN](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Get global config [TEST_SYNC_DRAIN](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [TEST_SYNC_DRAIN](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::OutputKind;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+	set(true);
+
+	{
+		test_utils::write_fail_once();
+		crate::comfy_println!("Test_01");
+
+		// No waiting: drained before returning.
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(crate::async_impl::STATE.lock().is_busy(), false);
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stdout, "Test_01\n".to_string())]);
+	}
+
+	{
+		crate::async_impl::pause();
+		crate::comfy_println!("Test_02");
+		crate::comfy_println!("Test_03");
+		assert_eq!(test_utils::get_queue().len(), 2);
+
+		crate::async_impl::resume();
+		assert!(test_utils::get_queue().is_empty());
+		assert_eq!(test_utils::take_written().len(), 2);
+	}
+}
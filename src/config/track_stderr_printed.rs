@@ -0,0 +1,55 @@
+//! Determines whether [stderr_was_printed()](crate::async_impl::stderr_was_printed()) is updated when a message is successfully written to [std::io::stderr].
+//! - Useful for CLIs that should exit with a non-zero code if any error was printed.
+//! - Messages redirected elsewhere (e.g. by [ROUTES](crate::config::routes)) don't count, even if they were meant for stderr.
+//! 
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [TRACK_STDERR_PRINTED](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [TRACK_STDERR_PRINTED](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_TRACK_STDERR_PRINTED";
+
+/// Get global config [TRACK_STDERR_PRINTED](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [TRACK_STDERR_PRINTED](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+#[cfg_attr(feature = "single-thread", ignore = "expects the queue to be printed by another thread")]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl::stderr_was_printed;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+
+	{
+		crate::comfy_eprintln!("Test_01");
+		assert_eq!(stderr_was_printed(), false);
+	}
+
+	{
+		set(true);
+		crate::comfy_println!("Test_02");
+		assert_eq!(stderr_was_printed(), false);
+
+		test_utils::write_fail_once();
+		crate::comfy_eprintln!("Test_03");
+		assert_eq!(stderr_was_printed(), false);
+
+		test_utils::yield_until_idle();
+		assert_eq!(stderr_was_printed(), true);
+	}
+}
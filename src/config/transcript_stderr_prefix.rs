@@ -0,0 +1,48 @@
+//! Prefix of each stderr line in a [transcript](crate::async_impl::capture_transcript()), telling it apart from stdout.
+//! - Stdout lines are never prefixed.
+//! - An empty string disables it, leaving the streams indistinguishable.
+//! 
+//! # Default: "[stderr] "
+
+use std::borrow::Cow;
+use parking_lot::Mutex;
+
+/// Current value of [TRANSCRIPT_STDERR_PREFIX](self).
+static CURRENT: Mutex<Cow<'static, str>> = Mutex::new(Cow::Borrowed(DEFAULT));
+
+/// Default value of [TRANSCRIPT_STDERR_PREFIX](self).
+pub const DEFAULT: &str = "[stderr] ";
+
+/// Environment variable name for global config [TRANSCRIPT_STDERR_PREFIX](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_TRANSCRIPT_STDERR_PREFIX";
+
+/// The string stored in global config [TRANSCRIPT_STDERR_PREFIX](self) will be appended to parameter `append_in_me`.
+pub fn get(append_in_me: &mut String) {
+	let guard = CURRENT.lock();
+	append_in_me.push_str(guard.as_ref());
+	drop(guard);
+}
+
+/// Set global config [TRANSCRIPT_STDERR_PREFIX](self).
+pub fn set(new_value: &str) {
+	*CURRENT.lock() = Cow::Owned(new_value.to_string());
+}
+
+#[test]
+fn test() {
+	let _guard = crate::test_utils::lock_tests();
+	let mut prefix = String::new();
+	
+	{
+		get(&mut prefix);
+		assert_eq!(prefix, DEFAULT);
+	}
+
+	{
+		std::env::set_var(ENV_NAME, "E| ");
+		super::env_vars::load_all();
+		prefix.clear();
+		get(&mut prefix);
+		assert_eq!(prefix, "E| ");
+	}
+}
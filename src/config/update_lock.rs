@@ -0,0 +1,120 @@
+//! Groups changes to several configs, so that [comfy_print](crate) sees either all of them or none, see [apply()].
+//! - Each config is stored on its own, a single `set` always takes effect immediately.
+//! - Decisions that depend on several configs read them together, e.g. [MAX_QUEUE_LENGTH](crate::config::max_queue_length),
+//!   [ON_QUEUE_FULL](crate::config::on_queue_full) and [CATEGORY_CAPS](crate::config::category_caps) when a message is queued,
+//!   or [COMBINED_STREAM](crate::config::combined_stream), [ANNOTATE_STREAM](crate::config::annotate_stream), 
+//!   [DRY_RUN](crate::config::dry_run), [ALWAYS_LOG_TO_DISK](crate::config::always_log_to_disk) and 
+//!   [FLUSH_STRATEGY](crate::config::flush_strategy) when a message is written.
+//! - [from_json()](crate::config::from_json) and [load_all()](crate::config::env_vars::load_all) apply their changes as a group.
+
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Held for writing while a group of changes is applied, for reading while related configs are read together.
+static LOCK: RwLock<()> = RwLock::new(());
+
+/// Runs `update`, e.g. several `set` calls, as a single change: configs read together never observe only part of it.
+/// 
+/// `update` must not print, nor call [apply()] again, which would deadlock.
+/// 
+/// ```
+/// use comfy_print::config;
+/// use comfy_print::config::on_queue_full::On_QueueFull;
+/// 
+/// config::apply(|| {
+/// 	config::max_queue_length::set(16);
+/// 	config::on_queue_full::set(On_QueueFull::KeepNewest);
+/// });
+/// ```
+/// 
+/// WARNING: Will lock [UPDATE_LOCK](self) for writing.
+pub fn apply<R>(update: impl FnOnce() -> R) -> R {
+	let guard = write();
+	let result = update();
+	drop(guard);
+	return result;
+}
+
+/// Waits for the group of changes being applied, if any, see [apply()].
+/// 
+/// Hold the guard while reading the configs, then release it before doing anything that may print.
+pub(crate) fn read() -> RwLockReadGuard<'static, ()> {
+	return LOCK.read_recursive();
+}
+
+/// See [apply()].
+pub(crate) fn write() -> RwLockWriteGuard<'static, ()> {
+	return LOCK.write();
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::async_impl;
+	use crate::message::{Message, OutputKind};
+	use super::on_queue_full::{self, On_QueueFull};
+	use super::max_queue_length;
+	let _guard = test_utils::lock_tests();
+	super::allow_logging_print_failures::set(false);
+	async_impl::pause();
+
+	max_queue_length::set(3);
+
+	// Only (1, KeepNewest, Some(Stderr), annotated) and (3, KeepOldest, None, not annotated) are ever applied.
+	let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+	let writer = std::thread::spawn({
+		let stop = stop.clone();
+		move || {
+			let mut small = true;
+			while stop.load(std::sync::atomic::Ordering::Relaxed) == false {
+				apply(|| {
+					if small {
+						max_queue_length::set(1);
+						on_queue_full::set(On_QueueFull::KeepNewest);
+						super::combined_stream::set(Some(OutputKind::Stderr));
+						super::annotate_stream::set(true);
+					} else {
+						max_queue_length::set(3);
+						on_queue_full::set(On_QueueFull::KeepOldest);
+						super::combined_stream::set(None);
+						super::annotate_stream::set(false);
+					}
+				});
+				
+				small = small == false;
+			}
+		}
+	});
+
+	for _ in 0..2000 {
+		{
+			let guard = read();
+			let pair = (max_queue_length::get(), on_queue_full::get());
+			drop(guard);
+
+			assert!(matches!(pair, (1, On_QueueFull::KeepNewest) | (3, On_QueueFull::KeepOldest)), "{pair:?}");
+		}
+
+		{
+			// The first message always fits. The second one either replaces it (1, KeepNewest) or joins it (3, KeepOldest).
+			// Keeping only the first one would mean queueing saw (1, KeepOldest), half of a change.
+			test_utils::get_queue().clear();
+			async_impl::enqueue_many(vec![Message::standard("Test_01"), Message::standard("Test_02")]);
+
+			let queue = test_utils::get_queue();
+			let queued = queue.iter().map(Message::str).collect::<Vec<_>>();
+			assert!(queued == ["Test_02"] || queued == ["Test_01", "Test_02"], "{queued:?}");
+			drop(queue);
+		}
+
+		{
+			// Writing a message (here, into a capture) reads where and how to write it together.
+			// An unannotated message on stderr would mean it saw (Some(Stderr), not annotated), half of a change.
+			let captured = async_impl::capture(|| crate::comfy_print!("Test_03"));
+			assert!(captured == [(OutputKind::Stderr, "O: Test_03".to_string())] || captured == [(OutputKind::Stdout, "Test_03".to_string())], "{captured:?}");
+		}
+	}
+
+	stop.store(true, std::sync::atomic::Ordering::Relaxed);
+	writer.join().unwrap();
+	test_utils::get_queue().clear();
+}
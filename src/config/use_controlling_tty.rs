@@ -0,0 +1,44 @@
+//! Determines whether [tty messages](crate::message::Message::to_tty()) are written to the controlling terminal, 
+//! e.g. to prompt the user even when [std::io::stdout] is piped.
+//! - The terminal is `/dev/tty` on unix and `CONOUT$` on Windows.
+//! - If disabled, or if the terminal can't be opened (e.g. the process has none), they're written to [std::io::stderr].
+//! - Other messages are not affected.
+//!
+//! # Default: **false**
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Current value of [USE_CONTROLLING_TTY](self).
+static CURRENT: AtomicBool = AtomicBool::new(false);
+
+/// Environment variable name for global config [USE_CONTROLLING_TTY](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_USE_CONTROLLING_TTY";
+
+/// Get global config [USE_CONTROLLING_TTY](self).
+pub fn get() -> bool { return CURRENT.load(Ordering::Relaxed); }
+
+/// Set global config [USE_CONTROLLING_TTY](self).
+pub fn set(new_value: bool) { CURRENT.store(new_value, Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::{Message, OutputKind};
+	let _guard = test_utils::lock_tests();
+	
+	{
+		std::env::set_var(ENV_NAME, "true");
+		super::env_vars::load_all();
+		assert_eq!(get(), true);
+		
+		std::env::set_var(ENV_NAME, "false");
+		super::env_vars::load_all();
+		assert_eq!(get(), false);
+	}
+	
+	{
+		crate::async_impl::comfy_print_async(Message::to_tty_ln("Test_01"));
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, "Test_01\n".to_string())]);
+	}
+}
@@ -0,0 +1,64 @@
+//! Maximum number of characters per line when writing to [std::io::stdout] | [std::io::stderr].
+//! - Newlines are inserted at word boundaries, words longer than the width are broken.
+//! - Messages written to disk are not wrapped.
+//! - **0** disables wrapping.
+//! 
+//! # Default: **0** (None)
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current value of [WRAP_WIDTH](self), 0 means None.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Environment variable name for global config [WRAP_WIDTH](self).
+pub const ENV_NAME: &str = "COMFY_PRINT_WRAP_WIDTH";
+
+/// Get global config [WRAP_WIDTH](self).
+pub fn get() -> Option<usize> {
+	return match CURRENT.load(Ordering::Relaxed) {
+		0 => None,
+		width => Some(width),
+	};
+}
+
+/// Set global config [WRAP_WIDTH](self). `Some(0)` is the same as `None`.
+pub fn set(new_value: Option<usize>) { CURRENT.store(new_value.unwrap_or(0), Ordering::Relaxed); }
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::render;
+	use crate::message::Message;
+	let _guard = test_utils::lock_tests();
+
+	{
+		std::env::set_var(ENV_NAME, "20");
+		super::env_vars::load_all();
+		assert_eq!(get(), Some(20));
+
+		std::env::set_var(ENV_NAME, "0");
+		super::env_vars::load_all();
+		assert_eq!(get(), None);
+	}
+
+	let msg = Message::standard_ln("The quick brown fox jumps over the lazy dog\nsupercalifragilisticexpialidocious end");
+
+	{
+		set(None);
+		assert_eq!(render::for_stream(&msg, false).unwrap(), msg.to_string());
+	}
+
+	{
+		set(Some(20));
+		let rendered = render::for_stream(&msg, false).unwrap();
+		assert_eq!(rendered, "The quick brown fox\njumps over the lazy\ndog\nsupercalifragilistic\nexpialidocious end\n");
+		assert!(rendered.lines().all(|line| line.chars().count() <= 20));
+
+		// Disk output is not wrapped.
+		assert_eq!(msg.to_string(), "The quick brown fox jumps over the lazy dog\nsupercalifragilisticexpialidocious end\n");
+	}
+
+	{
+		assert_eq!(render::wrap("  indented words", 10), "  indented\nwords");
+	}
+}
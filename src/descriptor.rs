@@ -0,0 +1,88 @@
+//! Writing to a file descriptor (unix) or handle (Windows) inherited by the process, 
+//! e.g. fd 3 for a log channel, see [Message::to_fd()](crate::message::Message::to_fd()).
+//! 
+//! The descriptor is borrowed, never closed. An invalid or closed descriptor fails like any other write, so the message is queued and retried.
+
+use std::io::Write;
+use std::mem::ManuallyDrop;
+
+/// Where a message is written instead of its stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Descriptor {
+	#[cfg(unix)]
+	Fd(std::os::unix::io::RawFd),
+	/// The address of a `RawHandle`, which isn't [Send].
+	#[cfg(windows)]
+	Handle(usize),
+}
+
+/// Writes `rendered` to `descriptor`, without closing it.
+pub(crate) fn write(descriptor: Descriptor, rendered: &str, should_flush: bool) -> std::io::Result<()> {
+	let mut file = ManuallyDrop::new(open(descriptor)?);
+	file.write_all(rendered.as_bytes())?;
+	
+	if should_flush {
+		file.flush()?;
+	}
+	
+	return Ok(());
+}
+
+/// A [File](std::fs::File) borrowing `descriptor`, must not be dropped.
+fn open(descriptor: Descriptor) -> std::io::Result<std::fs::File> {
+	match descriptor {
+		#[cfg(unix)]
+		Descriptor::Fd(fd) => {
+			use std::os::unix::io::FromRawFd;
+			
+			if fd < 0 {
+				return Err(std::io::Error::from_raw_os_error(libc::EBADF));
+			}
+			
+			// SAFETY: The file is never dropped, so the descriptor is never closed. Writing to an invalid descriptor only fails.
+			return Ok(unsafe { std::fs::File::from_raw_fd(fd) });
+		}
+		#[cfg(windows)]
+		Descriptor::Handle(handle) => {
+			use std::os::windows::io::FromRawHandle;
+			
+			// SAFETY: The file is never dropped, so the handle is never closed. Writing to an invalid handle only fails.
+			return Ok(unsafe { std::fs::File::from_raw_handle(handle as std::os::windows::io::RawHandle) });
+		}
+	}
+}
+
+#[cfg(unix)]
+#[test]
+fn test() {
+	use std::io::Read;
+	use std::os::unix::io::AsRawFd;
+	use crate::test_utils;
+	use crate::config;
+	use crate::message::Message;
+	let _guard = test_utils::lock_tests();
+	config::allow_logging_print_failures::set(false);
+	
+	{
+		let (mut reader, writer) = std::io::pipe().unwrap();
+		crate::async_impl::comfy_print_async(Message::to_fd_ln(writer.as_raw_fd(), "Test_01"));
+		crate::async_impl::comfy_print_async(Message::to_fd(writer.as_raw_fd(), "Test_02"));
+		test_utils::yield_until_idle();
+		drop(writer);
+		
+		let mut received = String::new();
+		reader.read_to_string(&mut received).unwrap();
+		assert_eq!(received, "Test_01\nTest_02");
+		assert!(test_utils::take_written().is_empty());
+	}
+	
+	{
+		config::max_retries::set(2);
+		crate::async_impl::comfy_print_async(Message::to_fd_ln(-1, "Test_03"));
+		test_utils::yield_until_idle();
+		
+		// Kept in the queue after MAX_RETRIES, like any other failure.
+		assert_eq!(test_utils::get_queue().iter().map(|msg| msg.str().to_string()).collect::<Vec<_>>(), ["Test_03"]);
+		assert!(test_utils::take_written().is_empty());
+	}
+}
@@ -0,0 +1,122 @@
+//! Histogram of the time between creating a message and writing it to its stream, see [latency_percentiles()](crate::async_impl::latency_percentiles()).
+//! 
+//! Log-linear buckets, in the style of HDR histograms: values below [SUB_BUCKETS] microseconds are exact, 
+//! larger values are grouped by power of two, each group split into [SUB_BUCKETS] buckets (at most ~6% relative error).
+//! Recording is lock-free.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKET_COUNT: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+static BUCKETS: [AtomicU64; BUCKET_COUNT] = [const { AtomicU64::new(0) }; BUCKET_COUNT];
+static MAX_MICROS: AtomicU64 = AtomicU64::new(0);
+
+fn bucket_index(micros: u64) -> usize {
+	if micros < SUB_BUCKETS as u64 {
+		return micros as usize;
+	}
+	
+	let magnitude = 63 - micros.leading_zeros();
+	let shift = magnitude - SUB_BUCKET_BITS;
+	let sub_bucket = (micros >> shift) as usize & (SUB_BUCKETS - 1);
+	return (shift as usize + 1) * SUB_BUCKETS + sub_bucket;
+}
+
+/// The smallest value that falls in the bucket at `index`, inverse of [bucket_index()].
+fn bucket_lower_bound(index: usize) -> u64 {
+	let group = index / SUB_BUCKETS;
+	let sub_bucket = (index % SUB_BUCKETS) as u64;
+	
+	if group == 0 {
+		return sub_bucket;
+	}
+	
+	let shift = group as u32 - 1;
+	return (1 << (shift + SUB_BUCKET_BITS)) | (sub_bucket << shift);
+}
+
+pub(crate) fn record(latency: Duration) {
+	let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+	BUCKETS[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+	MAX_MICROS.fetch_max(micros, Ordering::Relaxed);
+}
+
+/// How many latencies were recorded.
+#[cfg(test)]
+pub(crate) fn count() -> u64 {
+	return BUCKETS.iter().map(|bucket| bucket.load(Ordering::Relaxed)).sum();
+}
+
+/// `(p50, p99, max)` in microseconds, percentiles are rounded down to their bucket and never exceed `max`. 
+/// 
+/// All zeros if nothing was recorded.
+pub(crate) fn percentiles() -> (u64, u64, u64) {
+	let counts = BUCKETS.each_ref().map(|bucket| bucket.load(Ordering::Relaxed));
+	let max = MAX_MICROS.load(Ordering::Relaxed);
+	let total: u64 = counts.iter().sum();
+	
+	if total == 0 {
+		return (0, 0, 0);
+	}
+	
+	let percentile = |ratio: f64| {
+		let rank = ((total as f64 * ratio).ceil() as u64).max(1);
+		let mut seen = 0;
+		
+		for (index, count) in counts.iter().enumerate() {
+			seen += count;
+			if seen >= rank {
+				return bucket_lower_bound(index).min(max);
+			}
+		}
+		
+		return max;
+	};
+	
+	return (percentile(0.50), percentile(0.99), max);
+}
+
+#[cfg(test)]
+pub(crate) fn reset() {
+	for bucket in BUCKETS.iter() {
+		bucket.store(0, Ordering::Relaxed);
+	}
+	
+	MAX_MICROS.store(0, Ordering::Relaxed);
+}
+
+#[test]
+fn test() {
+	use crate::test_utils;
+	use crate::message::Message;
+	let _guard = test_utils::lock_tests();
+
+	{
+		for micros in [0, 15, 16, 17, 100, 1_000_000, u64::MAX] {
+			let index = bucket_index(micros);
+			assert!(bucket_lower_bound(index) <= micros);
+			assert!(index + 1 == BUCKET_COUNT || bucket_lower_bound(index + 1) > micros);
+		}
+	}
+
+	{
+		for index in 0..200 {
+			crate::comfy_println!("Test_{index:03}");
+		}
+
+		let slow_msg = Message::standard_ln("Test_slow");
+		std::thread::sleep(Duration::from_millis(2));
+		crate::async_impl::enqueue_many(vec![slow_msg]);
+		test_utils::yield_until_idle();
+
+		assert_eq!(count(), 201);
+
+		let (p50, p99, max) = crate::async_impl::latency_percentiles();
+		assert!(p50 <= p99);
+		assert!(p99 <= max);
+		assert!(max >= 2000);
+	}
+}
@@ -1,11 +1,32 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// Explicit returns, explicit boolean comparisons and tab indentation are deliberate in this codebase.
+#![allow(clippy::needless_return, clippy::bool_comparison, clippy::tabs_in_doc_comments, clippy::bool_assert_comparison)]
 
+extern crate alloc;
+
+pub mod message_core;
+#[cfg(feature = "std")]
 pub mod async_impl;
+#[cfg(feature = "std")]
 pub mod message;
+#[cfg(feature = "std")]
 pub mod config;
+#[cfg(feature = "std")]
 mod macros;
+#[cfg(feature = "std")]
 mod printing_state;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "std")]
+mod win_console;
+#[cfg(feature = "std")]
+mod tty;
+#[cfg(feature = "std")]
+mod descriptor;
+#[cfg(feature = "latency-metrics")]
+mod latency;
 
 
-#[cfg(test)] pub(crate) mod test_utils;
\ No newline at end of file
+#[cfg(all(test, feature = "std"))] pub(crate) mod test_utils;
\ No newline at end of file
@@ -16,6 +16,10 @@
 /// 
 /// Instead, it will store the failed message in a queue and attempt to print it later.
 /// 
+/// Panics raised while formatting the arguments are caught, see [`try_format`](crate::message::try_format).
+/// 
+/// The location of the call is attached to the message, see [SHOW_LOCATION](crate::config::show_location).
+/// 
 /// ---
 /// # Performance
 /// 
@@ -50,7 +54,7 @@
 #[macro_export]
 macro_rules! comfy_print {
     ($($arg:tt)*) => {{
-        $crate::async_impl::comfy_print_async($crate::message::Message::standard(std::format!($($arg)*)))
+        $crate::async_impl::comfy_print_async($crate::message::try_format(|| $crate::message::Message::standard(std::format!($($arg)*)).with_location(std::concat!(std::file!(), ":", std::line!()))))
     }};
 }
 
@@ -70,6 +74,10 @@ macro_rules! comfy_print {
 /// Unlike the standard library's [`println!`](std::println) macro, this macro will not panic if writing to stdout fails. 
 ///
 /// Instead, it will store the failed message in a queue and attempt to print it later.
+/// 
+/// Panics raised while formatting the arguments are caught, see [`try_format`](crate::message::try_format).
+/// 
+/// The location of the call is attached to the message, see [SHOW_LOCATION](crate::config::show_location).
 ///
 /// ---
 /// 
@@ -102,7 +110,7 @@ macro_rules! comfy_println {
         $crate::async_impl::comfy_print_async($crate::message::Message::standard_ln(""))
     };
     ($($arg:tt)*) => {{ 
-		$crate::async_impl::comfy_print_async($crate::message::Message::standard_ln(std::format!($($arg)*))) 
+		$crate::async_impl::comfy_print_async($crate::message::try_format(|| $crate::message::Message::standard_ln(std::format!($($arg)*)).with_location(std::concat!(std::file!(), ":", std::line!())))) 
 	}};
 }
 
@@ -121,6 +129,10 @@ macro_rules! comfy_println {
 /// Unlike the standard library's [`eprint!`](std::eprint) macro, this macro will not panic if writing to stderr fails. 
 ///
 /// Instead, it will store the failed error message in a queue and attempt to print it later.
+/// 
+/// Panics raised while formatting the arguments are caught, see [`try_format`](crate::message::try_format).
+/// 
+/// The location of the call is attached to the message, see [SHOW_LOCATION](crate::config::show_location).
 ///
 /// ---
 /// 
@@ -146,7 +158,7 @@ macro_rules! comfy_println {
 #[macro_export]
 macro_rules! comfy_eprint {
 	($($arg:tt)*) => {{
-		$crate::async_impl::comfy_print_async($crate::message::Message::error(std::format!($($arg)*)))
+		$crate::async_impl::comfy_print_async($crate::message::try_format(|| $crate::message::Message::error(std::format!($($arg)*)).with_location(std::concat!(std::file!(), ":", std::line!()))))
 	}};
 }
 
@@ -166,6 +178,10 @@ macro_rules! comfy_eprint {
 /// Unlike the standard library's [`eprintln!`](std::eprintln) macro, this macro will not panic if writing to stderr fails. 
 ///
 /// Instead, it will store the failed error message in a queue and attempt to print it later.
+/// 
+/// Panics raised while formatting the arguments are caught, see [`try_format`](crate::message::try_format).
+/// 
+/// The location of the call is attached to the message, see [SHOW_LOCATION](crate::config::show_location).
 ///
 /// ---
 ///
@@ -194,6 +210,34 @@ macro_rules! comfy_eprintln {
 		$crate::async_impl::comfy_print_async($crate::message::Message::error_ln(""))
 	};
 	($($arg:tt)*) => {{
-		$crate::async_impl::comfy_print_async($crate::message::Message::error_ln(std::format!($($arg)*)))
+		$crate::async_impl::comfy_print_async($crate::message::try_format(|| $crate::message::Message::error_ln(std::format!($($arg)*)).with_location(std::concat!(std::file!(), ":", std::line!()))))
 	}};
-}
\ No newline at end of file
+}
+/// # Prints the error of a [Result] to the error output, with a newline, then evaluates to [None].
+/// 
+/// ---
+/// 
+/// - Evaluates to `Some(value)` if the result is [Ok], without printing anything.
+/// - Same as [`log_err()`](crate::async_impl::log_err), but the location of the call is attached to the message, see [SHOW_LOCATION](crate::config::show_location).
+/// - The error is printed like [`comfy_eprintln!`](crate::comfy_eprintln), through its [Display](std::fmt::Display) implementation.
+/// 
+/// # Examples
+/// 
+/// ```
+/// use comfy_print::comfy_log_err;
+/// 
+/// let parsed: Option<u32> = comfy_log_err!("not a number".parse::<u32>()); // prints "invalid digit found in string"
+/// assert_eq!(parsed, None);
+/// ```
+#[macro_export]
+macro_rules! comfy_log_err {
+	($result:expr) => {
+		match $result {
+			std::result::Result::Ok(value) => std::option::Option::Some(value),
+			std::result::Result::Err(err) => {
+				$crate::async_impl::comfy_print_async($crate::message::try_format(|| $crate::message::Message::error_ln(std::string::ToString::to_string(&err)).with_location(std::concat!(std::file!(), ":", std::line!()))));
+				std::option::Option::None
+			}
+		}
+	};
+}
@@ -1,18 +1,31 @@
 //! # Message
 //! [comfy_print](crate)'s data type for storing messages that failed to be printed.
 
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
+use std::panic::AssertUnwindSafe;
+use std::str::FromStr;
+use crate::config;
+use crate::descriptor::Descriptor;
+use crate::config::on_queue_printing_fail::On_QueuePrintingFail;
+use crate::message_core::CoreMessage;
 
-/// Which stream to write to.
-/// - [Stdout](OutputKind::Stdout) write to [std::io::stdout()](std::io::stdout())
-/// - [Stderr](OutputKind::Stderr) write to [std::io::stderr()](std::io::stderr())
+pub use crate::message_core::OutputKind;
+
+/// Callback invoked once a [Message] leaves [comfy_print](crate), see [Message::with_on_complete()].
+pub type OnComplete = Box<dyn FnOnce(std::io::Result<()>) + Send>;
+
+/// How a message is placed within its [width](Message::with_width()).
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum OutputKind {
-	/// Write to [std::io::stdout()](std::io::stdout())
-	Stdout,
-	/// Write to [std::io::stderr()](std::io::stderr())
-	Stderr,
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum Alignment {
+	/// Pad with spaces on the right.
+	Left,
+	/// Pad with spaces on the left.
+	Right,
+	/// Pad with spaces on both sides, the extra space (if any) goes on the right.
+	Center,
 }
 
 /// Structure for storing messages that failed to be printed.
@@ -20,6 +33,38 @@ pub struct Message {
 	string: String,
 	output: OutputKind,
 	should_append_line: bool,
+	indent: Option<usize>,
+	/// Levels of [scope()](crate::async_impl::scope()) this message was created in.
+	scope_depth: usize,
+	fail_policy: Option<On_QueuePrintingFail>,
+	is_progress: bool,
+	is_diagnostic: bool,
+	/// See [Message::is_verbatim()].
+	is_verbatim: bool,
+	/// See [Message::to_tty()].
+	is_tty: bool,
+	/// See [Message::to_fd()].
+	descriptor: Option<Descriptor>,
+	/// See [Message::with_verbosity()].
+	verbosity: u8,
+	dedup_key: Option<u64>,
+	/// Assigned by [enqueue_seq()](crate::async_impl::enqueue_seq()).
+	seq: Option<u64>,
+	location: Option<&'static str>,
+	/// See [Message::with_category()].
+	category: Option<&'static str>,
+	/// Sink chosen by [ROUTES](crate::config::routes), if any.
+	route: Option<String>,
+	/// See [Message::with_width()].
+	width: Option<(usize, Alignment)>,
+	on_complete: Option<OnComplete>,
+	/// When this message was created, see [latency_percentiles()](crate::async_impl::latency_percentiles()).
+	#[cfg(feature = "latency-metrics")]
+	created_at: std::time::Instant,
+	/// See [Message::escalate_after()].
+	escalate_at: Option<std::time::Instant>,
+	/// Whether this message reached its stream, or the disk through [WriteToDisk](crate::config::on_max_retries_reached::On_MaxRetriesReached::WriteToDisk).
+	was_written: bool,
 }
 
 impl Message {
@@ -27,49 +72,598 @@ impl Message {
 		return self.string.deref();
 	}
 	
+	/// The full text of this message as written to disk: unlike [Message::str()], it includes the indentation, location prefix and trailing newline.
+	/// 
+	/// Borrows [Message::str()] when there's nothing to add.
+	pub fn rendered(&self) -> Cow<'_, str> {
+		let has_indent = self.total_indent() > 0;
+		let has_location = self.location.is_some() && config::show_location::get();
+		
+		if self.should_append_line || has_indent || has_location {
+			return Cow::Owned(self.to_string());
+		} else {
+			return Cow::Borrowed(self.str());
+		}
+	}
+	
 	pub fn output_kind(&self) -> OutputKind {
 		return self.output;
 	}
+	
+	/// Whether this is a progress line, see [Message::progress()].
+	pub fn is_progress(&self) -> bool {
+		return self.is_progress;
+	}
+	
+	/// Whether this message was generated by [comfy_print](crate) to describe a failure, see [ALLOW_LOGGING_PRINT_FAILURES](crate::config::allow_logging_print_failures).
+	/// 
+	/// Failing to print a diagnostic never generates further diagnostics.
+	pub fn is_diagnostic(&self) -> bool {
+		return self.is_diagnostic;
+	}
+	
+	/// Whether this message was already rendered elsewhere, and is written as is, see [forward_rendered()](crate::async_impl::forward_rendered()).
+	pub fn is_verbatim(&self) -> bool {
+		return self.is_verbatim;
+	}
+	
+	/// Whether this message is meant for the controlling terminal, see [Message::to_tty()].
+	pub fn is_tty(&self) -> bool {
+		return self.is_tty;
+	}
+	
+	/// The descriptor this message is written to instead of its stream, if any, see [Message::to_fd()].
+	pub(crate) fn descriptor(&self) -> Option<Descriptor> {
+		return self.descriptor;
+	}
+	
+	/// How verbose this message is, see [Message::with_verbosity()].
+	pub fn verbosity(&self) -> u8 {
+		return self.verbosity;
+	}
+	
+	/// The policy overriding [ON_QUEUE_PRINTING_FAIL](crate::config::on_queue_printing_fail) for this message, if any.
+	pub fn fail_policy(&self) -> Option<On_QueuePrintingFail> {
+		return self.fail_policy;
+	}
+	
+	/// The key identifying repeats of this message, if any, see [Message::with_dedup_key()].
+	pub fn dedup_key(&self) -> Option<u64> {
+		return self.dedup_key;
+	}
 
 	pub fn standard(print_me: impl Into<String>) -> Self {
-		return Self {
-			string: print_me.into(),
-			output: OutputKind::Stdout,
-			should_append_line: false,
-		};
+		return Self::new(print_me.into(), OutputKind::Stdout, false);
 	}
 	
 	pub fn standard_ln(print_me: impl Into<String>) -> Self {
-		return Self {
-			string: print_me.into(),
-			output: OutputKind::Stdout,
-			should_append_line: true,
-		};
+		return Self::new(print_me.into(), OutputKind::Stdout, true);
 	}
 	
 	pub fn error(print_me: impl Into<String>) -> Self {
-		return Self {
-			string: print_me.into(),
-			output: OutputKind::Stderr,
-			should_append_line: false,
-		};
+		return Self::new(print_me.into(), OutputKind::Stderr, false);
 	}
 
 	pub fn error_ln(print_me: impl Into<String>) -> Self {
+		return Self::new(print_me.into(), OutputKind::Stderr, true);
+	}
+	
+	/// A message for the controlling terminal, even if [std::io::stdout] and [std::io::stderr] are redirected, e.g. a prompt.
+	/// 
+	/// Targets [std::io::stderr] unless [USE_CONTROLLING_TTY](crate::config::use_controlling_tty) is enabled and the terminal can be opened.
+	pub fn to_tty(print_me: impl Into<String>) -> Self {
+		let mut msg = Self::new(print_me.into(), OutputKind::Stderr, false);
+		msg.is_tty = true;
+		return msg;
+	}
+	
+	/// Same as [Message::to_tty()], followed by a newline.
+	pub fn to_tty_ln(print_me: impl Into<String>) -> Self {
+		let mut msg = Self::new(print_me.into(), OutputKind::Stderr, true);
+		msg.is_tty = true;
+		return msg;
+	}
+	
+	/// A message for the file descriptor `fd` instead of a stream, e.g. fd 3 inherited as a log channel.
+	/// 
+	/// - The descriptor is never closed. If it's invalid or closed, writing fails and the message is queued and retried, like any other failure.
+	/// - Otherwise handled like a message to [std::io::stderr], e.g. by [STDERR_VERBOSITY](crate::config::stderr_verbosity).
+	#[cfg(unix)]
+	pub fn to_fd(fd: std::os::unix::io::RawFd, print_me: impl Into<String>) -> Self {
+		let mut msg = Self::new(print_me.into(), OutputKind::Stderr, false);
+		msg.descriptor = Some(Descriptor::Fd(fd));
+		return msg;
+	}
+	
+	/// Same as [Message::to_fd()], followed by a newline.
+	#[cfg(unix)]
+	pub fn to_fd_ln(fd: std::os::unix::io::RawFd, print_me: impl Into<String>) -> Self {
+		let mut msg = Self::new(print_me.into(), OutputKind::Stderr, true);
+		msg.descriptor = Some(Descriptor::Fd(fd));
+		return msg;
+	}
+	
+	/// A message for `handle` instead of a stream, e.g. a pipe inherited from the parent process, see [Message::to_fd()].
+	#[cfg(windows)]
+	pub fn to_handle(handle: std::os::windows::io::RawHandle, print_me: impl Into<String>) -> Self {
+		let mut msg = Self::new(print_me.into(), OutputKind::Stderr, false);
+		msg.descriptor = Some(Descriptor::Handle(handle as usize));
+		return msg;
+	}
+	
+	/// Same as [Message::to_handle()], followed by a newline.
+	#[cfg(windows)]
+	pub fn to_handle_ln(handle: std::os::windows::io::RawHandle, print_me: impl Into<String>) -> Self {
+		let mut msg = Self::new(print_me.into(), OutputKind::Stderr, true);
+		msg.descriptor = Some(Descriptor::Handle(handle as usize));
+		return msg;
+	}
+	
+	/// A progress line for [std::io::stdout]: written after a carriage return (`\r`) and without a newline, so that each progress line overwrites the previous one.
+	/// 
+	/// The next regular message will be preceded by a newline, see [print_progress()](crate::async_impl::print_progress()).
+	pub fn progress(progress: impl AsRef<str>) -> Self {
+		let mut msg = Self::new(format!("\r{}", progress.as_ref()), OutputKind::Stdout, false);
+		msg.is_progress = true;
+		return msg;
+	}
+	
+	/// A diagnostic describing a failure, targeting [std::io::stderr].
+	pub(crate) fn diagnostic(description: impl Into<String>) -> Self {
+		let mut msg = Self::new(description.into(), OutputKind::Stderr, true);
+		msg.is_diagnostic = true;
+		msg.scope_depth = 0;
+		return msg;
+	}
+	
+	/// Text that was already rendered, written as is: without indentation, location, trailing newline or stream-only configs.
+	pub(crate) fn verbatim(rendered: String, output: OutputKind) -> Self {
+		let mut msg = Self::new(rendered, output, false);
+		msg.indent = Some(0);
+		msg.scope_depth = 0;
+		msg.is_verbatim = true;
+		return msg;
+	}
+	
+	fn new(string: String, output: OutputKind, should_append_line: bool) -> Self {
 		return Self {
-			string: print_me.into(),
-			output: OutputKind::Stderr,
-			should_append_line: true,
+			string,
+			output,
+			should_append_line,
+			indent: None,
+			scope_depth: crate::async_impl::scope_depth(),
+			fail_policy: None,
+			is_progress: false,
+			is_diagnostic: false,
+			is_verbatim: false,
+			is_tty: false,
+			descriptor: None,
+			verbosity: 0,
+			dedup_key: None,
+			seq: None,
+			location: None,
+			category: None,
+			route: None,
+			width: None,
+			on_complete: None,
+			#[cfg(feature = "latency-metrics")]
+			created_at: std::time::Instant::now(),
+			escalate_at: None,
+			was_written: false,
+		};
+	}
+
+	/// Replace the text of this message, keeping everything else, e.g. from a [middleware](crate::config::middleware).
+	pub fn with_str(mut self, string: impl Into<String>) -> Self {
+		self.string = string.into();
+		return self;
+	}
+	
+	/// Indent each line of this message by `spaces`, overriding global config [INDENT](crate::config::indent).
+	pub fn with_indent(mut self, spaces: usize) -> Self {
+		self.indent = Some(spaces);
+		return self;
+	}
+	
+	/// Set how verbose this message is, 0 by default. 
+	/// 
+	/// Messages to [std::io::stderr] more verbose than [STDERR_VERBOSITY](crate::config::stderr_verbosity) are discarded.
+	pub fn with_verbosity(mut self, level: u8) -> Self {
+		self.verbosity = level;
+		return self;
+	}
+	
+	/// Decide what happens when printing this message from the queue fails, overriding global config [ON_QUEUE_PRINTING_FAIL](crate::config::on_queue_printing_fail).
+	pub fn with_fail_policy(mut self, policy: On_QueuePrintingFail) -> Self {
+		self.fail_policy = Some(policy);
+		return self;
+	}
+	
+	/// Pad or truncate each line of this message to exactly `width` characters when written to its stream, placed according to `alignment`.
+	/// 
+	/// - Lines longer than `width` are cut, ending with an ellipsis (`…`).
+	/// - The trailing newline isn't part of the last line.
+	/// - Messages written to disk are left as is.
+	pub fn with_width(mut self, width: usize, alignment: Alignment) -> Self {
+		self.width = Some((width, alignment));
+		return self;
+	}
+	
+	/// The column width and alignment of this message, if any, see [Message::with_width()].
+	pub fn width(&self) -> Option<(usize, Alignment)> {
+		return self.width;
+	}
+	
+	/// Spaces before each line: [Message::with_indent()] or global config [INDENT](crate::config::indent), plus the [scope()](crate::async_impl::scope()) levels.
+	fn total_indent(&self) -> usize {
+		let indent = self.indent.unwrap_or_else(config::indent::get);
+		return indent.saturating_add(self.scope_depth.saturating_mul(crate::async_impl::SCOPE_INDENT));
+	}
+	
+	/// Where this message was printed from, see [Message::with_location()].
+	pub fn location(&self) -> Option<&'static str> {
+		return self.location;
+	}
+	
+	/// Tag this message with `category`, e.g. `"telemetry"`, capping how many of its messages are queued, see [CATEGORY_CAPS](crate::config::category_caps).
+	pub fn with_category(mut self, category: &'static str) -> Self {
+		self.category = Some(category);
+		return self;
+	}
+	
+	/// The category of this message, if any, see [Message::with_category()].
+	pub fn category(&self) -> Option<&'static str> {
+		return self.category;
+	}
+	
+	/// The sink this message was routed to, see [ROUTES](crate::config::routes).
+	pub fn route(&self) -> Option<&str> {
+		return self.route.as_deref();
+	}
+	
+	pub(crate) fn set_route(&mut self, route: Option<String>) {
+		self.route = route;
+	}
+	
+	pub(crate) fn set_seq(&mut self, seq: u64) {
+		self.seq = Some(seq);
+	}
+	
+	/// A copy of this message for [subscribe()](crate::async_impl::subscribe()), 
+	/// without its sequence number or completion callback, which belong to the original.
+	#[cfg(feature = "subscribe")]
+	pub(crate) fn duplicate(&self) -> Self {
+		let mut copy = Self::new(self.string.clone(), self.output, self.should_append_line);
+		copy.indent = self.indent;
+		copy.scope_depth = self.scope_depth;
+		copy.fail_policy = self.fail_policy;
+		copy.is_progress = self.is_progress;
+		copy.is_diagnostic = self.is_diagnostic;
+		copy.is_verbatim = self.is_verbatim;
+		copy.is_tty = self.is_tty;
+		copy.descriptor = self.descriptor;
+		copy.verbosity = self.verbosity;
+		copy.dedup_key = self.dedup_key;
+		copy.location = self.location;
+		copy.category = self.category;
+		copy.width = self.width;
+		copy.route = self.route.clone();
+		copy.escalate_at = self.escalate_at;
+		#[cfg(feature = "latency-metrics")]
+		{ copy.created_at = self.created_at; }
+		copy.was_written = self.was_written;
+		return copy;
+	}
+	
+	/// Bytes allocated for the text of this message, including spare capacity, see [queue_memory_bytes()](crate::async_impl::queue_memory_bytes()).
+	pub(crate) fn string_capacity(&self) -> usize {
+		return self.string.capacity();
+	}
+	
+	pub(crate) fn set_written(&mut self) {
+		self.was_written = true;
+	}
+	
+	/// Invoke `callback` once this message leaves [comfy_print](crate), without blocking the caller:
+	/// 
+	/// - With `Ok(())` once written to its stream (or to disk by [WriteToDisk](crate::config::on_max_retries_reached::On_MaxRetriesReached::WriteToDisk)), 
+	///   including after failing and being retried from the queue.
+	/// - With an error once permanently dropped instead, e.g. [queue full](crate::config::on_queue_full) or [persisted](crate::async_impl::persist_queue()).
+	/// 
+	/// The callback runs on whichever thread the message left on, after releasing [comfy_print](crate)'s locks, so it may print.
+	pub fn with_on_complete(mut self, callback: impl FnOnce(std::io::Result<()>) + Send + 'static) -> Self {
+		self.on_complete = Some(Box::new(callback));
+		return self;
+	}
+	
+	/// If this message is still in the queue `duration` from now, e.g. because the streams keep failing, 
+	/// the drain writes it synchronously instead: flushed right away and retried up to [ESCALATION_RETRIES](crate::async_impl::ESCALATION_RETRIES) times,
+	/// before falling back to [ON_QUEUE_PRINTING_FAIL](crate::config::on_queue_printing_fail).
+	/// 
+	/// Meant for critical messages that should not wait behind a stalled queue.
+	pub fn escalate_after(mut self, duration: std::time::Duration) -> Self {
+		self.escalate_at = std::time::Instant::now().checked_add(duration);
+		return self;
+	}
+	
+	/// How long ago this message was created.
+	#[cfg(feature = "latency-metrics")]
+	pub(crate) fn age(&self) -> std::time::Duration {
+		return self.created_at.elapsed();
+	}
+	
+	/// Whether this message waited longer than [Message::escalate_after()].
+	pub(crate) fn is_overdue(&self) -> bool {
+		return self.escalate_at.is_some_and(|escalate_at| std::time::Instant::now() >= escalate_at);
+	}
+	
+	/// Attach where this message was printed from (e.g. `concat!(file!(), ":", line!())`), shown if [SHOW_LOCATION](crate::config::show_location) is enabled.
+	/// 
+	/// [comfy_print](crate)'s macros attach it automatically.
+	pub fn with_location(mut self, location: &'static str) -> Self {
+		self.location = Some(location);
+		return self;
+	}
+	
+	/// Suppress this message if another message with the same `key` was printed recently, even if their text differs.
+	/// 
+	/// See [DEDUP_WINDOW](crate::async_impl::DEDUP_WINDOW) and [suppressed_count()](crate::async_impl::suppressed_count()).
+	pub fn with_dedup_key(mut self, key: u64) -> Self {
+		self.dedup_key = Some(key);
+		return self;
+	}
+}
+
+impl Message {
+	/// Line format used by [persist_queue()](crate::async_impl::persist_queue()): `{output_kind} {should_append_line} {escaped_string}`.
+	/// 
+	/// Only the text, stream and newline flag are kept, per-message overrides are lost.
+	pub(crate) fn to_persisted_line(&self) -> String {
+		let mut line = format!("{} {} ", self.output as u8, self.should_append_line as u8);
+		
+		for char in self.string.chars() {
+			match char {
+				'\\' => line.push_str("\\\\"),
+				'\n' => line.push_str("\\n"),
+				'\r' => line.push_str("\\r"),
+				_ => line.push(char),
+			}
+		}
+		
+		return line;
+	}
+	
+	/// Inverse of [Message::to_persisted_line()], [None] if `line` is malformed.
+	pub(crate) fn from_persisted_line(line: &str) -> Option<Self> {
+		let mut parts = line.splitn(3, ' ');
+		let output = OutputKind::from_str(parts.next()?).ok()?;
+		let should_append_line = match parts.next()? {
+			"0" => false,
+			"1" => true,
+			_ => return None,
 		};
+		
+		let mut string = String::new();
+		let mut chars = parts.next()?.chars();
+		
+		while let Some(char) = chars.next() {
+			if char != '\\' {
+				string.push(char);
+				continue;
+			}
+			
+			match chars.next()? {
+				'\\' => string.push('\\'),
+				'n' => string.push('\n'),
+				'r' => string.push('\r'),
+				_ => return None,
+			}
+		}
+		
+		return Some(Self::new(string, output, should_append_line));
+	}
+}
+
+/// Keeps the text, stream, newline flag and indentation of `core`, which overrides global config [INDENT](crate::config::indent).
+impl From<CoreMessage> for Message {
+	fn from(core: CoreMessage) -> Self {
+		let msg = Self::new(core.str().to_string(), core.output_kind(), core.should_append_line());
+		return msg.with_indent(core.indent());
+	}
+}
+
+/// A message leaves [comfy_print](crate) when dropped: it was either written, discarded or persisted.
+impl Drop for Message {
+	fn drop(&mut self) {
+		if let Some(seq) = self.seq {
+			crate::async_impl::complete_seq(seq);
+		}
+		
+		if let Some(callback) = self.on_complete.take() {
+			let result = 
+				if self.was_written {
+					Ok(())
+				} else {
+					Err(std::io::Error::other("`comfy_print::message::Message`: The message was dropped before being written."))
+				};
+			
+			crate::async_impl::defer_completion(callback, result);
+		}
 	}
 }
 
 impl Display for Message {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-		if self.should_append_line {
-			return write!(f, "{}\n", self.string.deref());
-		} else {
-			return write!(f, "{}", self.string.deref());
+		let indent = self.total_indent();
+		let location = self.location.filter(|_| config::show_location::get());
+		
+		crate::message_core::write_lines(f, self.str(), indent, location)?;
+		
+		let already_ends_line = config::collapse_trailing_newline::get() && self.string.ends_with('\n');
+		if self.should_append_line && already_ends_line == false {
+			f.write_str("\n")?;
 		}
+		
+		return Ok(());
+	}
+}
+
+/// Builds a message through `build_msg`, catching any panic raised while formatting it (e.g. by a user [Display] impl).
+/// 
+/// All of [comfy_print](crate)'s macros format their arguments through this function.
+/// 
+/// # Returns
+/// 
+/// * The message built by `build_msg`, if it didn't panic.
+/// * A diagnostic message targeting [Stderr](OutputKind::Stderr) describing the panic, otherwise.
+/// * An empty message instead of the diagnostic, if [ALLOW_LOGGING_PRINT_FAILURES](config::allow_logging_print_failures) is false 
+///   or feature **no-diagnostics** is enabled.
+/// 
+/// # Limitations
+/// 
+/// - Only unwinding panics can be caught, if your program is compiled with `panic = "abort"` the process will still abort.
+/// - The [panic hook](std::panic::set_hook) still runs before the panic is caught, the default hook will report the panic to stderr.
+pub fn try_format(build_msg: impl FnOnce() -> Message) -> Message {
+	let payload = match std::panic::catch_unwind(AssertUnwindSafe(build_msg)) {
+		Ok(msg) => return msg,
+		Err(payload) => payload,
+	};
+	
+	if cfg!(feature = "no-diagnostics") || config::allow_logging_print_failures::get() == false {
+		return Message::verbatim(String::new(), OutputKind::Stderr);
 	}
-}
\ No newline at end of file
+	
+	let reason = panic_payload_str(payload.as_ref());
+	return Message::diagnostic(format!("`comfy_print::message::try_format()`: Formatting the message panicked.\nError: {reason}."));
+}
+
+/// The message of a panic, if it was raised with a string.
+pub(crate) fn panic_payload_str(payload: &(dyn std::any::Any + Send)) -> &str {
+	return if let Some(str) = payload.downcast_ref::<&str>() {
+		str
+	} else if let Some(string) = payload.downcast_ref::<String>() {
+		string.as_str()
+	} else {
+		"unknown panic payload"
+	};
+}
+
+#[test]
+#[cfg(not(feature = "no-diagnostics"))]
+fn test_try_format() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	struct PanickingDisplay;
+
+	impl Display for PanickingDisplay {
+		fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+			panic!("PanickingDisplay::fmt()");
+		}
+	}
+
+	{
+		let msg = try_format(|| Message::standard_ln("Test_01"));
+		assert_eq!(msg.output_kind(), OutputKind::Stdout);
+		assert_eq!(msg.str(), "Test_01");
+	}
+
+	{
+		let msg = try_format(|| Message::standard_ln(format!("{}", PanickingDisplay)));
+		assert_eq!(msg.output_kind(), OutputKind::Stderr);
+		assert!(msg.str().contains("PanickingDisplay::fmt()"));
+	}
+
+	{
+		let result = std::panic::catch_unwind(|| {
+			crate::comfy_println!("Test_02 {}", PanickingDisplay);
+			crate::comfy_eprint!("Test_03 {}", PanickingDisplay);
+		});
+
+		assert!(result.is_ok());
+
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::get_queue().len(), 0);
+	}
+
+	{
+		config::allow_logging_print_failures::set(false);
+		let msg = try_format(|| Message::standard_ln(format!("{}", PanickingDisplay)));
+		assert_eq!(msg.str(), "");
+		assert_eq!(msg.rendered(), "");
+	}
+}
+
+#[test]
+#[cfg(feature = "no-diagnostics")]
+fn test_try_format() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	struct PanickingDisplay;
+
+	impl Display for PanickingDisplay {
+		fn fmt(&self, _f: &mut Formatter<'_>) -> std::fmt::Result {
+			panic!("PanickingDisplay::fmt()");
+		}
+	}
+
+	{
+		let msg = try_format(|| Message::standard_ln("Test_01"));
+		assert_eq!(msg.str(), "Test_01");
+	}
+
+	{
+		config::allow_logging_print_failures::set(true);
+		let msg = try_format(|| Message::standard_ln(format!("{}", PanickingDisplay)));
+		assert_eq!(msg.str(), "");
+		assert_eq!(msg.rendered(), "");
+	}
+}
+
+#[test]
+fn test_rendered() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	{
+		let msg = Message::standard_ln("Test_01");
+		assert_eq!(msg.str(), "Test_01");
+		assert_eq!(msg.rendered(), "Test_01\n");
+	}
+
+	{
+		let msg = Message::error("Test_02");
+		assert!(matches!(msg.rendered(), Cow::Borrowed("Test_02")));
+		
+		let msg = msg.with_indent(2);
+		assert_eq!(msg.str(), "Test_02");
+		assert_eq!(msg.rendered(), "  Test_02");
+	}
+}
+
+#[test]
+fn test_with_width() {
+	use crate::test_utils;
+	let _guard = test_utils::lock_tests();
+
+	let cases = [
+		(Alignment::Left, "abc       \n", "abcdefghi…\n"),
+		(Alignment::Right, "       abc\n", "abcdefghi…\n"),
+		(Alignment::Center, "   abc    \n", "abcdefghi…\n"),
+	];
+
+	for (alignment, short, long) in cases {
+		let msg = Message::standard_ln("abc").with_width(10, alignment);
+		assert_eq!(crate::render::for_stream(&msg, false).unwrap(), short);
+		assert_eq!(msg.rendered(), "abc\n");
+
+		let msg = Message::standard_ln("abcdefghijklmnop").with_width(10, alignment);
+		assert_eq!(crate::render::for_stream(&msg, false).unwrap(), long);
+	}
+
+	{
+		let msg = Message::standard("abcdefghij\nab").with_width(4, Alignment::Right);
+		assert_eq!(crate::render::for_stream(&msg, false).unwrap(), "abc…\n  ab");
+	}
+}
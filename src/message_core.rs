@@ -0,0 +1,158 @@
+//! # Message core
+//! The parts of [Message](crate::message::Message) that don't need the standard library, for `no_std` targets that provide their own write backend.
+//! - Only requires `alloc`, available without feature **std**.
+//! - [CoreMessage] has no global configs, completion callbacks or queue: its [Display] is fully determined by its fields.
+//! - With feature **std**, a [CoreMessage] converts into a [Message](crate::message::Message), which shares the same rendering.
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt::{Display, Formatter, Write};
+use core::str::FromStr;
+
+/// Which stream to write to.
+/// - [Stdout](OutputKind::Stdout) write to the standard output, e.g. `std::io::stdout()`
+/// - [Stderr](OutputKind::Stderr) write to the standard error, e.g. `std::io::stderr()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutputKind {
+	/// Write to the standard output, e.g. `std::io::stdout()`
+	Stdout,
+	/// Write to the standard error, e.g. `std::io::stderr()`
+	Stderr,
+}
+
+impl FromStr for OutputKind {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"0" | "Stdout" => Ok(OutputKind::Stdout),
+			"1" | "Stderr" => Ok(OutputKind::Stderr),
+			_ => Err(format!("Invalid string value for OutputKind: {}", s)),
+		}
+	}
+}
+
+/// A message that can be built and rendered without the standard library, see [the module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreMessage {
+	string: String,
+	output: OutputKind,
+	should_append_line: bool,
+	indent: usize,
+}
+
+impl CoreMessage {
+	pub fn str(&self) -> &str {
+		return self.string.as_str();
+	}
+
+	pub fn output_kind(&self) -> OutputKind {
+		return self.output;
+	}
+
+	/// Whether a newline is written after the text.
+	pub fn should_append_line(&self) -> bool {
+		return self.should_append_line;
+	}
+
+	/// How many spaces each line is indented by.
+	pub fn indent(&self) -> usize {
+		return self.indent;
+	}
+
+	pub fn standard(print_me: impl Into<String>) -> Self {
+		return Self::new(print_me.into(), OutputKind::Stdout, false);
+	}
+
+	pub fn standard_ln(print_me: impl Into<String>) -> Self {
+		return Self::new(print_me.into(), OutputKind::Stdout, true);
+	}
+
+	pub fn error(print_me: impl Into<String>) -> Self {
+		return Self::new(print_me.into(), OutputKind::Stderr, false);
+	}
+
+	pub fn error_ln(print_me: impl Into<String>) -> Self {
+		return Self::new(print_me.into(), OutputKind::Stderr, true);
+	}
+
+	fn new(string: String, output: OutputKind, should_append_line: bool) -> Self {
+		return Self { string, output, should_append_line, indent: 0 };
+	}
+
+	/// Indent each line of this message by `spaces`.
+	pub fn with_indent(mut self, spaces: usize) -> Self {
+		self.indent = spaces;
+		return self;
+	}
+
+	/// Writes the rendered text of this message to `writer`, the same text as its [Display].
+	pub fn write_to(&self, writer: &mut impl Write) -> core::fmt::Result {
+		write_lines(writer, self.str(), self.indent, None)?;
+
+		if self.should_append_line {
+			writer.write_str("\n")?;
+		}
+
+		return Ok(());
+	}
+}
+
+impl Display for CoreMessage {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		return self.write_to(f);
+	}
+}
+
+/// Writes `string` with each line indented by `indent` spaces, prefixed by `[location] `, if any.
+///
+/// Empty lines are left as is, so that a trailing newline doesn't turn into trailing whitespace.
+pub(crate) fn write_lines(writer: &mut impl Write, string: &str, indent: usize, location: Option<&str>) -> core::fmt::Result {
+	if indent == 0 {
+		if let Some(location) = location {
+			write!(writer, "[{location}] ")?;
+		}
+
+		return writer.write_str(string);
+	}
+
+	for (index, line) in string.split('\n').enumerate() {
+		if index > 0 {
+			writer.write_str("\n")?;
+		}
+
+		if line.is_empty() == false || (index == 0 && location.is_some()) {
+			write!(writer, "{:indent$}", "")?;
+
+			if let (0, Some(location)) = (index, location) {
+				write!(writer, "[{location}] ")?;
+			}
+
+			writer.write_str(line)?;
+		}
+	}
+
+	return Ok(());
+}
+
+#[test]
+fn test() {
+	use alloc::string::ToString;
+
+	{
+		let msg = CoreMessage::error_ln("Test_01\n\nTest_02").with_indent(2);
+		assert_eq!(msg.output_kind(), OutputKind::Stderr);
+		assert_eq!(msg.to_string(), "  Test_01\n\n  Test_02\n");
+
+		let mut rendered = String::new();
+		msg.write_to(&mut rendered).unwrap();
+		assert_eq!(rendered, "  Test_01\n\n  Test_02\n");
+	}
+
+	{
+		assert_eq!(CoreMessage::standard("Test_03").to_string(), "Test_03");
+		assert_eq!(OutputKind::from_str("1"), Ok(OutputKind::Stderr));
+		assert!(OutputKind::from_str("2").is_err());
+	}
+}
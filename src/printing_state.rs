@@ -1,9 +1,18 @@
+use std::ops::{Deref, DerefMut};
 use std::thread::JoinHandle;
+use parking_lot::RawFairMutex;
+use parking_lot::lock_api::MutexGuard;
+use crate::config;
 
 pub(crate) enum PrintingState {
 	Idle,
 	Threaded(JoinHandle<()>),
+	/// Spawned by [warmup()](crate::async_impl::warmup), or lingering for [PRINTER_IDLE_TIMEOUT](crate::config::printer_idle_timeout), 
+	/// waiting to be unparked.
+	Parked(JoinHandle<()>),
 	Synchronous,
+	/// Handed to [DRAIN_SPAWNER](crate::config::drain_spawner), until the task finishes.
+	Spawned,
 }
 
 impl PrintingState {
@@ -11,7 +20,65 @@ impl PrintingState {
 		return match self {
 			Self::Idle => false,
 			Self::Threaded(handle) => handle.is_finished() == false,
+			Self::Parked(_) => false,
 			Self::Synchronous => true,
+			Self::Spawned => true,
 		};
 	}
+
+	/// The name of this state, as passed to [ON_STATE_CHANGE](crate::config::on_state_change), see [state_name()](crate::async_impl::state_name).
+	pub(crate) fn name(&self) -> &'static str {
+		return match self {
+			Self::Idle => "Idle",
+			Self::Threaded(_) => "Threaded",
+			Self::Parked(_) => "Parked",
+			Self::Synchronous => "Synchronous",
+			Self::Spawned => "Spawned",
+		};
+	}
+}
+
+/// A lock on [STATE](crate::async_impl::STATE) that, once released, 
+/// invokes [ON_STATE_CHANGE](crate::config::on_state_change) if the state's [name](PrintingState::name()) changed meanwhile.
+/// 
+/// The callback is invoked after unlocking [STATE](crate::async_impl::STATE), so that it may print.
+pub(crate) struct StateGuard {
+	guard: Option<MutexGuard<'static, RawFairMutex, PrintingState>>,
+	from: &'static str,
+}
+
+impl StateGuard {
+	pub(crate) fn new(guard: MutexGuard<'static, RawFairMutex, PrintingState>) -> Self {
+		let from = guard.name();
+		return Self { guard: Some(guard), from };
+	}
+}
+
+impl Deref for StateGuard {
+	type Target = PrintingState;
+
+	fn deref(&self) -> &Self::Target {
+		return self.guard.as_ref().expect("only taken on drop");
+	}
+}
+
+impl DerefMut for StateGuard {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		return self.guard.as_mut().expect("only taken on drop");
+	}
+}
+
+impl Drop for StateGuard {
+	/// WARNING: Will unlock [STATE](crate::async_impl::STATE), then may lock [ON_STATE_CHANGE](crate::config::on_state_change) for reading.
+	fn drop(&mut self) {
+		let Some(guard) = self.guard.take()
+				else { return; };
+		
+		let to = guard.name();
+		drop(guard);
+		
+		if to != self.from {
+			config::on_state_change::notify(self.from, to);
+		}
+	}
 }
\ No newline at end of file
@@ -0,0 +1,152 @@
+//! Rendering applied only when writing messages to [std::io::stdout] | [std::io::stderr].
+//! Messages written to disk are rendered through [Message::rendered()] alone.
+
+use crate::config;
+use crate::message::{Alignment, Message, OutputKind};
+
+/// Renders `msg` as it should be written to its stream, applying every stream-only config, or through [RENDERER](config::renderer) if set.
+/// 
+/// [ON_NUL_BYTE](config::on_nul_byte) applies either way.
+/// 
+/// [Verbatim](Message::is_verbatim()) messages are returned as is.
+/// 
+/// `tag_stream` tags each line with the stream of `msg`, see [ANNOTATE_STREAM](config::annotate_stream). 
+/// It's decided by the caller, along with the other configs read for the same write.
+pub(crate) fn for_stream(msg: &Message, tag_stream: bool) -> std::io::Result<String> {
+	if msg.is_verbatim() {
+		return Ok(msg.str().to_string());
+	}
+	
+	if let Some(result) = config::renderer::render(msg) {
+		return result.map(config::on_nul_byte::apply);
+	}
+	
+	let mut rendered = msg.rendered().into_owned();
+	
+	if let Some(width) = config::wrap_width::get() {
+		rendered = wrap(rendered.as_str(), width);
+	}
+	
+	if let Some((width, alignment)) = msg.width() {
+		rendered = fit(rendered.as_str(), width, alignment);
+	}
+	
+	if tag_stream {
+		let tag = match msg.output_kind() {
+			OutputKind::Stdout => "O: ",
+			OutputKind::Stderr => "E: ",
+		};
+		
+		rendered = annotate(rendered.as_str(), tag);
+	}
+	
+	return Ok(config::on_nul_byte::apply(rendered));
+}
+
+/// Inserts newlines at word boundaries so that no line is longer than `width` characters.
+/// - Existing newlines are preserved.
+/// - Words longer than `width` are broken wherever they reach `width`.
+pub(crate) fn wrap(text: &str, width: usize) -> String {
+	let mut wrapped = String::with_capacity(text.len());
+
+	for (line_index, line) in text.split('\n').enumerate() {
+		if line_index > 0 {
+			wrapped.push('\n');
+		}
+
+		let mut line_len = 0;
+
+		for (word_index, word) in line.split(' ').enumerate() {
+			let word_len = word.chars().count();
+
+			if word_index > 0 {
+				if line_len > 0 && line_len + 1 + word_len > width {
+					wrapped.push('\n');
+					line_len = 0;
+				} else {
+					wrapped.push(' ');
+					line_len += 1;
+				}
+			}
+
+			for char in word.chars() {
+				if line_len >= width {
+					wrapped.push('\n');
+					line_len = 0;
+				}
+
+				wrapped.push(char);
+				line_len += 1;
+			}
+		}
+	}
+
+	return wrapped;
+}
+
+/// Pads or truncates each line to exactly `width` characters, see [Message::with_width()].
+/// - A trailing newline is kept as is, and so is any leading carriage return of [progress](Message::progress()) lines.
+/// - Lines longer than `width` keep their first `width - 1` characters, followed by an ellipsis.
+pub(crate) fn fit(text: &str, width: usize, alignment: Alignment) -> String {
+	let (body, trailing_newline) = match text.strip_suffix('\n') {
+		Some(body) => (body, "\n"),
+		None => (text, ""),
+	};
+
+	let mut fitted = String::with_capacity(body.len().max(width) + 1);
+
+	for (line_index, line) in body.split('\n').enumerate() {
+		if line_index > 0 {
+			fitted.push('\n');
+		}
+
+		let content = line.trim_start_matches('\r');
+		fitted.push_str(&line[..line.len() - content.len()]);
+
+		let content_len = content.chars().count();
+		if content_len > width {
+			if width > 0 {
+				fitted.extend(content.chars().take(width - 1));
+				fitted.push('…');
+			}
+
+			continue;
+		}
+
+		let padding = width - content_len;
+		let (left, right) = match alignment {
+			Alignment::Left => (0, padding),
+			Alignment::Right => (padding, 0),
+			Alignment::Center => (padding / 2, padding - padding / 2),
+		};
+
+		fitted.extend(std::iter::repeat_n(' ', left));
+		fitted.push_str(content);
+		fitted.extend(std::iter::repeat_n(' ', right));
+	}
+
+	fitted.push_str(trailing_newline);
+	return fitted;
+}
+
+/// Prefixes each non-empty line with `tag`, after any leading carriage return so that [progress](Message::progress()) lines keep overwriting each other.
+pub(crate) fn annotate(text: &str, tag: &str) -> String {
+	let mut annotated = String::with_capacity(text.len());
+
+	for (line_index, line) in text.split('\n').enumerate() {
+		if line_index > 0 {
+			annotated.push('\n');
+		}
+
+		if line.is_empty() {
+			continue;
+		}
+
+		let content = line.trim_start_matches('\r');
+		annotated.push_str(&line[..line.len() - content.len()]);
+		annotated.push_str(tag);
+		annotated.push_str(content);
+	}
+
+	return annotated;
+}
@@ -2,7 +2,8 @@ use std::sync::atomic::Ordering;
 use parking_lot::lock_api::MutexGuard;
 use parking_lot::RawFairMutex;
 use crate::async_impl;
-use crate::message::Message;
+use crate::config;
+use crate::message::{Message, OutputKind};
 
 /*/// This is for testing only, there's no unsafe code in the crate.
 pub(crate) fn break_stdout() {
@@ -14,6 +15,175 @@ pub(crate) fn break_stdout() {
 	}
 }*/
 
+/// Tests share the global queue, state, configs and environment variables, so they must not run concurrently.
+static TEST_LOCK: parking_lot::Mutex<()> = parking_lot::Mutex::new(());
+
+/// Acquires the global test lock, then resets every global to its default value.
+///
+/// Hold the returned guard until the end of the test.
+pub(crate) fn lock_tests() -> parking_lot::MutexGuard<'static, ()> {
+	let guard = TEST_LOCK.lock();
+	reset_globals();
+	return guard;
+}
+
+pub(crate) fn reset_globals() {
+	set_toggle_write_fail(false);
+	async_impl::tests::FORCE_WRITE_FAIL.store(false, Ordering::Relaxed);
+	async_impl::tests::FORCE_STREAMS_SHARE_FILE.store(false, Ordering::Relaxed);
+	async_impl::tests::FAIL_NEXT_WRITES.store(0, Ordering::Relaxed);
+	async_impl::tests::WOULD_BLOCK_NEXT_WRITES.store(0, Ordering::Relaxed);
+	async_impl::tests::FAIL_NEXT_SPAWNS.store(0, Ordering::Relaxed);
+	#[cfg(not(feature = "single-thread"))]
+	async_impl::SPAWN_RETRY_PENDING.store(false, Ordering::Relaxed);
+	yield_until_idle();
+	get_queue().clear();
+	async_impl::resume();
+
+	for env_name in [
+		config::max_queue_length::ENV_NAME,
+		config::max_retries::ENV_NAME,
+		config::allow_logging_print_failures::ENV_NAME,
+		config::on_queue_full::ENV_NAME,
+		config::on_max_retries_reached::ENV_NAME,
+		config::on_queue_printing_fail::ENV_NAME,
+		config::log_io_path::ENV_NAME,
+		config::indent::ENV_NAME,
+		config::wrap_width::ENV_NAME,
+		config::track_stderr_printed::ENV_NAME,
+		config::combined_stream::ENV_NAME,
+		config::flush_strategy::ENV_NAME,
+		config::batch_preamble::ENV_NAME,
+		config::batch_postamble::ENV_NAME,
+		config::persist_queue_path::ENV_NAME,
+		config::emit_order::ENV_NAME,
+		config::annotate_stream::ENV_NAME,
+		config::always_async::ENV_NAME,
+		config::log_max_age::ENV_NAME,
+		config::always_log_to_disk::ENV_NAME,
+		config::collapse_trailing_newline::ENV_NAME,
+		config::queue_warn_threshold::ENV_NAME,
+		config::show_location::ENV_NAME,
+		config::serialize_startup::ENV_NAME,
+		config::buffer_policy::ENV_NAME,
+		config::printer_idle_timeout::ENV_NAME,
+		config::on_nul_byte::ENV_NAME,
+		config::dry_run::ENV_NAME,
+		config::enabled::ENV_NAME,
+		#[cfg(feature = "gzip")]
+		config::log_compression::ENV_NAME,
+		config::batch_separator::ENV_NAME,
+		config::max_diagnostics_in_queue::ENV_NAME,
+		config::log_file_bom::ENV_NAME,
+		config::on_spawn_failure::ENV_NAME,
+		config::heartbeat_interval::ENV_NAME,
+		config::max_open_log_files::ENV_NAME,
+		config::stderr_verbosity::ENV_NAME,
+		config::flush_timeout::ENV_NAME,
+		config::use_controlling_tty::ENV_NAME,
+		config::on_post_shutdown::ENV_NAME,
+		config::progress_throttle::ENV_NAME,
+		config::session_banner::ENV_NAME,
+		config::global_retry_budget::ENV_NAME,
+		config::flush_every::ENV_NAME,
+		config::transcript_stderr_prefix::ENV_NAME,
+	] {
+		std::env::remove_var(env_name);
+	}
+
+	config::max_queue_length::set(1024);
+	config::max_retries::set(64);
+	config::allow_logging_print_failures::set(true);
+	config::on_queue_full::set(config::on_queue_full::On_QueueFull::KeepOldest);
+	config::on_max_retries_reached::set(config::on_max_retries_reached::On_MaxRetriesReached::Return);
+	config::on_queue_printing_fail::set(config::on_queue_printing_fail::On_QueuePrintingFail::TryUntilMaxRetries);
+	config::env_vars::clear_last_env_load_errors();
+	config::drop_channel::clear();
+	config::middleware::clear();
+	config::log_targets::clear();
+	config::log_io_path::set("").unwrap();
+	config::renderer::clear();
+	config::routes::clear();
+	config::category_caps::clear();
+	config::queue_warn_channel::clear();
+	config::on_queue_empty::clear();
+	config::on_heartbeat::clear();
+	config::on_heartbeat::on_empty();
+	config::on_state_change::clear();
+	config::drain_spawner::clear();
+	config::test_sync_drain::set(false);
+	config::indent::set(0);
+	config::wrap_width::set(None);
+	config::track_stderr_printed::set(false);
+	async_impl::STDERR_PRINTED.store(false, Ordering::Relaxed);
+	async_impl::LAST_PROGRESS_STREAM.store(0, Ordering::Relaxed);
+	async_impl::tests::WRITTEN.lock().clear();
+	async_impl::tests::FLUSHED.lock().clear();
+	async_impl::RECENT_DEDUP_KEYS.lock().clear();
+	async_impl::SUPPRESSED_COUNT.store(0, Ordering::Relaxed);
+	async_impl::DROPPED_COUNT.store(0, Ordering::Relaxed);
+	async_impl::LAST_ENQUEUE_NANOS.store(0, Ordering::Relaxed);
+	async_impl::LAST_PROGRESS_NANOS.store(0, Ordering::Relaxed);
+	async_impl::take_pending_progress();
+	async_impl::SHUTDOWN_CALLED.store(false, Ordering::Relaxed);
+	async_impl::SUPPRESSED_DIAGNOSTICS.store(0, Ordering::Relaxed);
+	async_impl::RECENT.lock().clear();
+	async_impl::dry_run_output();
+	#[cfg(feature = "subscribe")]
+	async_impl::SUBSCRIBERS.lock().clear();
+	async_impl::PENDING_COMPLETIONS.lock().clear();
+	async_impl::STDOUT_SINK.lock().take();
+	#[cfg(feature = "latency-metrics")]
+	crate::latency::reset();
+	config::combined_stream::set(None);
+	config::flush_strategy::set(config::flush_strategy::FlushStrategy::EveryMessage);
+	config::batch_preamble::set("");
+	config::batch_postamble::set("");
+	config::persist_queue_path::set("");
+	config::emit_order::set(config::emit_order::EmitOrder::Chronological);
+	config::annotate_stream::set(false);
+	config::always_async::set(false);
+	config::log_max_age::set(None);
+	config::always_log_to_disk::set(None);
+	config::collapse_trailing_newline::set(false);
+	config::queue_warn_threshold::set(None);
+	config::queue_warn_threshold::observe(0);
+	config::show_location::set(false);
+	config::serialize_startup::set(false);
+	config::serialize_startup::STARTUP_DONE.store(false, Ordering::Relaxed);
+	config::buffer_policy::set(config::buffer_policy::BufferPolicy::default());
+	config::printer_idle_timeout::set(std::time::Duration::ZERO);
+	config::on_nul_byte::set(config::on_nul_byte::On_NulByte::Pass);
+	config::dry_run::set(false);
+	config::enabled::set(true);
+	#[cfg(feature = "gzip")]
+	config::log_compression::set(config::log_compression::LogCompression::None);
+	config::batch_separator::set("");
+	config::max_diagnostics_in_queue::set(usize::MAX);
+	config::log_file_bom::set(false);
+	config::on_spawn_failure::set(config::on_spawn_failure::On_SpawnFailure::Synchronous);
+	config::heartbeat_interval::set(None);
+	config::max_open_log_files::set(16);
+	config::stderr_verbosity::set(u8::MAX);
+	config::flush_timeout::set(None);
+	config::use_controlling_tty::set(false);
+	crate::tty::PATH_OVERRIDE.lock().take();
+	config::on_post_shutdown::set(config::on_post_shutdown::On_PostShutdown::Restart);
+	config::progress_throttle::set(std::time::Duration::ZERO);
+	config::session_banner::set(None);
+	config::session_banner::FIRST_WRITE_DONE.store(false, Ordering::Relaxed);
+	config::global_retry_budget::set(None);
+	config::global_retry_budget::refill();
+	config::flush_every::set(1);
+	config::flush_every::reset();
+	config::transcript_stderr_prefix::set(config::transcript_stderr_prefix::DEFAULT);
+}
+
+/// A path named `file_name` in the temporary directory, for tests that write files. Remove the file at the end of the test.
+pub(crate) fn temp_path(file_name: &str) -> String {
+	return std::env::temp_dir().join(file_name).to_string_lossy().into_owned();
+}
+
 pub(crate) fn get_queue() -> MutexGuard<'static, RawFairMutex, Vec<Message>> {
 	return async_impl::QUEUE.lock();
 }
@@ -26,8 +196,26 @@ pub(crate) fn set_toggle_write_fail(value: bool) {
 	async_impl::tests::TOGGLE_WRITE_FAIL.store(value, Ordering::Relaxed);
 }
 
+pub(crate) fn write_attempts() -> usize {
+	return async_impl::tests::WRITE_ATTEMPTS.load(Ordering::Relaxed);
+}
+
+pub(crate) fn reset_write_attempts() {
+	async_impl::tests::WRITE_ATTEMPTS.store(0, Ordering::Relaxed);
+}
+
+/// Takes every message written to [std::io::stdout] | [std::io::stderr] since the last call, as it was rendered.
+pub(crate) fn take_written() -> Vec<(OutputKind, String)> {
+	return std::mem::take(&mut *async_impl::tests::WRITTEN.lock());
+}
+
+/// Takes the stream of every message flushed right after being written since the last call.
+pub(crate) fn take_flushed() -> Vec<OutputKind> {
+	return std::mem::take(&mut *async_impl::tests::FLUSHED.lock());
+}
+
 pub(crate) fn yield_until_idle() {
 	while async_impl::STATE.lock().is_busy() {
 		std::thread::yield_now();
 	}
-}
\ No newline at end of file
+}
@@ -0,0 +1,99 @@
+//! Writing to the controlling terminal directly, for [tty messages](crate::message::Message::to_tty()), see [USE_CONTROLLING_TTY](crate::config::use_controlling_tty).
+//! 
+//! The terminal is `/dev/tty` on unix and `CONOUT$` on Windows, which stay attached to the terminal even when [std::io::stdout] 
+//! and [std::io::stderr] are redirected. It's opened for each message, so that a terminal attached later is picked up.
+
+use std::io::Write;
+
+/// Overrides the terminal's path in tests, e.g. with a pseudo-terminal.
+#[cfg(test)]
+pub(crate) static PATH_OVERRIDE: parking_lot::Mutex<Option<String>> = parking_lot::Mutex::new(None);
+
+/// Writes `rendered` to the controlling terminal.
+/// 
+/// # Returns
+/// 
+/// * `None` if the terminal couldn't be opened, e.g. the process has none, in which case nothing was written.
+/// * `Some(result)` with the result of writing to it otherwise.
+pub(crate) fn write(rendered: &str, should_flush: bool) -> Option<std::io::Result<()>> {
+	let mut terminal = open().ok()?;
+	let result = terminal.write_all(rendered.as_bytes())
+		.and_then(|()| if should_flush { terminal.flush() } else { Ok(()) });
+	
+	return Some(result);
+}
+
+#[cfg(unix)]
+fn open() -> std::io::Result<std::fs::File> {
+	use std::os::unix::fs::OpenOptionsExt;
+	
+	#[cfg(test)]
+	if let Some(path) = PATH_OVERRIDE.lock().as_deref() {
+		return std::fs::OpenOptions::new().write(true).custom_flags(libc::O_NOCTTY).open(path);
+	}
+	
+	// Doesn't make the terminal the controlling one of a session leader that has none.
+	return std::fs::OpenOptions::new().write(true).custom_flags(libc::O_NOCTTY).open("/dev/tty");
+}
+
+#[cfg(windows)]
+fn open() -> std::io::Result<std::fs::File> {
+	#[cfg(test)]
+	if let Some(path) = PATH_OVERRIDE.lock().as_deref() {
+		return std::fs::OpenOptions::new().write(true).open(path);
+	}
+	
+	// The console's screen buffer must be opened for reading too.
+	return std::fs::OpenOptions::new().read(true).write(true).open("CONOUT$");
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open() -> std::io::Result<std::fs::File> {
+	return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "No controlling terminal on this platform."));
+}
+
+#[cfg(unix)]
+#[test]
+fn test() {
+	use std::io::Read;
+	use std::os::unix::io::FromRawFd;
+	use crate::test_utils;
+	use crate::config;
+	use crate::message::{Message, OutputKind};
+	let _guard = test_utils::lock_tests();
+	
+	let (mut master, slave_path) = unsafe {
+		let master_fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+		assert!(master_fd >= 0);
+		assert_eq!(libc::grantpt(master_fd), 0);
+		assert_eq!(libc::unlockpt(master_fd), 0);
+		
+		let name = libc::ptsname(master_fd);
+		assert!(name.is_null() == false);
+		let slave_path = std::ffi::CStr::from_ptr(name).to_str().unwrap().to_string();
+		(std::fs::File::from_raw_fd(master_fd), slave_path)
+	};
+	
+	*PATH_OVERRIDE.lock() = Some(slave_path);
+	config::use_controlling_tty::set(true);
+	
+	{
+		crate::async_impl::comfy_print_async(Message::to_tty_ln("Test_01"));
+		test_utils::yield_until_idle();
+		
+		// The terminal turns `\n` into `\r\n`.
+		let mut received = [0; 9];
+		master.read_exact(&mut received).unwrap();
+		assert_eq!(&received, b"Test_01\r\n");
+		assert!(test_utils::take_written().is_empty());
+	}
+	
+	{
+		*PATH_OVERRIDE.lock() = Some("test_tty_missing/tty".to_string());
+		crate::async_impl::comfy_print_async(Message::to_tty("Test_02"));
+		test_utils::yield_until_idle();
+		assert_eq!(test_utils::take_written(), vec![(OutputKind::Stderr, "Test_02".to_string())]);
+	}
+	
+	PATH_OVERRIDE.lock().take();
+}
@@ -0,0 +1,114 @@
+//! Rewriting [progress](crate::message::Message::progress()) lines through the Windows console API.
+//! 
+//! Legacy Windows consoles don't reliably return to column 0 on `\r`, so when [std::io::stdout] is a real console, 
+//! the current line is blanked with `FillConsoleOutputCharacterW` and the cursor moved to its start with `SetConsoleCursorPosition`.
+//! Otherwise (redirected, [swapped](crate::async_impl::swap_stdout_sink()), or not on Windows) the `\r` is written as is.
+
+/// A cell of the console's screen buffer, same layout as Windows' `COORD`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Coord {
+	pub x: i16,
+	pub y: i16,
+}
+
+/// Where to start blanking the line the cursor is on, and how many cells to blank: the whole row of a buffer `buffer_width` cells wide.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn line_span(cursor: Coord, buffer_width: i16) -> (Coord, u32) {
+	return (Coord { x: 0, y: cursor.y }, buffer_width.max(0) as u32);
+}
+
+/// The text of a progress line without its leading carriage returns, [None] if `rendered` isn't a progress line.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn strip_progress_prefix(rendered: &str) -> Option<&str> {
+	let content = rendered.trim_start_matches('\r');
+	return (content.len() < rendered.len()).then_some(content);
+}
+
+/// If `rendered` is a progress line and `stdout` is a real console, flushes `stdout`, blanks the current line and moves the cursor to its start.
+/// 
+/// Returns what's left to write: `rendered` without its leading carriage returns if the line was cleared, `rendered` otherwise.
+#[cfg(windows)]
+pub(crate) fn clear_for_progress<'a>(stdout: &mut std::io::StdoutLock, rendered: &'a str) -> &'a str {
+	use std::io::Write;
+	use std::os::windows::io::AsRawHandle;
+	
+	let Some(content) = strip_progress_prefix(rendered)
+			else { return rendered; };
+	
+	if stdout.flush().is_err() {
+		return rendered;
+	}
+	
+	let handle = stdout.as_raw_handle();
+	let mut info = ffi::ConsoleScreenBufferInfo::default();
+	
+	// SAFETY: `info` is a valid, writable `CONSOLE_SCREEN_BUFFER_INFO`, the call fails (returning 0) if `handle` isn't a console.
+	if unsafe { ffi::GetConsoleScreenBufferInfo(handle, &mut info) } == 0 {
+		return rendered;
+	}
+	
+	let (start, len) = line_span(info.cursor_position, info.size.x);
+	let mut written = 0;
+	
+	// SAFETY: `handle` is a console (checked above), `written` is a valid, writable u32.
+	let is_cleared = unsafe {
+		ffi::FillConsoleOutputCharacterW(handle, u16::from(b' '), len, start, &mut written) != 0
+			&& ffi::SetConsoleCursorPosition(handle, start) != 0
+	};
+	
+	return if is_cleared { content } else { rendered };
+}
+
+#[cfg(windows)]
+#[allow(non_snake_case)]
+mod ffi {
+	use std::os::windows::raw::HANDLE;
+	use super::Coord;
+	
+	#[repr(C)]
+	#[derive(Default)]
+	struct SmallRect {
+		left: i16,
+		top: i16,
+		right: i16,
+		bottom: i16,
+	}
+	
+	/// Same layout as Windows' `CONSOLE_SCREEN_BUFFER_INFO`.
+	#[repr(C)]
+	#[derive(Default)]
+	pub(super) struct ConsoleScreenBufferInfo {
+		pub size: Coord,
+		pub cursor_position: Coord,
+		attributes: u16,
+		window: SmallRect,
+		maximum_window_size: Coord,
+	}
+	
+	#[link(name = "kernel32")]
+	extern "system" {
+		pub(super) fn GetConsoleScreenBufferInfo(console: HANDLE, info: *mut ConsoleScreenBufferInfo) -> i32;
+		pub(super) fn FillConsoleOutputCharacterW(console: HANDLE, character: u16, length: u32, write_coord: Coord, written: *mut u32) -> i32;
+		pub(super) fn SetConsoleCursorPosition(console: HANDLE, position: Coord) -> i32;
+	}
+}
+
+#[test]
+fn test() {
+	{
+		let (start, len) = line_span(Coord { x: 17, y: 4 }, 120);
+		assert_eq!(start, Coord { x: 0, y: 4 });
+		assert_eq!(len, 120);
+
+		let (_, len) = line_span(Coord { x: 0, y: 0 }, -1);
+		assert_eq!(len, 0);
+	}
+
+	{
+		assert_eq!(strip_progress_prefix("\r50%"), Some("50%"));
+		assert_eq!(strip_progress_prefix("\r\r"), Some(""));
+		assert_eq!(strip_progress_prefix("50%\r"), None);
+		assert_eq!(strip_progress_prefix(""), None);
+	}
+}